@@ -0,0 +1,18 @@
+#import bevy_sprite::mesh2d_vertex_output::VertexOutput
+
+@group(2) @binding(0) var<uniform> color: vec4<f32>;
+// x: density threshold for the surface, y: width of the soft edge
+@group(2) @binding(1) var<uniform> params: vec4<f32>;
+@group(2) @binding(2) var density_texture: texture_2d<f32>;
+@group(2) @binding(3) var density_sampler: sampler;
+
+@fragment
+fn fragment(mesh: VertexOutput) -> @location(0) vec4<f32> {
+    let density = textureSample(density_texture, density_sampler, mesh.uv).r;
+    let threshold = params.x;
+    let coverage = smoothstep(threshold - params.y, threshold + params.y, density);
+
+    // brighten the deep interior slightly so the blob is not flat
+    let depth = clamp(density / (threshold * 4.0), 0.0, 1.0);
+    return vec4<f32>(color.rgb * (0.7 + 0.3 * depth), color.a * coverage);
+}
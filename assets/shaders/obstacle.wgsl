@@ -0,0 +1,41 @@
+#import bevy_sprite::mesh2d_vertex_output::VertexOutput
+
+@group(2) @binding(0) var<uniform> fill: vec4<f32>;
+@group(2) @binding(1) var<uniform> outline: vec4<f32>;
+// x: 0 for a circle, 1 for a box; yz: radius or half size; w: glow falloff width
+@group(2) @binding(2) var<uniform> shape: vec4<f32>;
+// xy: half size of the quad; z: 1 when the glow is on; w: outline width
+@group(2) @binding(3) var<uniform> extent: vec4<f32>;
+
+fn sdf_box(p: vec2<f32>, half_size: vec2<f32>) -> f32 {
+    let d = abs(p) - half_size;
+    return length(max(d, vec2<f32>(0.0))) + min(max(d.x, d.y), 0.0);
+}
+
+@fragment
+fn fragment(mesh: VertexOutput) -> @location(0) vec4<f32> {
+    let p = (mesh.uv - vec2<f32>(0.5)) * vec2<f32>(2.0, -2.0) * extent.xy;
+
+    var d: f32;
+    if shape.x < 0.5 {
+        d = length(p) - shape.y;
+    } else {
+        d = sdf_box(p, shape.yz);
+    }
+
+    let inside = 1.0 - smoothstep(-1.0, 0.0, d);
+    let edge = 1.0 - smoothstep(extent.w - 1.0, extent.w, abs(d));
+
+    var rgb = mix(fill.rgb, outline.rgb, edge);
+    var alpha = max(fill.a * inside, outline.a * edge);
+
+    if extent.z > 0.5 && d > 0.0 {
+        let glow = 0.5 * exp(-d / shape.w);
+        if alpha < glow {
+            rgb = mix(outline.rgb, rgb, alpha / glow);
+            alpha = glow;
+        }
+    }
+
+    return vec4<f32>(rgb, alpha);
+}
@@ -0,0 +1,12 @@
+#import bevy_sprite::mesh2d_vertex_output::VertexOutput
+
+// x: peak contribution of a single particle
+@group(2) @binding(0) var<uniform> params: vec4<f32>;
+
+@fragment
+fn fragment(mesh: VertexOutput) -> @location(0) vec4<f32> {
+    let offset = mesh.uv * 2.0 - 1.0;
+    let falloff = max(1.0 - dot(offset, offset), 0.0);
+    let weight = falloff * falloff * params.x;
+    return vec4<f32>(weight, weight, weight, weight);
+}
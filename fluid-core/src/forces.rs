@@ -0,0 +1,121 @@
+//! The SPH sums for one sample particle over its neighbours. Particles are
+//! indices into slices in a fixed order, and `neighbours` lists the indices
+//! the sample interacts with, so callers can filter them however they like.
+
+use crate::kernels::{smoothing_kernel, smoothing_kernel_dx, Dimension, SphVector};
+
+/// The density at `sample` from `neighbours`, which should include the
+/// sample itself.
+pub fn density<V: SphVector>(
+  positions: &[V],
+  neighbours: impl IntoIterator<Item = usize>,
+  sample: usize,
+  radius: f32,
+  mass: f32,
+) -> f32 {
+  let mut density: f32 = 0.0;
+  let sample_position = positions[sample];
+
+  for i in neighbours {
+    let dist = positions[i].distance(sample_position);
+    let influence = smoothing_kernel(V::DIMENSION, radius, dist);
+
+    density += mass * influence;
+  }
+
+  density
+}
+
+/// The equation of state: pressure grows linearly with how far `density`
+/// is above `rest_density`. Both it and `stiffness` are 2D reference values,
+/// rescaled for `dimension`.
+pub fn pressure(density: f32, rest_density: f32, stiffness: f32, dimension: Dimension, radius: f32) -> f32 {
+  let density_err = density - dimension.target_density(rest_density, radius);
+  density_err * dimension.pressure_multiplier(stiffness, radius)
+}
+
+/// The pressure force on `sample`, from the mean of its and each
+/// neighbour's `pressure`, scaled by `pressure_scale`.
+#[allow(clippy::too_many_arguments)]
+pub fn pressure_force<V: SphVector>(
+  positions: &[V],
+  densities: &[f32],
+  pressure: impl Fn(usize) -> f32,
+  neighbours: impl IntoIterator<Item = usize>,
+  sample: usize,
+  radius: f32,
+  mass: f32,
+  pressure_scale: f32,
+) -> V {
+  let mut pressure_force = V::default();
+  let sample_position = positions[sample];
+
+  for i in neighbours {
+    if i == sample {
+      continue;
+    }
+    let dist = positions[i].distance(sample_position);
+
+    if dist > 0.0 {
+      let dir = (positions[i] - sample_position) / dist;
+      let slope = smoothing_kernel_dx(V::DIMENSION, radius, dist);
+      let density = densities[i];
+      let shared = (pressure(i) + pressure(sample)) / 2.0 * pressure_scale;
+
+      pressure_force += dir * shared * slope * mass / density;
+    }
+  }
+  pressure_force
+}
+
+/// The viscous pull on `sample` towards its neighbours' velocities.
+/// `particles` holds each particle's position and velocity.
+pub fn viscosity_force<V: SphVector>(
+  particles: &[(V, V)],
+  densities: &[f32],
+  neighbours: impl IntoIterator<Item = usize>,
+  sample: usize,
+  radius: f32,
+  mass: f32,
+) -> V {
+  let mut viscosity_force = V::default();
+  let (sample_position, sample_velocity) = particles[sample];
+
+  for i in neighbours {
+    if i == sample {
+      continue;
+    }
+    let (position, velocity) = particles[i];
+    let dist = position.distance(sample_position);
+    let influence = smoothing_kernel(V::DIMENSION, radius, dist);
+    let density = densities[i];
+
+    if density > 0.0 {
+      viscosity_force += (velocity - sample_velocity) * influence * mass / density;
+    }
+  }
+  viscosity_force
+}
+
+/// The pull on `sample` towards its neighbours, before scaling by a surface
+/// tension coefficient.
+pub fn cohesion<V: SphVector>(
+  positions: &[V],
+  densities: &[f32],
+  neighbours: impl IntoIterator<Item = usize>,
+  sample: usize,
+  radius: f32,
+  mass: f32,
+) -> V {
+  let mut cohesion = V::default();
+  let sample_position = positions[sample];
+
+  for i in neighbours {
+    let density = densities[i];
+    if i != sample && density > 0.0 {
+      let offset = positions[i] - sample_position;
+      cohesion += offset * smoothing_kernel(V::DIMENSION, radius, offset.length()) * mass / density;
+    }
+  }
+  cohesion
+}
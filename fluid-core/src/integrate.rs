@@ -0,0 +1,11 @@
+use crate::kernels::SphVector;
+
+/// One semi-implicit Euler step: `velocity` picks up `acceleration` and
+/// loses `damping` of itself per second, then `position` moves by it.
+/// Returns where the particle will be a step later, for the density pass.
+pub fn integrate<V: SphVector>(position: &mut V, velocity: &mut V, acceleration: V, damping: f32, dt: f32) -> V {
+  *velocity += acceleration * dt;
+  *velocity = *velocity * (1.0 - damping * dt).max(0.0);
+  *position += *velocity * dt;
+  *position + *velocity * dt
+}
@@ -0,0 +1,14 @@
+//! The SPH maths behind the fluid simulation, with no Bevy dependency: the
+//! smoothing kernels, the density and force sums, integration and the
+//! neighbour lists they run over. The `fluid-simulation` plugin wraps it in
+//! systems; CLIs, tests and other engines can call it directly on plain
+//! slices of `glam` vectors.
+
+pub mod forces;
+pub mod integrate;
+pub mod kernels;
+pub mod neighbours;
+pub mod ops;
+
+pub use glam::{Vec2, Vec3};
+pub use kernels::{Dimension, SphVector};
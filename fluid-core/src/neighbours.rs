@@ -0,0 +1,257 @@
+use std::collections::HashMap;
+
+use glam::Vec3;
+
+use crate::kernels::{Dimension, SphVector};
+
+/// The indices of the particles in each of `groups` groups, in order, from
+/// each particle's group, so the force sums only visit particles that share
+/// one. Particles in a group past the last are left out.
+pub fn partition(groups_of: impl IntoIterator<Item = usize>, groups: usize) -> Vec<Vec<usize>> {
+  let mut members = vec![Vec::new(); groups];
+  for (i, group) in groups_of.into_iter().enumerate() {
+    if let Some(members) = members.get_mut(group) {
+      members.push(i);
+    }
+  }
+  members
+}
+
+/// Finds the particles near a point, for the "for each neighbour of i" loops
+/// of the force sums. Backends index a set of members, indices into a slice
+/// of positions, once per step and then answer queries over them. Every
+/// backend returns exactly the members within the radius, in ascending index
+/// order, so they are interchangeable down to the bit: the sums visit the
+/// same particles in the same order whichever one is used.
+pub trait NeighborSearch<V: SphVector> {
+  /// Indexes `members` at `positions` for queries within `radius`.
+  fn build(&mut self, positions: &[V], members: &[usize], radius: f32);
+
+  /// Appends every indexed member no further than the radius from `point` to
+  /// `out`, in ascending order.
+  fn query(&self, positions: &[V], point: V, out: &mut Vec<usize>);
+}
+
+/// The available `NeighborSearch` implementations, to pick one at runtime.
+#[derive(Clone, Copy, PartialEq, Eq, Default, Debug)]
+pub enum SearchBackend {
+  /// Checks every member, which is quadratic but has nothing to go stale.
+  BruteForce,
+  /// Buckets members into cells one radius wide and checks the cells around
+  /// the query.
+  #[default]
+  UniformGrid,
+  /// Splits space where the members are, so sparse or clumped fluids don't
+  /// pay for empty cells; an octree in 3D.
+  Quadtree,
+}
+
+impl SearchBackend {
+  pub const ALL: [SearchBackend; 3] = [SearchBackend::BruteForce, SearchBackend::UniformGrid, SearchBackend::Quadtree];
+
+  pub fn label(self) -> &'static str {
+    match self {
+      SearchBackend::BruteForce => "brute-force",
+      SearchBackend::UniformGrid => "grid",
+      SearchBackend::Quadtree => "quadtree",
+    }
+  }
+
+  pub fn from_label(label: &str) -> Option<Self> {
+    SearchBackend::ALL.into_iter().find(|backend| backend.label() == label)
+  }
+
+  pub fn search<V: SphVector>(self) -> Box<dyn NeighborSearch<V>> {
+    match self {
+      SearchBackend::BruteForce => Box::<BruteForce>::default(),
+      SearchBackend::UniformGrid => Box::<UniformGrid>::default(),
+      SearchBackend::Quadtree => Box::<Quadtree>::default(),
+    }
+  }
+}
+
+#[derive(Default)]
+pub struct BruteForce {
+  members: Vec<usize>,
+  radius: f32,
+}
+
+impl<V: SphVector> NeighborSearch<V> for BruteForce {
+  fn build(&mut self, _positions: &[V], members: &[usize], radius: f32) {
+    self.members.clear();
+    self.members.extend_from_slice(members);
+    self.members.sort_unstable();
+    self.radius = radius;
+  }
+
+  fn query(&self, positions: &[V], point: V, out: &mut Vec<usize>) {
+    out.extend(self.members.iter().copied().filter(|&i| positions[i].distance(point) <= self.radius));
+  }
+}
+
+#[derive(Default)]
+pub struct UniformGrid {
+  cells: HashMap<[i32; 3], Vec<usize>>,
+  radius: f32,
+}
+
+impl UniformGrid {
+  fn cell(&self, point: Vec3) -> [i32; 3] {
+    let cell = (point / self.radius.max(f32::EPSILON)).floor();
+    [cell.x as i32, cell.y as i32, cell.z as i32]
+  }
+
+  /// Appends the members inside the box from `min` to `max`, in ascending
+  /// order, visiting only the cells it overlaps, or every occupied cell if
+  /// that is fewer.
+  pub fn query_box<V: SphVector>(&self, positions: &[V], min: V, max: V, out: &mut Vec<usize>) {
+    let start = out.len();
+    let (min, max) = (min.to_vec3(), max.to_vec3());
+    let inside = |i: &usize| {
+      let position = positions[*i].to_vec3();
+      position.cmpge(min).all() && position.cmple(max).all()
+    };
+    let (low, high) = (self.cell(min), self.cell(max));
+    let spans = [0, 1, 2].map(|axis| (high[axis] as i64 - low[axis] as i64 + 1).max(0) as u64);
+    if spans.iter().product::<u64>() > self.cells.len() as u64 {
+      out.extend(self.cells.values().flatten().copied().filter(inside));
+    } else {
+      for z in low[2]..=high[2] {
+        for y in low[1]..=high[1] {
+          for x in low[0]..=high[0] {
+            if let Some(cell) = self.cells.get(&[x, y, z]) {
+              out.extend(cell.iter().copied().filter(inside));
+            }
+          }
+        }
+      }
+    }
+    out[start..].sort_unstable();
+  }
+}
+
+impl<V: SphVector> NeighborSearch<V> for UniformGrid {
+  fn build(&mut self, positions: &[V], members: &[usize], radius: f32) {
+    self.cells.clear();
+    self.radius = radius;
+    for &i in members {
+      let cell = self.cell(positions[i].to_vec3());
+      self.cells.entry(cell).or_default().push(i);
+    }
+  }
+
+  fn query(&self, positions: &[V], point: V, out: &mut Vec<usize>) {
+    let start = out.len();
+    let [x, y, z] = self.cell(point.to_vec3());
+    let depth = if V::DIMENSION == Dimension::Three { -1..=1 } else { 0..=0 };
+    for dz in depth {
+      for dy in -1..=1 {
+        for dx in -1..=1 {
+          let Some(cell) = self.cells.get(&[x.saturating_add(dx), y.saturating_add(dy), z.saturating_add(dz)]) else {
+            continue;
+          };
+          out.extend(cell.iter().copied().filter(|&i| positions[i].distance(point) <= self.radius));
+        }
+      }
+    }
+    out[start..].sort_unstable();
+  }
+}
+
+// members a quadtree leaf holds before it splits
+const LEAF_CAPACITY: usize = 16;
+// deep enough for any sensible spread, and a stop for coincident particles
+const MAX_DEPTH: u32 = 16;
+
+struct Node {
+  min: Vec3,
+  max: Vec3,
+  depth: u32,
+  // index of the first of the node's children, which are contiguous
+  children: Option<usize>,
+  members: Vec<usize>,
+}
+
+#[derive(Default)]
+pub struct Quadtree {
+  nodes: Vec<Node>,
+  radius: f32,
+}
+
+impl Quadtree {
+  fn insert(&mut self, positions: &[Vec3], dimension: Dimension, mut node: usize, member: usize) {
+    while let Some(children) = self.nodes[node].children {
+      node = children + self.child_of(node, dimension, positions[member]);
+    }
+    self.nodes[node].members.push(member);
+    if self.nodes[node].members.len() > LEAF_CAPACITY && self.nodes[node].depth < MAX_DEPTH {
+      self.split(positions, dimension, node);
+    }
+  }
+
+  fn child_of(&self, node: usize, dimension: Dimension, point: Vec3) -> usize {
+    let center = (self.nodes[node].min + self.nodes[node].max) / 2.0;
+    let upper = point.cmpge(center);
+    let z = dimension == Dimension::Three && upper.z;
+    usize::from(upper.x) | usize::from(upper.y) << 1 | usize::from(z) << 2
+  }
+
+  fn split(&mut self, positions: &[Vec3], dimension: Dimension, node: usize) {
+    let (min, max) = (self.nodes[node].min, self.nodes[node].max);
+    let center = (min + max) / 2.0;
+    let count = if dimension == Dimension::Three { 8 } else { 4 };
+    let first = self.nodes.len();
+    let depth = self.nodes[node].depth + 1;
+    for child in 0..count {
+      let upper = Vec3::new((child & 1) as f32, ((child >> 1) & 1) as f32, ((child >> 2) & 1) as f32);
+      let child_min = Vec3::select(upper.cmpgt(Vec3::ZERO), center, min);
+      let child_max = Vec3::select(upper.cmpgt(Vec3::ZERO), max, center);
+      self.nodes.push(Node { min: child_min, max: child_max, depth, children: None, members: Vec::new() });
+    }
+    self.nodes[node].children = Some(first);
+    for member in std::mem::take(&mut self.nodes[node].members) {
+      self.insert(positions, dimension, node, member);
+    }
+  }
+}
+
+impl<V: SphVector> NeighborSearch<V> for Quadtree {
+  fn build(&mut self, positions: &[V], members: &[usize], radius: f32) {
+    self.nodes.clear();
+    self.radius = radius;
+    let world: Vec<Vec3> = positions.iter().map(|position| position.to_vec3()).collect();
+    let (min, max) = members
+      .iter()
+      .map(|&i| world[i])
+      .filter(|point| point.is_finite())
+      .fold((Vec3::INFINITY, Vec3::NEG_INFINITY), |(min, max), point| (min.min(point), max.max(point)));
+    if min.cmpgt(max).any() {
+      // nothing finite to index
+      return;
+    }
+    self.nodes.push(Node { min, max, depth: 0, children: None, members: Vec::new() });
+    for &i in members.iter().filter(|&&i| world[i].is_finite()) {
+      self.insert(&world, V::DIMENSION, 0, i);
+    }
+  }
+
+  fn query(&self, positions: &[V], point: V, out: &mut Vec<usize>) {
+    let start = out.len();
+    let world = point.to_vec3();
+    let mut stack = if self.nodes.is_empty() { Vec::new() } else { vec![0] };
+    while let Some(node) = stack.pop() {
+      let node = &self.nodes[node];
+      if world.clamp(node.min, node.max).distance(world) > self.radius {
+        continue;
+      }
+      match node.children {
+        Some(first) => {
+          let count = if V::DIMENSION == Dimension::Three { 8 } else { 4 };
+          stack.extend(first..first + count);
+        }
+        None => out.extend(node.members.iter().copied().filter(|&i| positions[i].distance(point) <= self.radius)),
+      }
+    }
+    out[start..].sort_unstable();
+  }
+}
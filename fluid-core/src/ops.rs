@@ -0,0 +1,13 @@
+//! The floating point functions the kernels use, from `libm` with the
+//! feature of the same name so results match on every platform, and from
+//! the standard library otherwise.
+
+#[cfg(feature = "libm")]
+pub fn powf(x: f32, y: f32) -> f32 {
+  libm::powf(x, y)
+}
+
+#[cfg(not(feature = "libm"))]
+pub fn powf(x: f32, y: f32) -> f32 {
+  x.powf(y)
+}
@@ -0,0 +1,131 @@
+//! The conformance suite every `NeighborSearch` backend has to pass: the
+//! same members as checking them all, in ascending order, in 2D and 3D,
+//! plus the grid's box queries.
+
+use fluid_core::{
+  neighbours::{NeighborSearch, SearchBackend, UniformGrid},
+  SphVector, Vec2, Vec3,
+};
+use proptest::prelude::*;
+
+/// The members within `radius` of `point`, straight from the definition.
+fn expected<V: SphVector>(positions: &[V], members: &[usize], point: V, radius: f32) -> Vec<usize> {
+  let mut expected: Vec<usize> = members.iter().copied().filter(|&i| positions[i].distance(point) <= radius).collect();
+  expected.sort_unstable();
+  expected
+}
+
+fn conforms<V: SphVector>(points: &[Vec3], members: &[usize], queries: &[Vec3], radius: f32) -> Result<(), TestCaseError> {
+  let positions: Vec<V> = points.iter().map(|point| V::from_vec3(*point)).collect();
+  for backend in SearchBackend::ALL {
+    let mut search = backend.search::<V>();
+    search.build(&positions, members, radius);
+    let samples = members.iter().map(|&i| positions[i]).chain(queries.iter().map(|query| V::from_vec3(*query)));
+    for point in samples {
+      // results are appended, so anything already there stays put
+      let mut found = vec![usize::MAX];
+      search.query(&positions, point, &mut found);
+      prop_assert_eq!(found[0], usize::MAX);
+      prop_assert_eq!(&found[1..], expected(&positions, members, point, radius), "{:?} at {:?}", backend, point);
+    }
+  }
+  Ok(())
+}
+
+fn boxes_match<V: SphVector>(points: &[Vec3], members: &[usize], corners: (Vec3, Vec3), radius: f32) -> Result<(), TestCaseError> {
+  let positions: Vec<V> = points.iter().map(|point| V::from_vec3(*point)).collect();
+  let (min, max) = (corners.0.min(corners.1), corners.0.max(corners.1));
+  let mut grid = UniformGrid::default();
+  NeighborSearch::<V>::build(&mut grid, &positions, members, radius);
+  let mut found = Vec::new();
+  grid.query_box(&positions, V::from_vec3(min), V::from_vec3(max), &mut found);
+
+  let (min, max) = (V::from_vec3(min).to_vec3(), V::from_vec3(max).to_vec3());
+  let mut expected: Vec<usize> = members
+    .iter()
+    .copied()
+    .filter(|&i| {
+      let position = positions[i].to_vec3();
+      position.cmpge(min).all() && position.cmple(max).all()
+    })
+    .collect();
+  expected.sort_unstable();
+  prop_assert_eq!(found, expected);
+  Ok(())
+}
+
+fn point(extent: f32) -> impl Strategy<Value = Vec3> {
+  (-extent..extent, -extent..extent, -extent..extent).prop_map(|(x, y, z)| Vec3::new(x, y, z))
+}
+
+/// Points with a subset of them indexed, out of order.
+fn cloud() -> impl Strategy<Value = (Vec<Vec3>, Vec<usize>)> {
+  prop::collection::vec(point(200.0), 0..120).prop_flat_map(|points| {
+    let count = points.len();
+    (Just(points), prop::collection::vec(0..count.max(1), 0..=count).prop_map(move |mut members| {
+      members.retain(|&i| i < count);
+      members.sort_unstable();
+      members.dedup();
+      members.reverse();
+      members
+    }))
+  })
+}
+
+proptest! {
+  #[test]
+  fn every_backend_finds_exactly_the_neighbours(
+    (points, members) in cloud(),
+    queries in prop::collection::vec(point(250.0), 0..8),
+    radius in 1.0f32..80.0,
+  ) {
+    conforms::<Vec2>(&points, &members, &queries, radius)?;
+    conforms::<Vec3>(&points, &members, &queries, radius)?;
+  }
+
+  /// Dense clumps split the quadtree deep and share grid cells.
+  #[test]
+  fn clumped_points_are_all_found(center in point(50.0), count in 1usize..200, radius in 0.5f32..20.0) {
+    let points: Vec<Vec3> = (0..count).map(|i| center + Vec3::splat(i as f32 * 1e-3)).collect();
+    let members: Vec<usize> = (0..count).collect();
+    conforms::<Vec2>(&points, &members, &[center], radius)?;
+    conforms::<Vec3>(&points, &members, &[center], radius)?;
+  }
+}
+
+proptest! {
+  #[test]
+  fn grid_boxes_hold_exactly_the_members_inside(
+    (points, members) in cloud(),
+    corners in (point(250.0), point(250.0)),
+    radius in 1.0f32..80.0,
+  ) {
+    boxes_match::<Vec2>(&points, &members, corners, radius)?;
+    boxes_match::<Vec3>(&points, &members, corners, radius)?;
+  }
+}
+
+#[test]
+fn coincident_and_boundary_points() {
+  let points = [Vec3::ZERO; 40].into_iter().chain([Vec3::X * 10.0, Vec3::NEG_Y * 10.0, Vec3::new(-10.0, -10.0, 0.0)]).collect::<Vec<_>>();
+  let members: Vec<usize> = (0..points.len()).collect();
+  // exactly one radius away counts, and so does the far corner of a cell
+  conforms::<Vec2>(&points, &members, &[Vec3::ZERO, Vec3::splat(-5.0)], 10.0).unwrap();
+  conforms::<Vec3>(&points, &members, &[Vec3::ZERO, Vec3::splat(-5.0)], 10.0).unwrap();
+}
+
+#[test]
+fn empty_and_non_finite_members_find_nothing() {
+  let points = [Vec3::NAN, Vec3::INFINITY, Vec3::ZERO];
+  conforms::<Vec2>(&points, &[], &[Vec3::ZERO], 10.0).unwrap();
+  conforms::<Vec2>(&points, &[0, 1], &[Vec3::ZERO], 10.0).unwrap();
+  conforms::<Vec3>(&points, &[0, 1, 2], &[Vec3::ZERO], 10.0).unwrap();
+}
+
+#[test]
+fn backends_round_trip_their_labels() {
+  for backend in SearchBackend::ALL {
+    assert_eq!(SearchBackend::from_label(backend.label()), Some(backend));
+  }
+  assert_eq!(SearchBackend::from_label("octree"), None);
+}
@@ -0,0 +1,59 @@
+//! A whole solver step written against the crate alone, the way a CLI or
+//! another engine would drive it, with no Bevy in sight.
+
+use fluid_core::{forces, integrate::integrate, neighbours, Dimension, Vec2};
+
+// the plugin's default tuning
+const RADIUS: f32 = 20.0;
+const MASS: f32 = 1.0;
+const REST_DENSITY: f32 = 0.4;
+const STIFFNESS: f32 = 6500.0;
+const HALF: Vec2 = Vec2::new(150.0, 100.0);
+const DT: f32 = 1.0 / 60.0;
+
+fn step(positions: &mut [Vec2], velocities: &mut [Vec2]) -> Vec<f32> {
+  let mut predicted = vec![Vec2::ZERO; positions.len()];
+  for i in 0..positions.len() {
+    predicted[i] = integrate(&mut positions[i], &mut velocities[i], Vec2::new(0.0, -500.0), 0.0, DT);
+    // a box the walls clamp particles back into
+    let clamped = positions[i].clamp(-HALF, HALF);
+    if clamped != positions[i] {
+      positions[i] = clamped;
+      velocities[i] *= -0.5;
+    }
+  }
+
+  let all: Vec<usize> = (0..positions.len()).collect();
+  let densities: Vec<f32> = (0..positions.len())
+    .map(|i| forces::density(&predicted, all.iter().copied(), i, RADIUS, MASS))
+    .collect();
+  let pressure = |i: usize| forces::pressure(densities[i], REST_DENSITY, STIFFNESS, Dimension::Two, RADIUS);
+  for i in 0..positions.len() {
+    let force = forces::pressure_force(&predicted, &densities, pressure, all.iter().copied(), i, RADIUS, MASS, 1.0);
+    velocities[i] += force / densities[i] * DT;
+  }
+  densities
+}
+
+#[test]
+fn a_block_of_fluid_settles_without_blowing_up() {
+  let mut positions: Vec<Vec2> = (0..100)
+    .map(|i| Vec2::new((i % 10) as f32, (i / 10) as f32) * 8.0 - Vec2::new(100.0, 60.0))
+    .collect();
+  let mut velocities = vec![Vec2::ZERO; positions.len()];
+
+  for _ in 0..240 {
+    let densities = step(&mut positions, &mut velocities);
+    assert!(densities.iter().all(|density| density.is_finite() && *density > 0.0));
+  }
+  for (position, velocity) in positions.iter().zip(&velocities) {
+    assert!(position.is_finite() && velocity.is_finite());
+    assert!(position.abs().cmple(HALF).all(), "escaped to {position}");
+  }
+}
+
+#[test]
+fn partition_keeps_query_order_and_drops_unknown_groups() {
+  let members = neighbours::partition([1, 0, 1, 5, 0], 2);
+  assert_eq!(members, vec![vec![1, 4], vec![0, 2]]);
+}
@@ -0,0 +1,142 @@
+//! Python bindings for the headless solver: a `Simulation` steps the SPH
+//! physics without a window, with the particles as numpy arrays, so runs can
+//! be driven and analysed from a notebook. Build with `maturin develop`.
+
+use std::time::Duration;
+
+use bevy::{prelude::*, time::TimeUpdateStrategy};
+use fluid::{
+  domain::{DomainId, Domains, DEFAULT_DOMAIN_SIZE},
+  solver::SimulationState,
+  Particle, SimulationConfig, SolverPlugin,
+};
+use numpy::{ndarray::Array2, IntoPyArray, PyArray1, PyArray2, PyReadonlyArray2};
+use pyo3::{exceptions::PyValueError, prelude::*, types::PyDict};
+
+/// A single 2D tank centred on the origin, stepped at a fixed `dt`.
+/// Positions and velocities are `(n, 2)` float32 arrays in world units, in
+/// the order the particles were added.
+#[pyclass(unsendable)]
+struct Simulation {
+  app: App,
+}
+
+#[pymethods]
+impl Simulation {
+  #[new]
+  #[pyo3(signature = (width = DEFAULT_DOMAIN_SIZE.x, height = DEFAULT_DOMAIN_SIZE.y, dt = 1.0 / 60.0))]
+  fn new(width: f32, height: f32, dt: f64) -> Self {
+    let mut app = App::new();
+    app
+      // the library's own solver, so the bindings step what the app does
+      .add_plugins((MinimalPlugins, SolverPlugin { domains: Domains::single(Vec2::new(width, height)), ..default() }))
+      .insert_resource(TimeUpdateStrategy::ManualDuration(Duration::from_secs_f64(dt)));
+    Simulation { app }
+  }
+
+  /// Adds a particle at each row of `positions`, starting at the matching
+  /// row of `velocities`, or at rest.
+  #[pyo3(signature = (positions, velocities = None))]
+  fn add_particles(&mut self, positions: PyReadonlyArray2<f32>, velocities: Option<PyReadonlyArray2<f32>>) -> PyResult<()> {
+    let positions = positions.as_array();
+    let velocities = velocities.as_ref().map(|velocities| velocities.as_array());
+    if positions.ncols() != 2 {
+      return Err(PyValueError::new_err("positions must have shape (n, 2)"));
+    }
+    if velocities.as_ref().is_some_and(|velocities| velocities.shape() != positions.shape()) {
+      return Err(PyValueError::new_err("velocities must have the same shape as positions"));
+    }
+
+    let mass = self.app.world().resource::<SimulationConfig>().mass;
+    for (i, row) in positions.rows().into_iter().enumerate() {
+      let position = Vec3::new(row[0], row[1], 0.0);
+      let velocity = velocities.as_ref().map_or(Vec3::ZERO, |velocities| Vec3::new(velocities[[i, 0]], velocities[[i, 1]], 0.0));
+      self.app.world_mut().spawn((
+        Particle { velocity, ..Particle::at_rest(position, mass) },
+        DomainId(0),
+        Transform::from_translation(position),
+      ));
+    }
+    Ok(())
+  }
+
+  /// Advances the simulation by `steps` fixed steps.
+  #[pyo3(signature = (steps = 1))]
+  fn step(&mut self, steps: u32) {
+    for _ in 0..steps {
+      self.app.update();
+    }
+  }
+
+  fn positions<'py>(&mut self, py: Python<'py>) -> Bound<'py, PyArray2<f32>> {
+    let rows: Vec<[f32; 2]> = self.particles().iter().map(|(particle, _)| particle.position.truncate().to_array()).collect();
+    array(rows).into_pyarray(py)
+  }
+
+  fn velocities<'py>(&mut self, py: Python<'py>) -> Bound<'py, PyArray2<f32>> {
+    let rows: Vec<[f32; 2]> = self.particles().iter().map(|(particle, _)| particle.velocity.truncate().to_array()).collect();
+    array(rows).into_pyarray(py)
+  }
+
+  /// Each particle's density from the last step, zero before the first.
+  fn densities<'py>(&mut self, py: Python<'py>) -> Bound<'py, PyArray1<f32>> {
+    let densities: Vec<f32> = self.particles().into_iter().map(|(_, density)| density).collect();
+    densities.into_pyarray(py)
+  }
+
+  /// Sets a parameter by its `SimulationConfig` field name, e.g.
+  /// `set("viscosity", 0.5)`.
+  fn set(&mut self, name: &str, value: f32) -> PyResult<()> {
+    let mut config = self.app.world_mut().resource_mut::<SimulationConfig>();
+    let field = config.field_mut(name).ok_or_else(|| unknown_parameter(name))?;
+    *field = value;
+    Ok(())
+  }
+
+  fn get(&mut self, name: &str) -> PyResult<f32> {
+    let mut config = self.app.world_mut().resource_mut::<SimulationConfig>();
+    config.field_mut(name).map(|field| *field).ok_or_else(|| unknown_parameter(name))
+  }
+
+  /// Every parameter `set` accepts, with its current value.
+  fn parameters<'py>(&mut self, py: Python<'py>) -> PyResult<Bound<'py, PyDict>> {
+    let parameters = PyDict::new(py);
+    for name in SimulationConfig::FIELDS {
+      parameters.set_item(name, self.get(name)?)?;
+    }
+    Ok(parameters)
+  }
+
+  fn __len__(&mut self) -> usize {
+    self.particles().len()
+  }
+}
+
+impl Simulation {
+  /// Every particle with its density, in the order they were added.
+  fn particles(&mut self) -> Vec<(Particle, f32)> {
+    let world = self.app.world_mut();
+    let mut query = world.query::<(Entity, &Particle)>();
+    let state = world.resource::<SimulationState>();
+    let mut particles: Vec<(Entity, Particle, f32)> = query
+      .iter(world)
+      .map(|(entity, particle)| (entity, particle.clone(), state.density_of(entity)))
+      .collect();
+    particles.sort_by_key(|(entity, ..)| *entity);
+    particles.into_iter().map(|(_, particle, density)| (particle, density)).collect()
+  }
+}
+
+fn array(rows: Vec<[f32; 2]>) -> Array2<f32> {
+  let count = rows.len();
+  Array2::from_shape_vec((count, 2), rows.into_iter().flatten().collect()).expect("two columns per row")
+}
+
+fn unknown_parameter(name: &str) -> PyErr {
+  PyValueError::new_err(format!("unknown parameter {name}, expected one of {}", SimulationConfig::FIELDS.join(", ")))
+}
+
+#[pymodule]
+fn fluid_simulation(module: &Bound<'_, PyModule>) -> PyResult<()> {
+  module.add_class::<Simulation>()
+}
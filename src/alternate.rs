@@ -0,0 +1,34 @@
+use bevy::prelude::*;
+
+use crate::SimulationConfig;
+
+const HOLD_KEY: KeyCode = KeyCode::KeyX;
+
+/// A second parameter set, swapped in while X is held so two tunings can be
+/// felt on the same fluid. It starts as the defaults; the parameters panel
+/// stores the current set into it, and edits made while holding X change it.
+#[derive(Resource, Default)]
+pub struct AlternateConfig {
+  pub config: SimulationConfig,
+  pub active: bool,
+}
+
+/// Compares against the held state rather than waiting for a release, so the
+/// set still swaps back if egui takes the keyboard mid-hold. The container
+/// isn't part of a tuning and stays put.
+pub fn hold_alternate(
+  keys: Res<ButtonInput<KeyCode>>,
+  mut alternate: ResMut<AlternateConfig>,
+  mut config: ResMut<SimulationConfig>,
+) {
+  let held = keys.pressed(HOLD_KEY);
+  if held == alternate.active {
+    return;
+  }
+
+  let alternate = &mut *alternate;
+  alternate.active = held;
+  std::mem::swap(&mut *config, &mut alternate.config);
+  std::mem::swap(&mut config.container, &mut alternate.config.container);
+  info!("parameter set {}", if held { "B" } else { "A" });
+}
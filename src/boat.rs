@@ -0,0 +1,74 @@
+use bevy::prelude::*;
+
+use crate::{
+  domain::{DomainId, Domains},
+  obstacle::{Floating, Kinematic, Obstacle, ObstacleShape},
+  paddle::steering_input,
+  SimulationConfig,
+};
+
+const HULL_HALF_SIZE: Vec2 = Vec2::new(50.0, 10.0);
+// fraction of the mass of the fluid the hull would displace when submerged
+const HULL_DENSITY: f32 = 0.5;
+const THRUST: f32 = 600.0;
+// fraction of velocity lost per second to air and water drag
+const DRAG: f32 = 0.5;
+
+/// A floating hull with horizontal thrust. It has no buoyancy of its own:
+/// it rests on the fluid only through the reaction of the particles that
+/// collide with it.
+#[derive(Component)]
+pub struct Boat {
+  pub domain: DomainId,
+}
+
+pub fn spawn_boats(commands: &mut Commands, domains: &Domains, config: &SimulationConfig) {
+  let area = 4.0 * HULL_HALF_SIZE.x * HULL_HALF_SIZE.y;
+  // particles carry their size as mass, at roughly the rest density per area
+  let mass = HULL_DENSITY * area * config.target_density * config.particle_size;
+
+  for i in 0..domains.params.len() {
+    let (center, half) = domains.bounds(i);
+    commands.spawn((
+      Boat { domain: DomainId(i) },
+      Obstacle { shape: ObstacleShape::Box { half_size: HULL_HALF_SIZE } },
+      Kinematic::default(),
+      Floating { mass },
+      Transform::from_translation((center + Vec2::Y * half.y * 0.5).extend(-1.0)),
+    ));
+  }
+}
+
+/// Applies gravity, A/D (or right stick) thrust and drag to each boat, moves
+/// it, and keeps it inside its domain. Particle impacts are added by the
+/// collision pass in `gravity`.
+pub fn update_boats(
+  keys: Res<ButtonInput<KeyCode>>,
+  gamepads: Query<&Gamepad>,
+  mut boat_query: Query<(&Boat, &mut Kinematic, &mut Transform)>,
+  domains: Res<Domains>,
+  config: Res<SimulationConfig>,
+  time: Res<Time>,
+) {
+  let dt = time.delta_secs();
+  let thrust = steering_input(&keys, &gamepads).x * THRUST;
+
+  for (boat, mut kinematic, mut transform) in &mut boat_query {
+    let acceleration = config.gravity_vector().truncate() + Vec2::X * thrust;
+    kinematic.velocity = (kinematic.velocity + acceleration * dt) * (1.0 - DRAG * dt).max(0.0);
+
+    let (center, half) = domains.bounds(boat.domain.0);
+    let limit = half - HULL_HALF_SIZE;
+    let position = transform.translation.truncate() + kinematic.velocity * dt;
+    let clamped = position.clamp(center - limit, center + limit);
+
+    // walls stop the hull dead along the axis it hit
+    if clamped.x != position.x {
+      kinematic.velocity.x = 0.0;
+    }
+    if clamped.y != position.y {
+      kinematic.velocity.y = 0.0;
+    }
+    transform.translation = clamped.extend(transform.translation.z);
+  }
+}
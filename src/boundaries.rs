@@ -0,0 +1,32 @@
+use bevy::prelude::*;
+
+use crate::{domain::ContainerShape, Particle, SimulationConfig};
+
+/// Keeps a particle two radii inside the container's wall, pushing it back
+/// along the wall normal and reflecting the outward part of its velocity.
+/// Returns the wall's outward normal and the speed the particle hit it at,
+/// if it was moving into the wall.
+pub fn detect_boundaries(
+  particle: &mut Particle,
+  shape: &ContainerShape,
+  center: Vec2,
+  half_size: Vec2,
+  config: &SimulationConfig,
+) -> Option<(Vec2, f32)> {
+  let wall = 2.0 * config.particle_size;
+  let local = particle.position.truncate() - center;
+
+  let (distance, normal) = shape.signed_distance(local, half_size);
+  let penetration = distance + wall;
+  if penetration <= 0.0 {
+    return None;
+  }
+
+  particle.position -= (normal * penetration).extend(0.0);
+  let outward = particle.velocity.truncate().dot(normal);
+  if outward <= 0.0 {
+    return None;
+  }
+  particle.velocity -= (normal * outward * (1.0 + config.collision_dampening)).extend(0.0);
+  Some((normal, outward))
+}
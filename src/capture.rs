@@ -0,0 +1,76 @@
+use std::{path::{Path, PathBuf}, process::Command};
+
+// std's clock panics in the browser
+use bevy::{prelude::*, render::view::screenshot::{save_to_disk, Screenshot}, utils::SystemTime};
+
+pub fn screenshot_hotkey(
+  mut commands: Commands,
+  keys: Res<ButtonInput<KeyCode>>,
+) {
+  if !keys.just_pressed(KeyCode::F12) {
+    return;
+  }
+
+  let timestamp = SystemTime::now()
+    .duration_since(SystemTime::UNIX_EPOCH)
+    .map(|duration| duration.as_millis())
+    .unwrap_or_default();
+  let path = format!("screenshot-{timestamp}.png");
+
+  info!("saving screenshot to {path}");
+  commands
+    .spawn(Screenshot::primary_window())
+    .observe(save_to_disk(path));
+}
+
+pub const EXPORT_FRAME_RATE: f64 = 60.0;
+// screenshots are read back a frame or two after they are requested
+const FLUSH_FRAMES: u32 = 3;
+
+/// Offline export: the app steps at a fixed dt and saves every frame as a
+/// numbered PNG, so output does not depend on how fast frames render.
+#[derive(Resource)]
+pub struct FrameExport {
+  pub dir: PathBuf,
+  pub frames: u32,
+  pub ffmpeg: bool,
+  pub frame: u32,
+}
+
+pub fn export_frames(
+  mut commands: Commands,
+  mut export: ResMut<FrameExport>,
+  mut exit: EventWriter<AppExit>,
+) {
+  if export.frame < export.frames {
+    let path = export.dir.join(format!("frame_{:05}.png", export.frame));
+    commands
+      .spawn(Screenshot::primary_window())
+      .observe(save_to_disk(path));
+  } else if export.frame == export.frames + FLUSH_FRAMES {
+    if export.ffmpeg {
+      encode_video(&export.dir);
+    }
+    exit.send(AppExit::Success);
+  }
+
+  export.frame += 1;
+}
+
+fn encode_video(dir: &Path) {
+  let output = dir.join("output.mp4");
+  let status = Command::new("ffmpeg")
+    .arg("-y")
+    .args(["-framerate", &EXPORT_FRAME_RATE.to_string()])
+    .arg("-i")
+    .arg(dir.join("frame_%05d.png"))
+    .args(["-pix_fmt", "yuv420p"])
+    .arg(&output)
+    .status();
+
+  match status {
+    Ok(status) if status.success() => info!("encoded {}", output.display()),
+    Ok(status) => warn!("ffmpeg exited with {status}"),
+    Err(err) => warn!("could not run ffmpeg: {err}"),
+  }
+}
@@ -0,0 +1,189 @@
+use std::{
+  fs, io,
+  path::{Path, PathBuf},
+};
+
+use bevy::{prelude::*, utils::SystemTime};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+  domain::DomainId,
+  drain::Drain,
+  emitter::Emitter,
+  history::SceneEdited,
+  layers::CollisionLayers,
+  material::FluidMaterial,
+  obstacle::{Kinematic, Obstacle},
+  pin::Pinned,
+  render::ParticleColor,
+  schema,
+  zone::Zone,
+  Particle, ParticleSpawn, SimulationConfig,
+};
+
+const CHECKPOINT_DIR: &str = "checkpoints";
+
+/// Saves or restores a checkpoint file, from Ctrl+S or the console.
+#[derive(Event, Clone, Debug)]
+pub enum CheckpointCommand {
+  Save(PathBuf),
+  Restore(PathBuf),
+}
+
+#[derive(Serialize, Deserialize)]
+struct SavedParticle {
+  particle: Particle,
+  domain: DomainId,
+  color: ParticleColor,
+  #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+  pinned: bool,
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  layers: Option<CollisionLayers>,
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  material: Option<FluidMaterial>,
+}
+
+/// The whole 2D simulation as a RON file: the parameters, every particle, and
+/// the obstacles, emitters, drains and zones of the scene. Code-driven
+/// obstacles like the paddle and boats belong to the scenario and are left
+/// alone.
+#[derive(Serialize, Deserialize)]
+pub struct Checkpoint {
+  config: SimulationConfig,
+  particles: Vec<SavedParticle>,
+  obstacles: Vec<(Obstacle, Transform)>,
+  emitters: Vec<Emitter>,
+  drains: Vec<Drain>,
+  zones: Vec<Zone>,
+}
+
+impl Checkpoint {
+  pub fn load(path: &Path) -> io::Result<Self> {
+    let text = fs::read_to_string(path)?;
+    schema::from_str(&text).map_err(io::Error::other)
+  }
+
+  pub fn save(&self, path: &Path) -> io::Result<()> {
+    let text = schema::to_string(self).map_err(io::Error::other)?;
+    if let Some(dir) = path.parent().filter(|dir| !dir.as_os_str().is_empty()) {
+      fs::create_dir_all(dir)?;
+    }
+    fs::write(path, text)
+  }
+}
+
+/// Ctrl+S saves to `checkpoints/checkpoint-<timestamp>.ron`.
+pub fn checkpoint_hotkey(keys: Res<ButtonInput<KeyCode>>, mut checkpoints: EventWriter<CheckpointCommand>) {
+  if keys.any_pressed([KeyCode::ControlLeft, KeyCode::ControlRight]) && keys.just_pressed(KeyCode::KeyS) {
+    let timestamp = SystemTime::now()
+      .duration_since(SystemTime::UNIX_EPOCH)
+      .map(|duration| duration.as_millis())
+      .unwrap_or_default();
+    let path = Path::new(CHECKPOINT_DIR).join(format!("checkpoint-{timestamp}.ron"));
+    checkpoints.send(CheckpointCommand::Save(path));
+  }
+}
+
+/// Restoring replaces the particles, the scene and the parameters, and
+/// starts a new undo history, like loading a scenario.
+pub fn run_checkpoints(
+  mut commands: Commands,
+  mut checkpoints: EventReader<CheckpointCommand>,
+  particle_query: Query<
+    (Entity, &Particle, &DomainId, &ParticleColor, Has<Pinned>, Option<&CollisionLayers>, Option<&FluidMaterial>),
+  >,
+  obstacle_query: Query<(Entity, &Obstacle, &Transform), Without<Kinematic>>,
+  emitter_query: Query<(Entity, &Emitter)>,
+  drain_query: Query<(Entity, &Drain)>,
+  zone_query: Query<(Entity, &Zone)>,
+  mut meshes: ResMut<Assets<Mesh>>,
+  mut materials: ResMut<Assets<ColorMaterial>>,
+  mut config: ResMut<SimulationConfig>,
+  mut edits: EventWriter<SceneEdited>,
+) {
+  for command in checkpoints.read() {
+    match command {
+      CheckpointCommand::Save(path) => {
+        let checkpoint = Checkpoint {
+          config: config.clone(),
+          particles: particle_query
+            .iter()
+            .map(|(_, particle, domain, color, pinned, layers, material)| SavedParticle {
+              particle: particle.clone(),
+              domain: *domain,
+              color: color.clone(),
+              pinned,
+              layers: layers.copied(),
+              material: material.copied(),
+            })
+            .collect(),
+          obstacles: obstacle_query.iter().map(|(_, obstacle, transform)| (*obstacle, *transform)).collect(),
+          emitters: emitter_query.iter().map(|(_, emitter)| emitter.clone()).collect(),
+          drains: drain_query.iter().map(|(_, drain)| drain.clone()).collect(),
+          zones: zone_query.iter().map(|(_, zone)| zone.clone()).collect(),
+        };
+        match checkpoint.save(path) {
+          Ok(()) => info!("saved {} particles to {}", checkpoint.particles.len(), path.display()),
+          Err(err) => warn!("could not save {}: {err}", path.display()),
+        }
+      }
+      CheckpointCommand::Restore(path) => {
+        let checkpoint = match Checkpoint::load(path) {
+          Ok(checkpoint) => checkpoint,
+          Err(err) => {
+            warn!("could not read checkpoint {}: {err}", path.display());
+            continue;
+          }
+        };
+
+        let fixtures = obstacle_query
+          .iter()
+          .map(|(entity, ..)| entity)
+          .chain(emitter_query.iter().map(|(entity, _)| entity))
+          .chain(drain_query.iter().map(|(entity, _)| entity))
+          .chain(zone_query.iter().map(|(entity, _)| entity));
+        for entity in particle_query.iter().map(|(entity, ..)| entity).chain(fixtures) {
+          commands.entity(entity).despawn_recursive();
+        }
+
+        *config = checkpoint.config;
+        for saved in &checkpoint.particles {
+          let entity = ParticleSpawn {
+            position: saved.particle.position,
+            velocity: saved.particle.velocity,
+            domain: saved.domain,
+            color: saved.color.source,
+            size: config.particle_size,
+          }.spawn(&mut commands, &mut meshes, &mut materials);
+
+          let mut particle = commands.entity(entity);
+          particle.insert((saved.particle.clone(), saved.color.clone()));
+          if saved.pinned {
+            particle.insert(Pinned);
+          }
+          if let Some(layers) = saved.layers {
+            particle.insert(layers);
+          }
+          if let Some(material) = saved.material {
+            particle.insert(material);
+          }
+        }
+        for (obstacle, transform) in checkpoint.obstacles {
+          commands.spawn((obstacle, transform));
+        }
+        for emitter in checkpoint.emitters {
+          commands.spawn(emitter);
+        }
+        for drain in checkpoint.drains {
+          commands.spawn(drain);
+        }
+        for zone in checkpoint.zones {
+          commands.spawn(zone);
+        }
+
+        edits.send(SceneEdited::Loaded);
+        info!("restored {} particles from {}", checkpoint.particles.len(), path.display());
+      }
+    }
+  }
+}
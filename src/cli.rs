@@ -0,0 +1,239 @@
+use std::{path::PathBuf, time::Instant};
+
+use bevy::prelude::*;
+use clap::Parser;
+
+use fluid_simulation::{
+  determinism::state_checksum, domain::ContainerShape, pressure::PressureMethod, scenario::Scenario,
+  solver::SearchBackend, theme::Theme, Particle,
+};
+#[cfg(feature = "export")]
+use fluid_simulation::export::ExportFormat;
+
+/// Particle-based fluid simulation.
+#[derive(Parser)]
+#[command(version)]
+pub struct Args {
+  /// Simulate the 3D box instead of the 2D domain
+  #[cfg(feature = "3d")]
+  #[arg(long = "3d")]
+  pub three_d: bool,
+
+  /// Run two domains side by side from the same start
+  #[arg(long)]
+  pub compare: bool,
+
+  /// Run two separate tanks side by side, each with its own parameters
+  #[arg(long, conflicts_with = "compare")]
+  pub tanks: bool,
+
+  /// World-space size of the simulation area, as WIDTHxHEIGHT
+  #[arg(long, value_name = "SIZE", value_parser = parse_size)]
+  pub domain: Option<Vec2>,
+
+  /// Reshape the domain to the window's aspect ratio when the window is
+  /// resized, instead of letterboxing it
+  #[arg(long)]
+  pub rescale_domain: bool,
+
+  /// Colour theme: dark, gradient, light or plot
+  #[arg(long, value_parser = parse_theme)]
+  pub theme: Option<Theme>,
+
+  /// Scenario to start with, e.g. rain or fill-and-drain
+  #[arg(long, value_parser = parse_scenario)]
+  pub scenario: Option<Scenario>,
+
+  /// RON scene file to start with instead of a scenario
+  #[arg(long, value_name = "FILE", conflicts_with = "scenario")]
+  pub scene: Option<PathBuf>,
+
+  /// Container shape: rectangle, circle or hexagon; arbitrary convex
+  /// polygons can be given in a --config file
+  #[arg(long, value_parser = parse_container)]
+  pub container: Option<ContainerShape>,
+
+  /// Number of particles in each domain
+  #[arg(long, value_name = "COUNT")]
+  pub particles: Option<u32>,
+
+  /// CSV or RON file of particle positions and velocities to start from
+  /// instead of a random fill
+  #[arg(long, value_name = "FILE")]
+  pub initial: Option<PathBuf>,
+
+  /// Seed for the initial particle layout, so runs start identically
+  #[arg(long)]
+  pub seed: Option<u64>,
+
+  /// Gravity strength, overriding the default
+  #[arg(long)]
+  pub gravity: Option<f32>,
+
+  /// Step the simulation by a fixed number of seconds per frame
+  #[arg(long, value_name = "SECONDS", value_parser = parse_dt)]
+  pub dt: Option<f64>,
+
+  /// Neighbour search backend: brute-force, grid or quadtree. All give the
+  /// same results; only the speed differs
+  #[arg(long, value_name = "BACKEND", value_parser = parse_search)]
+  pub neighbours: Option<SearchBackend>,
+
+  /// Pressure solver: state-equation, pbf or pcisph, overriding the config
+  #[arg(long, value_name = "METHOD", value_parser = parse_solver)]
+  pub solver: Option<PressureMethod>,
+
+  /// Make the run repeat bit for bit: one thread, a fixed step (1/60s unless
+  /// --dt is given) and seed 0 unless --seed is given. Build with
+  /// `--features deterministic` for runs to also match across machines
+  #[arg(long)]
+  pub deterministic: bool,
+
+  /// Add two coloured emitters firing at each other
+  #[arg(long)]
+  pub emitters: bool,
+
+  /// Drop a couple of solid obstacles into the fluid
+  #[arg(long)]
+  pub obstacles: bool,
+
+  /// RON file of parameters to load and reapply whenever it changes
+  #[arg(long, value_name = "FILE")]
+  pub config: Option<PathBuf>,
+
+  /// Rhai script to run, reloaded whenever it changes
+  #[arg(long, value_name = "FILE")]
+  pub script: Option<PathBuf>,
+
+  /// Export every frame as a PNG into this directory
+  #[arg(long, value_name = "DIR")]
+  pub record: Option<PathBuf>,
+
+  /// Write every particle's position, velocity and density each simulated
+  /// step to this .csv or .jsonl file, a .pvd ParaView time series, or .ply
+  /// point clouds
+  #[cfg(feature = "export")]
+  #[arg(long, value_name = "FILE", value_parser = parse_export)]
+  pub export: Option<PathBuf>,
+
+  /// Export only every Nth step
+  #[cfg(feature = "export")]
+  #[arg(long, value_name = "N", default_value_t = 1, requires = "export")]
+  pub export_every: u32,
+
+  /// Serve step time, particle count, density error and kinetic energy at
+  /// http://ADDR/metrics, e.g. 127.0.0.1:9100
+  #[cfg(feature = "metrics")]
+  #[arg(long, value_name = "ADDR")]
+  pub metrics: Option<String>,
+
+  /// Play back a session recorded with F9 instead of reading live input
+  #[arg(long, value_name = "FILE")]
+  pub replay: Option<PathBuf>,
+
+  /// Encode the exported frames into a video with ffmpeg
+  #[arg(long, requires = "record")]
+  pub ffmpeg: bool,
+
+  /// Simulate without a window or renderer and log timings
+  #[arg(long, conflicts_with = "record")]
+  pub headless: bool,
+
+  /// Keep simulating when the window loses focus instead of pausing
+  #[arg(long)]
+  pub run_unfocused: bool,
+
+  /// Frames to export, or to simulate when headless
+  #[arg(long, default_value_t = 600)]
+  pub frames: u32,
+}
+
+fn parse_size(size: &str) -> Result<Vec2, String> {
+  let parsed = size
+    .split_once('x')
+    .and_then(|(width, height)| Some(Vec2::new(width.parse().ok()?, height.parse().ok()?)))
+    .ok_or_else(|| format!("expected WIDTHxHEIGHT, got {size}"))?;
+  if !parsed.is_finite() || parsed.min_element() <= 0.0 {
+    return Err(format!("both sides must be positive, got {size}"));
+  }
+  Ok(parsed)
+}
+
+fn parse_dt(dt: &str) -> Result<f64, String> {
+  match dt.parse::<f64>() {
+    Ok(seconds) if seconds.is_finite() && seconds > 0.0 => Ok(seconds),
+    Ok(_) => Err(format!("the step must be a positive number of seconds, got {dt}")),
+    Err(_) => Err(format!("not a number of seconds: {dt}")),
+  }
+}
+
+fn parse_container(name: &str) -> Result<ContainerShape, String> {
+  ContainerShape::PRESETS
+    .into_iter()
+    .find(|shape| shape.label() == name)
+    .ok_or_else(|| format!("unknown container {name}, expected rectangle, circle or hexagon"))
+}
+
+#[cfg(feature = "export")]
+fn parse_export(path: &str) -> Result<PathBuf, String> {
+  let path = PathBuf::from(path);
+  match ExportFormat::from_path(&path) {
+    Some(_) => Ok(path),
+    None => Err(format!("unknown export format for {}, expected .csv, .jsonl, .pvd or .ply", path.display())),
+  }
+}
+
+fn parse_search(name: &str) -> Result<SearchBackend, String> {
+  SearchBackend::from_label(name).ok_or_else(|| format!("unknown neighbour search {name}, expected brute-force, grid or quadtree"))
+}
+
+fn parse_solver(name: &str) -> Result<PressureMethod, String> {
+  PressureMethod::from_label(name).ok_or_else(|| format!("unknown pressure solver {name}, expected state-equation, pbf or pcisph"))
+}
+
+fn parse_theme(name: &str) -> Result<Theme, String> {
+  Theme::from_name(name).ok_or_else(|| format!("unknown theme {name}"))
+}
+
+fn parse_scenario(name: &str) -> Result<Scenario, String> {
+  Scenario::from_name(name).ok_or_else(|| {
+    let names: Vec<String> = Scenario::ALL.iter().map(|scenario| scenario.name()).collect();
+    format!("unknown scenario {name}, expected one of {}", names.join(", "))
+  })
+}
+
+/// A headless run: simulates a fixed number of frames, then reports how
+/// long they took and a checksum of the final state, and exits.
+#[derive(Resource)]
+pub struct HeadlessRun {
+  pub frames: u32,
+  frame: u32,
+  started: Option<Instant>,
+}
+
+impl HeadlessRun {
+  pub fn new(frames: u32) -> Self {
+    HeadlessRun { frames, frame: 0, started: None }
+  }
+}
+
+pub fn count_headless_frames(
+  mut run: ResMut<HeadlessRun>,
+  mut exit: EventWriter<AppExit>,
+  particle_query: Query<&Particle>,
+) {
+  let started = *run.started.get_or_insert_with(Instant::now);
+  run.frame += 1;
+  if run.frame < run.frames {
+    return;
+  }
+
+  let elapsed = started.elapsed().as_secs_f64();
+  info!(
+    "simulated {} frames in {elapsed:.2}s ({:.2}ms per frame)",
+    run.frames,
+    elapsed * 1000.0 / run.frames as f64,
+  );
+  info!("final state checksum {:016x}", state_checksum(&particle_query));
+  exit.send(AppExit::Success);
+}
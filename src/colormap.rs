@@ -0,0 +1,247 @@
+use bevy::prelude::*;
+
+use crate::{
+  domain::{DomainId, Domains},
+  render::ParticleColor,
+  solver::{calculate_vorticity, material_pressure, SimulationState},
+  Particle, SimulationConfig, SimulationMode,
+};
+
+const LEGEND_STEPS: usize = 20;
+
+#[derive(Resource, Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub enum ColorMode {
+  #[default]
+  Source,
+  Velocity,
+  Density,
+  Pressure,
+  Vorticity,
+  Temperature,
+}
+
+impl ColorMode {
+  fn next(self) -> Self {
+    match self {
+      ColorMode::Source => ColorMode::Velocity,
+      ColorMode::Velocity => ColorMode::Density,
+      ColorMode::Density => ColorMode::Pressure,
+      ColorMode::Pressure => ColorMode::Vorticity,
+      ColorMode::Vorticity => ColorMode::Temperature,
+      ColorMode::Temperature => ColorMode::Source,
+    }
+  }
+
+  fn label(self) -> &'static str {
+    match self {
+      ColorMode::Source => "Source",
+      ColorMode::Velocity => "Speed",
+      ColorMode::Density => "Density",
+      ColorMode::Pressure => "Pressure",
+      ColorMode::Vorticity => "Vorticity",
+      ColorMode::Temperature => "Temperature",
+    }
+  }
+
+  /// Signed quantities centred on zero use the diverging map.
+  fn diverging(self) -> bool {
+    matches!(self, ColorMode::Vorticity | ColorMode::Temperature)
+  }
+
+  fn color(self, t: f32) -> Color {
+    if self.diverging() {
+      diverging_colormap(t)
+    } else {
+      colormap(t)
+    }
+  }
+}
+
+/// Range of the active scalar over all particles last frame.
+#[derive(Resource, Default)]
+pub struct ColorRange {
+  pub min: f32,
+  pub max: f32,
+}
+
+/// Blue for low values through to red for high ones.
+pub fn colormap(t: f32) -> Color {
+  Color::hsl(240.0 * (1.0 - t.clamp(0.0, 1.0)), 1.0, 0.5)
+}
+
+/// Blue at 0 through white at 0.5 to red at 1: clockwise to counter-clockwise
+/// for vorticity, cold to hot for temperature.
+pub fn diverging_colormap(t: f32) -> Color {
+  let t = t.clamp(0.0, 1.0);
+  let blue = Color::srgb(0.15, 0.3, 0.9);
+  let red = Color::srgb(0.9, 0.15, 0.15);
+  if t < 0.5 {
+    blue.mix(&Color::WHITE, t * 2.0)
+  } else {
+    Color::WHITE.mix(&red, (t - 0.5) * 2.0)
+  }
+}
+
+fn format_value(value: f32) -> String {
+  if value.abs() >= 100.0 {
+    format!("{value:.0}")
+  } else if value.abs() >= 1.0 {
+    format!("{value:.2}")
+  } else {
+    format!("{value:.3}")
+  }
+}
+
+pub fn cycle_color_mode(keys: Res<ButtonInput<KeyCode>>, mut mode: ResMut<ColorMode>) {
+  if keys.just_pressed(KeyCode::KeyC) {
+    *mode = mode.next();
+  }
+}
+
+/// Picks the colour each particle is drawn with: its (possibly blended)
+/// source tint, or the active scalar mapped over this frame's range.
+pub fn apply_color_mode(
+  mut particle_query: Query<(Entity, &Particle, &DomainId, &mut ParticleColor)>,
+  state: Res<SimulationState>,
+  mode: Res<ColorMode>,
+  simulation_mode: Res<SimulationMode>,
+  domains: Res<Domains>,
+  config: Res<SimulationConfig>,
+  mut range: ResMut<ColorRange>,
+) {
+  if *mode == ColorMode::Source {
+    for (.., mut color) in &mut particle_query {
+      color.shown = color.current;
+    }
+    return;
+  }
+
+  let dimension = simulation_mode.dimension();
+  let kinematics: Vec<(Vec3, Vec3, DomainId)> = particle_query
+    .iter()
+    .map(|(_, particle, domain, _)| (particle.position, particle.velocity, *domain))
+    .collect();
+  let densities: Vec<f32> = particle_query.iter().map(|(entity, ..)| state.density_of(entity)).collect();
+
+  let scalars: Vec<f32> = particle_query
+    .iter()
+    .enumerate()
+    .map(|(i, (entity, particle, domain, _))| {
+      match *mode {
+        ColorMode::Velocity => particle.velocity.length(),
+        ColorMode::Density => densities[i],
+        ColorMode::Pressure => {
+          material_pressure(densities[i], state.material_of(entity), dimension, domains.config(domain.0, &config))
+            * domains.params[domain.0].pressure_scale
+        }
+        ColorMode::Vorticity => calculate_vorticity(&kinematics, &densities, i, dimension, config.smoothing_radius, config.mass),
+        ColorMode::Temperature => particle.temperature,
+        ColorMode::Source => unreachable!(),
+      }
+    })
+    .collect();
+
+  range.min = scalars.iter().copied().fold(f32::INFINITY, f32::min);
+  range.max = scalars.iter().copied().fold(f32::NEG_INFINITY, f32::max);
+
+  // keep zero at the centre of the diverging map
+  if mode.diverging() {
+    let extent = range.min.abs().max(range.max.abs());
+    range.min = -extent;
+    range.max = extent;
+  }
+
+  let span = (range.max - range.min).max(f32::EPSILON);
+
+  for ((.., mut color), scalar) in particle_query.iter_mut().zip(scalars) {
+    color.shown = mode.color((scalar - range.min) / span);
+  }
+}
+
+#[derive(Component)]
+pub struct Legend;
+
+/// One segment of the legend's colour bar, at `t` along the colormap.
+#[derive(Component)]
+pub struct LegendStep(f32);
+
+#[derive(Component)]
+pub enum LegendLabel {
+  Title,
+  Max,
+  Min,
+}
+
+pub fn setup_legend(mut commands: Commands) {
+  commands
+    .spawn((
+      Legend,
+      Node {
+        position_type: PositionType::Absolute,
+        bottom: Val::Px(12.0),
+        right: Val::Px(12.0),
+        flex_direction: FlexDirection::Column,
+        align_items: AlignItems::Center,
+        row_gap: Val::Px(4.0),
+        ..default()
+      },
+      Visibility::Hidden,
+    ))
+    .with_children(|legend| {
+      legend.spawn((LegendLabel::Title, Text::default()));
+      legend.spawn((LegendLabel::Max, Text::default()));
+      legend
+        .spawn(Node {
+          width: Val::Px(16.0),
+          height: Val::Px(200.0),
+          flex_direction: FlexDirection::ColumnReverse,
+          ..default()
+        })
+        .with_children(|bar| {
+          for step in 0..LEGEND_STEPS {
+            let t = step as f32 / (LEGEND_STEPS - 1) as f32;
+            bar.spawn((
+              LegendStep(t),
+              Node {
+                width: Val::Percent(100.0),
+                height: Val::Percent(100.0 / LEGEND_STEPS as f32),
+                ..default()
+              },
+              BackgroundColor(colormap(t)),
+            ));
+          }
+        });
+      legend.spawn((LegendLabel::Min, Text::default()));
+    });
+}
+
+pub fn update_legend(
+  mode: Res<ColorMode>,
+  range: Res<ColorRange>,
+  mut legend_query: Query<&mut Visibility, With<Legend>>,
+  mut label_query: Query<(&mut Text, &LegendLabel)>,
+  mut step_query: Query<(&mut BackgroundColor, &LegendStep)>,
+) {
+  let active = *mode != ColorMode::Source;
+  for mut visibility in &mut legend_query {
+    *visibility = if active { Visibility::Inherited } else { Visibility::Hidden };
+  }
+
+  if !active {
+    return;
+  }
+
+  if mode.is_changed() {
+    for (mut color, step) in &mut step_query {
+      color.0 = mode.color(step.0);
+    }
+  }
+
+  for (mut text, label) in &mut label_query {
+    text.0 = match label {
+      LegendLabel::Title => mode.label().to_string(),
+      LegendLabel::Max => format_value(range.max),
+      LegendLabel::Min => format_value(range.min),
+    };
+  }
+}
@@ -0,0 +1,75 @@
+use std::{fs, path::PathBuf, time::SystemTime};
+
+use bevy::prelude::*;
+
+use crate::{schema, SimulationConfig};
+
+// how often the file's modification time is checked
+const POLL_INTERVAL: f32 = 0.5;
+
+/// The RON file passed with `--config`, reapplied to `SimulationConfig`
+/// whenever it changes on disk.
+#[derive(Resource)]
+pub struct ConfigFile {
+  path: PathBuf,
+  modified: Option<SystemTime>,
+  poll: Timer,
+}
+
+impl ConfigFile {
+  pub fn new(path: impl Into<PathBuf>) -> Self {
+    ConfigFile {
+      path: path.into(),
+      modified: None,
+      poll: Timer::from_seconds(POLL_INTERVAL, TimerMode::Repeating),
+    }
+  }
+}
+
+/// Writes the current parameters to the file if it doesn't exist yet, so
+/// there is something to edit.
+pub fn create_config_file(file: Res<ConfigFile>, config: Res<SimulationConfig>) {
+  if file.path.exists() {
+    return;
+  }
+
+  let written = schema::to_string(&*config)
+    .map_err(std::io::Error::other)
+    .and_then(|text| fs::write(&file.path, text));
+  match written {
+    Ok(()) => info!("wrote default parameters to {}", file.path.display()),
+    Err(err) => warn!("could not write {}: {err}", file.path.display()),
+  }
+}
+
+/// Polls the file on real time, so edits still apply while paused. A file
+/// that fails to parse is reported and the current parameters are kept.
+pub fn watch_config_file(
+  mut file: ResMut<ConfigFile>,
+  mut config: ResMut<SimulationConfig>,
+  time: Res<Time<Real>>,
+) {
+  let first = file.modified.is_none();
+  if !file.poll.tick(time.delta()).just_finished() && !first {
+    return;
+  }
+
+  let Ok(modified) = fs::metadata(&file.path).and_then(|meta| meta.modified()) else {
+    return;
+  };
+  if file.modified == Some(modified) {
+    return;
+  }
+  file.modified = Some(modified);
+
+  let parsed = fs::read_to_string(&file.path)
+    .map_err(|err| err.to_string())
+    .and_then(|text| schema::from_str::<SimulationConfig>(&text).map_err(|err| err.to_string()));
+  match parsed {
+    Ok(loaded) => {
+      info!("applied parameters from {}", file.path.display());
+      *config = loaded;
+    }
+    Err(err) => warn!("could not read {}: {err}", file.path.display()),
+  }
+}
@@ -0,0 +1,326 @@
+use bevy::prelude::*;
+#[cfg(feature = "egui")]
+use bevy_egui::{egui, EguiContexts};
+
+use crate::{
+  checkpoint::CheckpointCommand,
+  pause::PauseControl,
+  pressure::PressureMethod,
+  rollback::RollbackBuffer,
+  scene::LoadScene,
+  scenario::{LoadScenario, Scenario, SpawnOptions},
+  script::SceneScript,
+  solver::{SearchBackend, SimulationState},
+  spawn::{DespawnParticles, Region, SpawnParticles},
+  ResetSimulation, SimulationConfig, SimulationMode,
+};
+
+// lines kept in the scrollback
+const MAX_LOG_LINES: usize = 200;
+
+const HELP: &str = "\
+spawn <count> [x y r]  scatter more particles over each domain, or a circle
+despawn <x> <y> <r>    remove the particles in a circle, or despawn all
+set <name> [value]     show or change a parameter, e.g. set viscosity 4
+params                 list every parameter and its value
+load <scenario>        load a scenario, e.g. load fill_and_drain
+scene <file>           load a scene file, e.g. scene scenes/two-blocks.ron
+seed <n> | seed off    seed the particle layout and restart
+count <n>              particles a restart spawns
+neighbours [backend]   show or pick the neighbour search: brute-force, grid, quadtree
+solver [method]        show or pick the pressure solver: state-equation, pbf, pcisph
+script <file> | off    run a rhai script, or stop the current one
+save | restore <file>  write the whole simulation to a file, or load it back
+rollback <steps>       rewind the particles and parameters, e.g. rollback 120
+reset | restart        default parameters | respawn the particles
+pause | resume | step | edit
+clear | help";
+
+/// A line typed into the console. Anything that should be able to drive the
+/// simulation from text, like a replay, sends these too.
+#[derive(Event, Clone, Debug)]
+pub struct ConsoleCommand(pub String);
+
+/// The drop-down console, toggled with the backtick/tilde key.
+#[derive(Resource, Default)]
+#[cfg_attr(not(feature = "egui"), allow(dead_code))]
+pub struct Console {
+  pub open: bool,
+  input: String,
+  log: Vec<String>,
+  history: Vec<String>,
+  // how far back up the history the arrow keys are, 0 being the new line
+  recall: usize,
+  focus: bool,
+}
+
+impl Console {
+  fn print(&mut self, line: impl Into<String>) {
+    self.log.push(line.into());
+    if self.log.len() > MAX_LOG_LINES {
+      self.log.remove(0);
+    }
+  }
+}
+
+pub fn toggle_console(keys: Res<ButtonInput<KeyCode>>, mut console: ResMut<Console>) {
+  if keys.just_pressed(KeyCode::Backquote) {
+    console.open = !console.open;
+    console.focus = console.open;
+  }
+}
+
+#[cfg(feature = "egui")]
+pub fn console_panel(
+  mut contexts: EguiContexts,
+  mut console: ResMut<Console>,
+  mut commands: EventWriter<ConsoleCommand>,
+) {
+  if !console.open {
+    return;
+  }
+
+  egui::TopBottomPanel::top("console").show(contexts.ctx_mut(), |ui| {
+    egui::ScrollArea::vertical()
+      .max_height(160.0)
+      .stick_to_bottom(true)
+      .show(ui, |ui| {
+        ui.set_min_width(ui.available_width());
+        for line in &console.log {
+          ui.monospace(line);
+        }
+      });
+
+    let input = ui.add(
+      egui::TextEdit::singleline(&mut console.input)
+        .font(egui::TextStyle::Monospace)
+        .hint_text("help")
+        .desired_width(f32::INFINITY),
+    );
+    // bevy doesn't see keys while egui has keyboard focus, so the toggle key
+    // is caught here too
+    let (backtick, enter, up, down) = ui.input(|i| {
+      (
+        i.key_pressed(egui::Key::Backtick),
+        i.key_pressed(egui::Key::Enter),
+        i.key_pressed(egui::Key::ArrowUp),
+        i.key_pressed(egui::Key::ArrowDown),
+      )
+    });
+    console.input.retain(|c| c != '`' && c != '~');
+
+    if backtick {
+      console.open = false;
+      return;
+    }
+
+    if input.lost_focus() && enter {
+      let line = console.input.trim().to_owned();
+      console.input.clear();
+      console.recall = 0;
+      if !line.is_empty() {
+        console.history.push(line.clone());
+        commands.send(ConsoleCommand(line));
+      }
+      console.focus = true;
+    } else if input.has_focus() && (up || down) {
+      let recall = if up { console.recall + 1 } else { console.recall.saturating_sub(1) };
+      console.recall = recall.min(console.history.len());
+      console.input = match console.recall {
+        0 => String::new(),
+        n => console.history[console.history.len() - n].clone(),
+      };
+    }
+
+    if std::mem::take(&mut console.focus) {
+      input.request_focus();
+    }
+  });
+}
+
+pub fn run_console_commands(
+  mut commands: Commands,
+  mut lines: EventReader<ConsoleCommand>,
+  mut console: ResMut<Console>,
+  mut config: ResMut<SimulationConfig>,
+  mut spawn: ResMut<SpawnOptions>,
+  mut pause: PauseControl,
+  mut loads: EventWriter<LoadScenario>,
+  mut resets: EventWriter<ResetSimulation>,
+  mut spawns: EventWriter<SpawnParticles>,
+  mut despawns: EventWriter<DespawnParticles>,
+  mode: Res<SimulationMode>,
+  mut checkpoints: EventWriter<CheckpointCommand>,
+  mut rollback: ResMut<RollbackBuffer>,
+  mut scenes: EventWriter<LoadScene>,
+  mut state: ResMut<SimulationState>,
+) {
+  for ConsoleCommand(line) in lines.read() {
+    console.print(format!("> {line}"));
+    let words: Vec<&str> = line.split_whitespace().collect();
+
+    let reply = match words.as_slice() {
+      ["help"] => HELP.to_owned(),
+      ["clear"] => {
+        console.log.clear();
+        continue;
+      }
+      ["spawn", ..] | ["despawn", ..] if *mode == SimulationMode::ThreeD => "spawn only works in 2D".to_owned(),
+      ["spawn", count] => match count.parse::<u32>() {
+        Ok(count) => {
+          spawns.send(SpawnParticles::new(Region::Domains, count));
+          format!("spawning {count} particles")
+        }
+        Err(_) => format!("not a particle count: {count}"),
+      },
+      ["spawn", count, x, y, radius] => match (count.parse::<u32>(), circle(x, y, radius)) {
+        (Ok(count), Some(region)) => {
+          spawns.send(SpawnParticles::new(region, count));
+          format!("spawning {count} particles")
+        }
+        (Err(_), _) => format!("not a particle count: {count}"),
+        (_, None) => "expected spawn <count> <x> <y> <radius>".to_owned(),
+      },
+      ["despawn", "all"] => {
+        despawns.send(DespawnParticles { region: Region::Domains });
+        "removing every particle".to_owned()
+      }
+      ["despawn", x, y, radius] => match circle(x, y, radius) {
+        Some(region) => {
+          despawns.send(DespawnParticles { region });
+          "removing the particles in the circle".to_owned()
+        }
+        None => "expected despawn <x> <y> <radius>".to_owned(),
+      },
+      ["set", name] => match config.bypass_change_detection().field_mut(name) {
+        Some(value) => format!("{name} = {value}"),
+        None => format!("unknown parameter {name}, see params"),
+      },
+      ["set", name, value] => match (config.field_mut(name), value.parse::<f32>()) {
+        (Some(field), Ok(value)) => {
+          *field = value;
+          format!("{name} = {value}")
+        }
+        (None, _) => format!("unknown parameter {name}, see params"),
+        (_, Err(_)) => format!("not a number: {value}"),
+      },
+      ["params"] => SimulationConfig::FIELDS
+        .iter()
+        .map(|name| format!("{name} = {}", config.bypass_change_detection().field_mut(name).map_or(0.0, |value| *value)))
+        .collect::<Vec<_>>()
+        .join("\n"),
+      ["load", name] => match Scenario::from_name(&name.replace('_', "-")) {
+        Some(scenario) => {
+          loads.send(LoadScenario(scenario));
+          format!("loading {}", scenario.label())
+        }
+        None => {
+          let names: Vec<String> = Scenario::ALL.iter().map(|scenario| scenario.name()).collect();
+          format!("unknown scenario {name}, expected one of {}", names.join(", "))
+        }
+      },
+      ["scene", _] if *mode == SimulationMode::ThreeD => "scenes only work in 2D".to_owned(),
+      ["scene", path] => {
+        scenes.send(LoadScene::File(path.into()));
+        format!("loading scene {path}")
+      }
+      ["seed", "off"] => {
+        spawn.seed = None;
+        resets.send_default();
+        "layout unseeded, restarting".to_owned()
+      }
+      ["seed", seed] => match seed.parse::<u64>() {
+        Ok(seed) => {
+          spawn.seed = Some(seed);
+          resets.send_default();
+          format!("seed {seed}, restarting")
+        }
+        Err(_) => format!("not a seed: {seed}"),
+      },
+      ["count", count] => match count.parse::<u32>() {
+        Ok(count) => {
+          spawn.particles = count;
+          format!("restarts spawn {count} particles")
+        }
+        Err(_) => format!("not a particle count: {count}"),
+      },
+      ["neighbours"] => format!("neighbour search: {}", state.search.label()),
+      ["neighbours", name] => match SearchBackend::from_label(name) {
+        Some(search) => {
+          state.search = search;
+          format!("neighbour search: {name}")
+        }
+        None => format!("unknown neighbour search {name}, expected brute-force, grid or quadtree"),
+      },
+      ["solver"] => format!("pressure solver: {}", config.pressure_solver.label()),
+      ["solver", name] => match PressureMethod::from_label(name) {
+        Some(method) => {
+          config.pressure_solver = method;
+          format!("pressure solver: {name}")
+        }
+        None => format!("unknown pressure solver {name}, expected state-equation, pbf or pcisph"),
+      },
+      ["save", path] | ["restore", path] if *mode == SimulationMode::ThreeD => {
+        format!("checkpoints only work in 2D, not saving or restoring {path}")
+      }
+      ["save", path] => {
+        checkpoints.send(CheckpointCommand::Save(path.into()));
+        format!("saving {path}")
+      }
+      ["restore", path] => {
+        checkpoints.send(CheckpointCommand::Restore(path.into()));
+        format!("restoring {path}")
+      }
+      ["rollback", steps] => match steps.parse::<u64>() {
+        Ok(steps) => match rollback.rollback(steps) {
+          Some(back) => format!("rolling back {back} steps"),
+          None => "nothing to roll back to yet".to_owned(),
+        },
+        Err(_) => format!("not a step count: {steps}"),
+      },
+      ["script", "off"] => {
+        commands.remove_resource::<SceneScript>();
+        "script stopped".to_owned()
+      }
+      ["script", path] => {
+        commands.insert_resource(SceneScript::new(*path));
+        format!("running {path}")
+      }
+      ["reset"] => {
+        *config = SimulationConfig::default();
+        "parameters reset".to_owned()
+      }
+      ["restart"] => {
+        resets.send_default();
+        "restarting".to_owned()
+      }
+      ["pause"] => {
+        pause.pause();
+        "paused".to_owned()
+      }
+      ["resume"] => {
+        pause.resume();
+        "resumed".to_owned()
+      }
+      ["step"] => {
+        pause.step();
+        "stepped".to_owned()
+      }
+      ["edit"] => {
+        pause.edit();
+        "editing, resume to continue".to_owned()
+      }
+      [] => continue,
+      [command, ..] => format!("unknown command {command}, see help"),
+    };
+
+    for reply_line in reply.lines() {
+      console.print(reply_line);
+    }
+  }
+}
+
+fn circle(x: &str, y: &str, radius: &str) -> Option<Region> {
+  let center = Vec2::new(x.parse().ok()?, y.parse().ok()?);
+  Some(Region::Circle { center, radius: radius.parse().ok()? })
+}
@@ -0,0 +1,41 @@
+use bevy::{
+  ecs::schedule::{ExecutorKind, ScheduleLabel},
+  prelude::*,
+};
+
+use crate::Particle;
+
+/// Whether this build does its transcendental maths in software, through the
+/// `deterministic` feature. Without it, runs only repeat exactly on machines
+/// with the same platform maths library.
+pub const STRICT_MATH: bool = cfg!(feature = "deterministic");
+
+/// Runs the frame's schedules on one thread, in the same order every time.
+/// Otherwise, systems with no ordering between them could swap places from one
+/// run to the next. Bit-identical runs also need a fixed `--dt` and a seed.
+pub struct DeterminismPlugin;
+
+impl Plugin for DeterminismPlugin {
+  fn build(&self, app: &mut App) {
+    for label in [PreUpdate.intern(), Update.intern(), PostUpdate.intern()] {
+      app.edit_schedule(label, |schedule| {
+        schedule.set_executor_kind(ExecutorKind::SingleThreaded);
+      });
+    }
+  }
+}
+
+/// FNV-1a hash of every particle's position and velocity bits, in query
+/// order, for checking that two runs ended in exactly the same state.
+pub fn state_checksum<'a>(particles: impl IntoIterator<Item = &'a Particle>) -> u64 {
+  let mut hash: u64 = 0xcbf29ce484222325;
+  for particle in particles {
+    for value in particle.position.to_array().into_iter().chain(particle.velocity.to_array()) {
+      for byte in value.to_bits().to_le_bytes() {
+        hash ^= byte as u64;
+        hash = hash.wrapping_mul(0x100000001b3);
+      }
+    }
+  }
+  hash
+}
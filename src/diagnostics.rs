@@ -0,0 +1,203 @@
+use std::collections::VecDeque;
+
+use bevy::prelude::*;
+#[cfg(feature = "egui")]
+use bevy::{
+  render::{camera::RenderTarget, view::RenderLayers},
+  window::WindowRef,
+};
+#[cfg(feature = "egui")]
+use bevy_egui::{egui, EguiContexts};
+
+use crate::{solver::SimulationState, Particle};
+#[cfg(feature = "egui")]
+use crate::minimap::Minimap;
+
+#[cfg(feature = "egui")]
+const DIAGNOSTICS_KEY: KeyCode = KeyCode::F7;
+// ten seconds of steps at 60 a second
+const MAX_SAMPLES: usize = 600;
+#[cfg(feature = "egui")]
+const BINS: usize = 24;
+#[cfg(feature = "egui")]
+const PLOT_HEIGHT: f32 = 80.0;
+
+/// A second OS window holding the plots, histograms and minimap, so the
+/// primary window shows nothing but the fluid.
+#[derive(Component)]
+pub struct DiagnosticsWindow;
+
+/// Clears the diagnostics window each frame for egui to draw over.
+#[cfg(feature = "egui")]
+#[derive(Component)]
+pub struct DiagnosticsCamera;
+
+/// Per-step totals plotted in the diagnostics window, recorded only while it
+/// is open.
+#[derive(Resource, Default)]
+pub struct DiagnosticsHistory {
+  pub kinetic_energy: VecDeque<f32>,
+  pub mean_density: VecDeque<f32>,
+}
+
+pub fn diagnostics_open(window_query: Query<(), With<DiagnosticsWindow>>) -> bool {
+  !window_query.is_empty()
+}
+
+/// Opens the window, or closes it along with its camera. Closing it from its
+/// title bar works too, since the window is then already gone.
+#[cfg(feature = "egui")]
+pub fn toggle_diagnostics(
+  mut commands: Commands,
+  keys: Res<ButtonInput<KeyCode>>,
+  window_query: Query<Entity, With<DiagnosticsWindow>>,
+  camera_query: Query<Entity, With<DiagnosticsCamera>>,
+  mut history: ResMut<DiagnosticsHistory>,
+) {
+  let open = !window_query.is_empty();
+  if !keys.just_pressed(DIAGNOSTICS_KEY) {
+    if !open {
+      for camera in &camera_query {
+        commands.entity(camera).despawn();
+      }
+    }
+    return;
+  }
+
+  if open {
+    for entity in window_query.iter().chain(&camera_query) {
+      commands.entity(entity).despawn();
+    }
+    return;
+  }
+
+  *history = DiagnosticsHistory::default();
+  let window = commands.spawn((
+    DiagnosticsWindow,
+    Window {
+      title: "Diagnostics".into(),
+      resolution: (420.0, 720.0).into(),
+      ..default()
+    },
+  )).id();
+  commands.spawn((
+    DiagnosticsCamera,
+    Camera2d,
+    Camera { target: RenderTarget::Window(WindowRef::Entity(window)), ..default() },
+    RenderLayers::none(),
+  ));
+}
+
+/// Runs after the solver, once the densities are fresh.
+pub fn record_diagnostics(
+  mut history: ResMut<DiagnosticsHistory>,
+  particle_query: Query<&Particle>,
+  state: Res<SimulationState>,
+) {
+  let energy = particle_query.iter().map(|particle| 0.5 * particle.mass * particle.velocity.length_squared()).sum();
+  let density = if state.densities.is_empty() {
+    0.0
+  } else {
+    state.densities.iter().sum::<f32>() / state.densities.len() as f32
+  };
+
+  let history = &mut *history;
+  for (samples, value) in [(&mut history.kinetic_energy, energy), (&mut history.mean_density, density)] {
+    if samples.len() == MAX_SAMPLES {
+      samples.pop_front();
+    }
+    samples.push_back(value);
+  }
+}
+
+#[cfg(feature = "egui")]
+pub fn diagnostics_panel(
+  mut contexts: EguiContexts,
+  window_query: Query<Entity, With<DiagnosticsWindow>>,
+  minimap_query: Query<&ImageNode, With<Minimap>>,
+  particle_query: Query<&Particle>,
+  images: Res<Assets<Image>>,
+  history: Res<DiagnosticsHistory>,
+  state: Res<SimulationState>,
+) {
+  let Ok(window) = window_query.get_single() else {
+    return;
+  };
+  let minimap = minimap_query.get_single().ok().and_then(|node| {
+    let size = images.get(&node.image)?.size_f32();
+    Some((contexts.add_image(node.image.clone()), egui::vec2(size.x, size.y)))
+  });
+  // the window's context only exists from the frame after it opens
+  let Some(ctx) = contexts.try_ctx_for_entity_mut(window) else {
+    return;
+  };
+
+  egui::CentralPanel::default().show(ctx, |ui| {
+    ui.heading("Kinetic energy");
+    plot(ui, &history.kinetic_energy);
+    ui.heading("Mean density");
+    plot(ui, &history.mean_density);
+    ui.heading("Speed");
+    histogram(ui, particle_query.iter().map(|particle| particle.velocity.length()).collect());
+    ui.heading("Density");
+    histogram(ui, state.densities.clone());
+    if let Some(minimap) = minimap {
+      ui.heading("Domain");
+      ui.image(minimap);
+    }
+  });
+}
+
+#[cfg(feature = "egui")]
+fn range(values: impl Iterator<Item = f32>) -> (f32, f32) {
+  let (min, max) = values.fold((f32::INFINITY, f32::NEG_INFINITY), |(min, max), value| (min.min(value), max.max(value)));
+  if min > max {
+    (0.0, 1.0)
+  } else {
+    (min, max.max(min + f32::EPSILON))
+  }
+}
+
+/// The samples as a line across the full width, oldest on the left.
+#[cfg(feature = "egui")]
+fn plot(ui: &mut egui::Ui, samples: &VecDeque<f32>) {
+  let (rect, _) = ui.allocate_exact_size(egui::vec2(ui.available_width(), PLOT_HEIGHT), egui::Sense::hover());
+  let painter = ui.painter_at(rect);
+  painter.rect_filled(rect, 0.0, ui.visuals().extreme_bg_color);
+
+  let (min, max) = range(samples.iter().copied());
+  let points = samples
+    .iter()
+    .enumerate()
+    .map(|(i, value)| {
+      egui::pos2(
+        rect.left() + rect.width() * i as f32 / (MAX_SAMPLES - 1) as f32,
+        rect.bottom() - rect.height() * (value - min) / (max - min),
+      )
+    })
+    .collect();
+  painter.add(egui::Shape::line(points, egui::Stroke::new(1.5, ui.visuals().text_color())));
+  ui.label(format!("{:.2} (range {min:.2} to {max:.2})", samples.back().copied().unwrap_or_default()));
+}
+
+#[cfg(feature = "egui")]
+fn histogram(ui: &mut egui::Ui, values: Vec<f32>) {
+  let (rect, _) = ui.allocate_exact_size(egui::vec2(ui.available_width(), PLOT_HEIGHT), egui::Sense::hover());
+  let painter = ui.painter_at(rect);
+  painter.rect_filled(rect, 0.0, ui.visuals().extreme_bg_color);
+
+  let (min, max) = range(values.iter().copied());
+  let mut counts = [0u32; BINS];
+  for value in &values {
+    counts[(((value - min) / (max - min) * BINS as f32) as usize).min(BINS - 1)] += 1;
+  }
+  let tallest = counts.iter().copied().max().unwrap_or_default().max(1);
+  let width = rect.width() / BINS as f32;
+  for (i, count) in counts.into_iter().enumerate() {
+    let left = rect.left() + i as f32 * width;
+    let top = rect.bottom() - rect.height() * count as f32 / tallest as f32;
+    let bar = egui::Rect::from_min_max(egui::pos2(left + 1.0, top), egui::pos2(left + width - 1.0, rect.bottom()));
+    painter.rect_filled(bar, 0.0, ui.visuals().selection.bg_fill);
+  }
+  ui.label(format!("{} particles, {min:.2} to {max:.2}", values.len()));
+}
@@ -0,0 +1,314 @@
+use bevy::prelude::*;
+#[cfg(feature = "render")]
+use bevy::{
+  render::camera::ScalingMode,
+  window::{PrimaryWindow, WindowResized},
+};
+
+use crate::SimulationConfig;
+#[cfg(feature = "render")]
+use crate::{follow::FollowCamera, metaballs::SplatCamera, minimap::MinimapCamera, theme::Theme, Particle};
+
+/// Bevy's default window resolution, so the default domain fills the window.
+pub const DEFAULT_DOMAIN_SIZE: Vec2 = Vec2::new(1280.0, 720.0);
+// segments used to draw the circular container
+const CIRCLE_RESOLUTION: u32 = 64;
+
+/// The wall each domain's fluid is held in, inscribed in the domain's column.
+/// Polygon vertices are in units of the column's half-size, so `(1.0, 1.0)`
+/// is its top right corner, and must describe a convex outline.
+#[derive(Clone, PartialEq, Default, Debug, Reflect, serde::Serialize, serde::Deserialize)]
+pub enum ContainerShape {
+  #[default]
+  Rectangle,
+  Circle,
+  Hexagon,
+  Polygon(Vec<Vec2>),
+}
+
+impl ContainerShape {
+  pub const PRESETS: [ContainerShape; 3] = [ContainerShape::Rectangle, ContainerShape::Circle, ContainerShape::Hexagon];
+
+  pub fn label(&self) -> &'static str {
+    match self {
+      ContainerShape::Rectangle => "rectangle",
+      ContainerShape::Circle => "circle",
+      ContainerShape::Hexagon => "hexagon",
+      ContainerShape::Polygon(_) => "polygon",
+    }
+  }
+
+  /// Outline in domain-local coordinates, anticlockwise; `None` for the
+  /// circle, whose radius is the smaller half-size.
+  fn polygon(&self, half: Vec2) -> Option<Vec<Vec2>> {
+    let vertices = match self {
+      ContainerShape::Circle => return None,
+      ContainerShape::Rectangle => vec![
+        Vec2::new(-half.x, -half.y),
+        Vec2::new(half.x, -half.y),
+        Vec2::new(half.x, half.y),
+        Vec2::new(-half.x, half.y),
+      ],
+      // regular and flat-topped, as large as fits
+      ContainerShape::Hexagon => {
+        let radius = half.x.min(half.y * 2.0 / 3f32.sqrt());
+        (0..6)
+          .map(|i| Vec2::from_angle(i as f32 * std::f32::consts::FRAC_PI_3) * radius)
+          .collect()
+      }
+      ContainerShape::Polygon(points) => {
+        let mut vertices: Vec<Vec2> = points.iter().map(|point| *point * half).collect();
+        let area: f32 = (0..vertices.len())
+          .map(|i| vertices[i].perp_dot(vertices[(i + 1) % vertices.len()]))
+          .sum();
+        if area < 0.0 {
+          vertices.reverse();
+        }
+        vertices
+      }
+    };
+    Some(vertices)
+  }
+
+  /// Signed distance from `local` to the wall, negative inside, and the
+  /// outward normal of the wall nearest to it.
+  pub fn signed_distance(&self, local: Vec2, half: Vec2) -> (f32, Vec2) {
+    let Some(vertices) = self.polygon(half).filter(|vertices| vertices.len() >= 3) else {
+      let radius = half.min_element();
+      let normal = local.try_normalize().unwrap_or(Vec2::Y);
+      return (local.length() - radius, normal);
+    };
+
+    let edges = (0..vertices.len()).map(|i| (vertices[i], vertices[(i + 1) % vertices.len()]));
+
+    // inside a convex polygon the nearest wall is the one furthest in front of
+    let (inside, normal) = edges
+      .clone()
+      .map(|(a, b)| {
+        let normal = -(b - a).perp().normalize();
+        ((local - a).dot(normal), normal)
+      })
+      .fold((f32::NEG_INFINITY, Vec2::Y), |best, edge| if edge.0 > best.0 { edge } else { best });
+    if inside <= 0.0 {
+      return (inside, normal);
+    }
+
+    // outside, it's the nearest point on any edge
+    edges
+      .map(|(a, b)| {
+        let t = ((local - a).dot(b - a) / (b - a).length_squared()).clamp(0.0, 1.0);
+        let offset = local - a.lerp(b, t);
+        (offset.length(), offset.try_normalize().unwrap_or(normal))
+      })
+      .fold((f32::INFINITY, normal), |best, edge| if edge.0 < best.0 { edge } else { best })
+  }
+
+  pub fn contains(&self, local: Vec2, half: Vec2) -> bool {
+    self.signed_distance(local, half).0 <= 0.0
+  }
+
+  /// Closed outline for drawing.
+  pub fn outline(&self, half: Vec2) -> Vec<Vec2> {
+    let mut points = self.polygon(half).unwrap_or_else(|| {
+      let radius = half.min_element();
+      (0..CIRCLE_RESOLUTION)
+        .map(|i| Vec2::from_angle(i as f32 * std::f32::consts::TAU / CIRCLE_RESOLUTION as f32) * radius)
+        .collect()
+    });
+    points.push(points[0]);
+    points
+  }
+}
+
+/// Which side-by-side domain a particle belongs to. Particles only interact
+/// with others in the same domain.
+#[derive(Component, Reflect, Clone, Copy, PartialEq, Eq, Debug, serde::Serialize, serde::Deserialize)]
+#[reflect(Component)]
+pub struct DomainId(pub usize);
+
+#[derive(Clone)]
+pub struct DomainParams {
+  pub label: &'static str,
+  pub pressure_scale: f32,
+  /// Parameters of the domain's own, including its container; `None`
+  /// follows the shared `SimulationConfig`.
+  pub config: Option<SimulationConfig>,
+}
+
+/// A fixed world-space rectangle centred on the origin, split left to right
+/// into equal-width columns, one per domain. It does not depend on the window;
+/// the camera scales to fit it instead, unless the `ResizePolicy` says to
+/// reshape it.
+#[derive(Resource, Clone)]
+pub struct Domains {
+  pub size: Vec2,
+  pub params: Vec<DomainParams>,
+}
+
+impl Domains {
+  pub fn single(size: Vec2) -> Self {
+    Domains {
+      size,
+      params: vec![DomainParams { label: "Default", pressure_scale: 1.0, config: None }],
+    }
+  }
+
+  /// Default tuning on the left against a softer, more compressible fluid
+  /// on the right, both started from the same particle layout.
+  pub fn comparison(size: Vec2) -> Self {
+    Domains {
+      size,
+      params: vec![
+        DomainParams { label: "Default", pressure_scale: 1.0, config: None },
+        DomainParams { label: "Soft (0.25x pressure)", pressure_scale: 0.25, config: None },
+      ],
+    }
+  }
+
+  /// Two unrelated tanks: the shared parameters on the left, and a round tank
+  /// of thick fluid under weaker gravity on the right with its own fixed
+  /// parameters.
+  pub fn tanks(size: Vec2) -> Self {
+    let syrup = SimulationConfig {
+      gravity: 250.0,
+      viscosity: 8.0,
+      container: ContainerShape::Circle,
+      ..default()
+    };
+    Domains {
+      size,
+      params: vec![
+        DomainParams { label: "Water", pressure_scale: 1.0, config: None },
+        DomainParams { label: "Syrup", pressure_scale: 1.0, config: Some(syrup) },
+      ],
+    }
+  }
+
+  /// The parameters a domain's particles are simulated with.
+  pub fn config<'a>(&'a self, index: usize, shared: &'a SimulationConfig) -> &'a SimulationConfig {
+    self.params.get(index).and_then(|params| params.config.as_ref()).unwrap_or(shared)
+  }
+
+  /// Centre and half-size of a domain's column in world space.
+  pub fn bounds(&self, index: usize) -> (Vec2, Vec2) {
+    let width = self.size.x / self.params.len() as f32;
+    let center = Vec2::new(-self.size.x / 2.0 + (index as f32 + 0.5) * width, 0.0);
+    (center, Vec2::new(width, self.size.y) / 2.0)
+  }
+
+  /// Index of the column a world-space point falls in, if any.
+  pub fn containing(&self, point: Vec2) -> Option<usize> {
+    (0..self.params.len()).find(|&i| {
+      let (center, half) = self.bounds(i);
+      (point - center).abs().cmple(half).all()
+    })
+  }
+
+  /// Resizes the domain, returning the per-axis factor that keeps a point in
+  /// the same place relative to its column's walls.
+  pub fn rescale(&mut self, size: Vec2) -> Vec2 {
+    let factor = size / self.size;
+    self.size = size;
+    factor
+  }
+
+  #[cfg(feature = "render")]
+  /// Keeps the whole domain in view at any window size, letterboxing
+  /// whichever axis has room to spare.
+  pub fn projection(&self) -> OrthographicProjection {
+    OrthographicProjection {
+      scaling_mode: ScalingMode::AutoMin { min_width: self.size.x, min_height: self.size.y },
+      ..OrthographicProjection::default_2d()
+    }
+  }
+}
+
+/// What a window resize does to the simulation area.
+#[derive(Resource, Clone, Copy, PartialEq, Eq, Default, Debug)]
+pub enum ResizePolicy {
+  /// The domain stays as it is and the camera rescales to keep all of it in
+  /// view, letterboxing the spare axis.
+  #[default]
+  KeepDomain,
+  /// The domain keeps its height and widens or narrows to the window's aspect
+  /// ratio, with the particles stretched along with it. Obstacles, emitters
+  /// and drains stay where they are.
+  RescaleDomain,
+}
+
+#[cfg(feature = "render")]
+/// Applies the `ResizePolicy` to primary window resizes, so the walls only
+/// ever move when asked to.
+pub fn handle_window_resize(
+  mut resizes: EventReader<WindowResized>,
+  window_query: Query<(), With<PrimaryWindow>>,
+  policy: Res<ResizePolicy>,
+  mut domains: ResMut<Domains>,
+  mut particle_query: Query<&mut Particle>,
+  mut camera_query: Query<
+    (&mut OrthographicProjection, Has<MinimapCamera>),
+    Or<(With<FollowCamera>, With<SplatCamera>, With<MinimapCamera>)>,
+  >,
+) {
+  let Some(resize) = resizes.read().filter(|resize| window_query.contains(resize.window)).last() else {
+    return;
+  };
+  // minimised windows report a zero size
+  if *policy == ResizePolicy::KeepDomain || resize.width <= 0.0 || resize.height <= 0.0 {
+    return;
+  }
+
+  let size = Vec2::new(domains.size.y * resize.width / resize.height, domains.size.y);
+  if size == domains.size {
+    return;
+  }
+  let factor = domains.rescale(size).extend(1.0);
+  for mut particle in &mut particle_query {
+    particle.position *= factor;
+    particle.predicted_position *= factor;
+    particle.previous_position *= factor;
+  }
+
+  for (mut projection, minimap) in &mut camera_query {
+    projection.scaling_mode = if minimap {
+      ScalingMode::Fixed { width: size.x, height: size.y }
+    } else {
+      domains.projection().scaling_mode
+    };
+  }
+  info!("domain resized to {}x{} with the window", size.x, size.y);
+}
+
+#[cfg(feature = "render")]
+pub fn setup_labels(mut commands: Commands, domains: Res<Domains>) {
+  if domains.params.len() < 2 {
+    return;
+  }
+
+  let column = 100.0 / domains.params.len() as f32;
+  for (i, params) in domains.params.iter().enumerate() {
+    commands.spawn((
+      Text::new(params.label),
+      Node {
+        position_type: PositionType::Absolute,
+        top: Val::Px(40.0),
+        left: Val::Percent(i as f32 * column + 1.0),
+        ..default()
+      },
+    ));
+  }
+}
+
+#[cfg(feature = "render")]
+pub fn draw_domain_bounds(
+  mut gizmos: Gizmos,
+  domains: Res<Domains>,
+  config: Res<SimulationConfig>,
+  theme: Res<Theme>,
+) {
+  for i in 0..domains.params.len() {
+    let (center, half) = domains.bounds(i);
+    let outline = domains.config(i, &config).container.outline(half).into_iter().map(|point| center + point);
+    gizmos.linestrip_2d(outline, theme.foreground);
+  }
+}
@@ -0,0 +1,89 @@
+use bevy::{math::ops, prelude::*, window::PrimaryWindow};
+
+use crate::{
+  follow::{cursor_world_position, nearest_particle, FollowCamera},
+  theme::Theme,
+  ui::UiFocus,
+  Particle,
+};
+
+// spring pulling a dragged particle to the cursor, critically damped
+const DRAG_STIFFNESS: f32 = 400.0;
+const DRAG_DAMPING: f32 = 40.0;
+// how quickly the tracked cursor velocity follows the actual motion
+const CURSOR_SMOOTHING: f32 = 20.0;
+
+/// The particle being dragged with the right mouse button.
+#[derive(Component)]
+pub struct Dragged;
+
+/// Cursor position and smoothed velocity in world space, so a released
+/// particle can be thrown with the motion that was dragging it.
+#[derive(Resource, Default)]
+pub struct DragCursor {
+  position: Option<Vec2>,
+  velocity: Vec2,
+}
+
+/// Right click grabs the nearest particle, holding pulls it towards the
+/// cursor on a spring and releasing throws it with the cursor's velocity.
+pub fn drag_particle(
+  mut commands: Commands,
+  mouse_buttons: Res<ButtonInput<MouseButton>>,
+  window_query: Query<&Window, With<PrimaryWindow>>,
+  camera_query: Query<(&Camera, &GlobalTransform), With<FollowCamera>>,
+  mut particle_query: Query<(Entity, &mut Particle, Has<Dragged>)>,
+  mut drag_cursor: ResMut<DragCursor>,
+  focus: Res<UiFocus>,
+  mut gizmos: Gizmos,
+  theme: Res<Theme>,
+  time: Res<Time>,
+) {
+  let cursor = cursor_world_position(&window_query, &camera_query);
+  let dt = time.delta_secs();
+
+  match (cursor, drag_cursor.position) {
+    (Some(cursor), Some(last)) if dt > 0.0 => {
+      let blend = 1.0 - ops::exp(-CURSOR_SMOOTHING * dt);
+      drag_cursor.velocity = drag_cursor.velocity.lerp((cursor - last) / dt, blend);
+    }
+    _ => drag_cursor.velocity = Vec2::ZERO,
+  }
+  drag_cursor.position = cursor;
+
+  if mouse_buttons.just_released(MouseButton::Right) {
+    for (entity, mut particle, dragged) in &mut particle_query {
+      if dragged {
+        particle.velocity = drag_cursor.velocity.extend(0.0);
+        commands.entity(entity).remove::<Dragged>();
+      }
+    }
+    return;
+  }
+
+  let Some(cursor) = cursor else {
+    return;
+  };
+
+  if mouse_buttons.just_pressed(MouseButton::Right) && !focus.pointer {
+    let particles = particle_query.iter().map(|(entity, particle, _)| (entity, particle.position.truncate()));
+    if let Some(entity) = nearest_particle(particles, cursor) {
+      commands.entity(entity).insert(Dragged);
+    }
+    return;
+  }
+
+  for (_, mut particle, dragged) in &mut particle_query {
+    if !dragged {
+      continue;
+    }
+
+    let position = particle.position.truncate();
+    let stretch = cursor - position;
+    let relative_velocity = particle.velocity.truncate() - drag_cursor.velocity;
+    let accel = stretch * DRAG_STIFFNESS - relative_velocity * DRAG_DAMPING;
+    particle.velocity += accel.extend(0.0) * dt;
+
+    gizmos.line_2d(position, cursor, theme.foreground);
+  }
+}
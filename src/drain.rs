@@ -0,0 +1,75 @@
+use bevy::prelude::*;
+
+use crate::{theme::Theme, Particle};
+
+pub const DEFAULT_DRAIN_RADIUS: f32 = 20.0;
+
+/// Circular sink that removes particles entering it, optionally no faster
+/// than `max_rate` particles per second.
+#[derive(Component, Clone, serde::Serialize, serde::Deserialize)]
+pub struct Drain {
+  pub position: Vec2,
+  pub radius: f32,
+  pub max_rate: Option<f32>,
+  pub budget: f32,
+  pub removed: u32,
+}
+
+/// How many particles a drain removed this frame, sent once per drain on
+/// frames where it removed any, for scenes that count the flow out.
+#[derive(Event, Clone, Copy, Debug)]
+pub struct DrainedEvent {
+  pub drain: Entity,
+  pub count: u32,
+}
+
+impl Drain {
+  pub fn new(position: Vec2, radius: f32, max_rate: Option<f32>) -> Self {
+    Drain { position, radius, max_rate, budget: 0.0, removed: 0 }
+  }
+}
+
+pub fn drain_particles(
+  mut commands: Commands,
+  particle_query: Query<(Entity, &Particle)>,
+  mut drain_query: Query<(Entity, &mut Drain)>,
+  mut drained: EventWriter<DrainedEvent>,
+  time: Res<Time>,
+) {
+  let mut counts: Vec<(Entity, u32)> = drain_query.iter().map(|(entity, _)| (entity, 0)).collect();
+
+  for (_, mut drain) in &mut drain_query {
+    if let Some(rate) = drain.max_rate {
+      // at most a second's worth of flow can build up while nothing drains
+      drain.budget = (drain.budget + rate * time.delta_secs()).min(rate.max(1.0));
+    }
+  }
+
+  for (entity, particle) in &particle_query {
+    let position = particle.position.truncate();
+    let accepting = drain_query.iter_mut().enumerate().find(|(_, (_, drain))| {
+      position.distance(drain.position) < drain.radius
+        && (drain.max_rate.is_none() || drain.budget >= 1.0)
+    });
+
+    if let Some((i, (_, mut drain))) = accepting {
+      commands.entity(entity).despawn_recursive();
+      drain.budget -= 1.0;
+      drain.removed += 1;
+      counts[i].1 += 1;
+    }
+  }
+
+  for (drain, count) in counts {
+    if count > 0 {
+      drained.send(DrainedEvent { drain, count });
+    }
+  }
+}
+
+pub fn draw_drains(drain_query: Query<&Drain>, mut gizmos: Gizmos, theme: Res<Theme>) {
+  for drain in &drain_query {
+    gizmos.circle_2d(drain.position, drain.radius, theme.foreground);
+    gizmos.circle_2d(drain.position, drain.radius * 0.5, theme.foreground);
+  }
+}
@@ -0,0 +1,136 @@
+use bevy::prelude::*;
+use rand::Rng;
+
+use crate::{domain::{DomainId, Domains}, rng::SimulationRng, ParticleSpawn, SimulationConfig};
+
+/// How far in from a wall emitters are placed, so their particles start
+/// inside the container.
+pub const EMITTER_INSET: f32 = 8.0;
+
+/// Colours handed out to emitters in spawn order.
+pub const EMITTER_PALETTE: [Color; 4] = [
+  Color::hsl(0.0, 1.0, 0.55),
+  Color::hsl(50.0, 1.0, 0.5),
+  Color::hsl(120.0, 1.0, 0.45),
+  Color::hsl(280.0, 1.0, 0.6),
+];
+
+pub const DEFAULT_RATE: f32 = 20.0;
+pub const DEFAULT_SPEED: f32 = 300.0;
+
+/// Continuously spawns particles at its position, every one of them tinted
+/// with the emitter's colour. Spawning one from a scene or another crate is
+/// enough to get a faucet; `emit_particles` does the rest.
+#[derive(Component, Clone, serde::Serialize, serde::Deserialize)]
+pub struct Emitter {
+  pub position: Vec2,
+  pub direction: Vec2,
+  pub rate: f32,
+  pub speed: f32,
+  /// Random spread of each particle's direction either side of `direction`,
+  /// in radians.
+  pub jitter: f32,
+  /// Seconds each emitted particle lives before it is removed, if limited.
+  pub lifetime: Option<f32>,
+  /// How many particles the emitter spawns in total before it stops.
+  pub max_count: Option<u32>,
+  pub emitted: u32,
+  pub color: Color,
+  pub domain: DomainId,
+  pub accumulator: f32,
+}
+
+/// Time left before an emitted particle is removed.
+#[derive(Component, Clone, Copy, Debug)]
+pub struct Lifetime {
+  pub remaining: f32,
+}
+
+impl Emitter {
+  pub fn new(position: Vec2, direction: Vec2, index: usize, domain: DomainId) -> Self {
+    Emitter {
+      position,
+      direction: direction.normalize_or_zero(),
+      rate: DEFAULT_RATE,
+      speed: DEFAULT_SPEED,
+      jitter: 0.0,
+      lifetime: None,
+      max_count: None,
+      emitted: 0,
+      color: EMITTER_PALETTE[index % EMITTER_PALETTE.len()],
+      domain,
+      accumulator: 0.0,
+    }
+  }
+}
+
+/// Two emitters on opposite walls of every domain, aimed at each other.
+pub fn spawn_demo_emitters(mut commands: Commands, domains: Res<Domains>) {
+  demo_emitters(&mut commands, &domains);
+}
+
+pub fn demo_emitters(commands: &mut Commands, domains: &Domains) {
+  for i in 0..domains.params.len() {
+    let (center, half) = domains.bounds(i);
+    let inset = Vec2::new(half.x - EMITTER_INSET, 0.0);
+
+    commands.spawn(Emitter::new(center - inset, Vec2::new(1.0, 0.3), 0, DomainId(i)));
+    commands.spawn(Emitter::new(center + inset, Vec2::new(-1.0, 0.3), 1, DomainId(i)));
+  }
+}
+
+pub fn emit_particles(
+  mut commands: Commands,
+  mut meshes: ResMut<Assets<Mesh>>,
+  mut materials: ResMut<Assets<ColorMaterial>>,
+  mut emitter_query: Query<&mut Emitter>,
+  config: Res<SimulationConfig>,
+  mut rng: ResMut<SimulationRng>,
+  time: Res<Time>,
+) {
+  let rng = &mut rng.0;
+
+  for mut emitter in &mut emitter_query {
+    emitter.accumulator += emitter.rate * time.delta_secs();
+
+    while emitter.accumulator >= 1.0 {
+      emitter.accumulator -= 1.0;
+      if emitter.max_count.is_some_and(|max| emitter.emitted >= max) {
+        emitter.accumulator = 0.0;
+        break;
+      }
+      emitter.emitted += 1;
+
+      // spread spawns across the nozzle so particles never start stacked
+      let across = emitter.direction.perp() * rng.gen_range(-2.0..2.0) * config.particle_size;
+      let spread = if emitter.jitter > 0.0 { rng.gen_range(-emitter.jitter..emitter.jitter) } else { 0.0 };
+      let direction = Vec2::from_angle(spread).rotate(emitter.direction);
+
+      let particle = ParticleSpawn {
+        position: (emitter.position + across).extend(0.0),
+        velocity: (direction * emitter.speed).extend(0.0),
+        domain: emitter.domain,
+        color: emitter.color,
+        size: config.particle_size,
+      }.spawn(&mut commands, &mut meshes, &mut materials);
+
+      if let Some(remaining) = emitter.lifetime {
+        commands.entity(particle).insert(Lifetime { remaining });
+      }
+    }
+  }
+}
+
+/// Removes emitted particles whose lifetime has run out.
+pub fn expire_particles(
+  mut commands: Commands,
+  mut lifetime_query: Query<(Entity, &mut Lifetime)>,
+  time: Res<Time>,
+) {
+  for (entity, mut lifetime) in &mut lifetime_query {
+    lifetime.remaining -= time.delta_secs();
+    if lifetime.remaining <= 0.0 {
+      commands.entity(entity).despawn_recursive();
+    }
+  }
+}
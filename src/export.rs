@@ -0,0 +1,280 @@
+use std::{
+  fmt::Write as _,
+  fs::{self, File},
+  io::{self, BufWriter, Write},
+  path::{Path, PathBuf},
+};
+
+use bevy::prelude::*;
+
+use crate::{
+  domain::{DomainId, Domains},
+  render::ParticleColor,
+  solver::{material_pressure, SimulationState},
+  Particle, SimulationConfig, SimulationMode, COLOR,
+};
+
+/// How `--export` writes the particles, picked from the file extension.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum ExportFormat {
+  /// One row per particle per exported step, under a header.
+  Csv,
+  /// One JSON object per exported step, with a column array per field.
+  Jsonl,
+  /// A ParaView collection: the `.pvd` file lists one `.vtp` poly data file
+  /// per exported step, kept in a directory named after it.
+  Vtk,
+  /// One binary `.ply` point cloud per exported step, in a directory named
+  /// after the path, for Houdini or Blender to load as a sequence.
+  Ply,
+}
+
+impl ExportFormat {
+  pub fn from_path(path: &Path) -> Option<Self> {
+    match path.extension()?.to_str()? {
+      "csv" => Some(ExportFormat::Csv),
+      "jsonl" => Some(ExportFormat::Jsonl),
+      "pvd" => Some(ExportFormat::Vtk),
+      "ply" => Some(ExportFormat::Ply),
+      _ => None,
+    }
+  }
+}
+
+/// One particle as exported. Particles are identified by entity index, which
+/// stays the same for as long as the particle lives.
+pub struct ExportedParticle {
+  pub id: u32,
+  pub domain: usize,
+  pub position: Vec3,
+  pub velocity: Vec3,
+  pub density: f32,
+  pub pressure: f32,
+  pub color: Color,
+}
+
+/// Writes every particle's position, velocity and density after each
+/// simulated step, or every `every`th step, for analysis outside the app. The
+/// ParaView files carry the pressure as well, and the point clouds also the
+/// colour each particle is drawn with.
+#[derive(Resource)]
+pub struct DataExport {
+  pub path: PathBuf,
+  pub format: ExportFormat,
+  pub every: u32,
+  step: u64,
+  writer: Option<BufWriter<File>>,
+  // (time, file relative to the collection) of each step written so far
+  frames: Vec<(f32, String)>,
+}
+
+impl DataExport {
+  pub fn create(path: impl Into<PathBuf>, format: ExportFormat, every: u32) -> io::Result<Self> {
+    let path = path.into();
+    if let Some(dir) = path.parent().filter(|dir| !dir.as_os_str().is_empty()) {
+      fs::create_dir_all(dir)?;
+    }
+
+    let writer = match format {
+      ExportFormat::Csv | ExportFormat::Jsonl => {
+        let mut writer = BufWriter::new(File::create(&path)?);
+        if format == ExportFormat::Csv {
+          writeln!(writer, "step,time,id,domain,x,y,z,vx,vy,vz,density")?;
+        }
+        Some(writer)
+      }
+      ExportFormat::Vtk | ExportFormat::Ply => {
+        fs::create_dir_all(path.with_extension(""))?;
+        None
+      }
+    };
+    Ok(DataExport { path, format, every: every.max(1), step: 0, writer, frames: Vec::new() })
+  }
+
+  fn write_step(&mut self, time: f32, particles: &[ExportedParticle]) -> io::Result<()> {
+    let step = self.step;
+    match self.format {
+      ExportFormat::Csv => {
+        let writer = self.writer.as_mut().expect("csv exports write to one file");
+        for particle in particles {
+          let (p, v) = (particle.position, particle.velocity);
+          writeln!(
+            writer,
+            "{step},{time},{},{},{},{},{},{},{},{},{}",
+            particle.id, particle.domain, p.x, p.y, p.z, v.x, v.y, v.z, particle.density,
+          )?;
+        }
+        writer.flush()
+      }
+      ExportFormat::Jsonl => {
+        let mut line = format!("{{\"step\":{step},\"time\":{time}");
+        let _ = write!(line, ",\"id\":{}", json_array(particles.iter().map(|p| p.id.to_string())));
+        let _ = write!(line, ",\"domain\":{}", json_array(particles.iter().map(|p| p.domain.to_string())));
+        let _ = write!(line, ",\"position\":{}", json_array(particles.iter().map(|p| json_vec(p.position))));
+        let _ = write!(line, ",\"velocity\":{}", json_array(particles.iter().map(|p| json_vec(p.velocity))));
+        let _ = write!(line, ",\"density\":{}", json_array(particles.iter().map(|p| json_number(p.density))));
+        line.push('}');
+
+        let writer = self.writer.as_mut().expect("jsonl exports write to one file");
+        writeln!(writer, "{line}")?;
+        writer.flush()
+      }
+      ExportFormat::Vtk => {
+        let dir = self.path.with_extension("");
+        let stem = dir.file_name().map(|name| name.to_string_lossy().into_owned()).unwrap_or_default();
+        let file = format!("{stem}_{step:06}.vtp");
+        fs::write(dir.join(&file), poly_data(particles))?;
+
+        // rewritten every step so the collection opens even mid-run
+        self.frames.push((time, format!("{stem}/{file}")));
+        fs::write(&self.path, collection(&self.frames))
+      }
+      ExportFormat::Ply => {
+        let dir = self.path.with_extension("");
+        let stem = dir.file_name().map(|name| name.to_string_lossy().into_owned()).unwrap_or_default();
+        fs::write(dir.join(format!("{stem}_{step:06}.ply")), point_cloud(particles))
+      }
+    }
+  }
+}
+
+fn json_array(values: impl Iterator<Item = String>) -> String {
+  format!("[{}]", values.collect::<Vec<_>>().join(","))
+}
+
+fn json_vec(v: Vec3) -> String {
+  json_array(v.to_array().into_iter().map(json_number))
+}
+
+// JSON has no NaN or infinity, so a blown-up particle is written as null
+fn json_number(value: f32) -> String {
+  if value.is_finite() {
+    value.to_string()
+  } else {
+    "null".to_owned()
+  }
+}
+
+/// A VTK XML poly data file with one vertex per particle, and the velocity,
+/// density, pressure, id and domain as point data.
+fn poly_data(particles: &[ExportedParticle]) -> String {
+  let n = particles.len();
+  let mut vtp = String::new();
+  let _ = writeln!(vtp, "<?xml version=\"1.0\"?>");
+  let _ = writeln!(vtp, "<VTKFile type=\"PolyData\" version=\"0.1\" byte_order=\"LittleEndian\">");
+  let _ = writeln!(vtp, "<PolyData>");
+  let _ = writeln!(vtp, "<Piece NumberOfPoints=\"{n}\" NumberOfVerts=\"{n}\">");
+
+  let _ = writeln!(vtp, "<Points>");
+  data_array(&mut vtp, "Float32", "Position", 3, particles.iter().map(|p| vtk_vec(p.position)));
+  let _ = writeln!(vtp, "</Points>");
+
+  let _ = writeln!(vtp, "<PointData Scalars=\"density\" Vectors=\"velocity\">");
+  data_array(&mut vtp, "Float32", "velocity", 3, particles.iter().map(|p| vtk_vec(p.velocity)));
+  data_array(&mut vtp, "Float32", "density", 1, particles.iter().map(|p| p.density.to_string()));
+  data_array(&mut vtp, "Float32", "pressure", 1, particles.iter().map(|p| p.pressure.to_string()));
+  data_array(&mut vtp, "UInt32", "id", 1, particles.iter().map(|p| p.id.to_string()));
+  data_array(&mut vtp, "UInt32", "domain", 1, particles.iter().map(|p| p.domain.to_string()));
+  let _ = writeln!(vtp, "</PointData>");
+
+  // each particle is its own vertex cell, so ParaView draws it as a point
+  let _ = writeln!(vtp, "<Verts>");
+  data_array(&mut vtp, "Int32", "connectivity", 1, (0..n).map(|i| i.to_string()));
+  data_array(&mut vtp, "Int32", "offsets", 1, (1..=n).map(|i| i.to_string()));
+  let _ = writeln!(vtp, "</Verts>");
+
+  let _ = writeln!(vtp, "</Piece>");
+  let _ = writeln!(vtp, "</PolyData>");
+  let _ = writeln!(vtp, "</VTKFile>");
+  vtp
+}
+
+fn data_array(vtp: &mut String, kind: &str, name: &str, components: u32, values: impl Iterator<Item = String>) {
+  let _ = writeln!(
+    vtp,
+    "<DataArray type=\"{kind}\" Name=\"{name}\" NumberOfComponents=\"{components}\" format=\"ascii\">",
+  );
+  let _ = writeln!(vtp, "{}", values.collect::<Vec<_>>().join(" "));
+  let _ = writeln!(vtp, "</DataArray>");
+}
+
+fn vtk_vec(v: Vec3) -> String {
+  format!("{} {} {}", v.x, v.y, v.z)
+}
+
+/// The `.pvd` file that strings the steps together into a time series.
+fn collection(frames: &[(f32, String)]) -> String {
+  let mut pvd = String::new();
+  let _ = writeln!(pvd, "<?xml version=\"1.0\"?>");
+  let _ = writeln!(pvd, "<VTKFile type=\"Collection\" version=\"0.1\" byte_order=\"LittleEndian\">");
+  let _ = writeln!(pvd, "<Collection>");
+  for (time, file) in frames {
+    let _ = writeln!(pvd, "<DataSet timestep=\"{time}\" part=\"0\" file=\"{file}\"/>");
+  }
+  let _ = writeln!(pvd, "</Collection>");
+  let _ = writeln!(pvd, "</VTKFile>");
+  pvd
+}
+
+/// A binary PLY point cloud with the velocity, density, pressure and id as
+/// extra vertex properties, which Houdini reads in as point attributes.
+fn point_cloud(particles: &[ExportedParticle]) -> Vec<u8> {
+  let mut ply = format!(
+    "ply\nformat binary_little_endian 1.0\nelement vertex {}\n",
+    particles.len(),
+  );
+  for name in ["x", "y", "z", "vx", "vy", "vz", "density", "pressure"] {
+    let _ = writeln!(ply, "property float {name}");
+  }
+  ply.push_str("property uint id\nproperty uchar red\nproperty uchar green\nproperty uchar blue\nend_header\n");
+
+  let mut bytes = ply.into_bytes();
+  for particle in particles {
+    let values = particle.position.to_array().into_iter()
+      .chain(particle.velocity.to_array())
+      .chain([particle.density, particle.pressure]);
+    for value in values {
+      bytes.extend(value.to_le_bytes());
+    }
+    bytes.extend(particle.id.to_le_bytes());
+    let [red, green, blue, _] = Srgba::from(particle.color).to_u8_array();
+    bytes.extend([red, green, blue]);
+  }
+  bytes
+}
+
+/// Runs with the physics, after the step, so only simulated steps count.
+pub fn export_particle_data(
+  mut export: ResMut<DataExport>,
+  particle_query: Query<(Entity, &Particle, &DomainId, Option<&ParticleColor>)>,
+  state: Res<SimulationState>,
+  mode: Res<SimulationMode>,
+  domains: Res<Domains>,
+  config: Res<SimulationConfig>,
+  time: Res<Time>,
+) {
+  if export.step.is_multiple_of(export.every as u64) {
+    let particles: Vec<ExportedParticle> = particle_query
+      .iter()
+      .map(|(entity, particle, domain, color)| {
+        let density = state.density_of(entity);
+        let scale = domains.params.get(domain.0).map_or(1.0, |params| params.pressure_scale);
+        let material = state.material_of(entity);
+        let pressure = material_pressure(density, material, mode.dimension(), domains.config(domain.0, &config)) * scale;
+        ExportedParticle {
+          id: entity.index(),
+          domain: domain.0,
+          position: particle.position,
+          velocity: particle.velocity,
+          density,
+          pressure,
+          color: color.map_or(COLOR, |color| color.shown),
+        }
+      })
+      .collect();
+    if let Err(err) = export.write_step(time.elapsed_secs(), &particles) {
+      warn!("could not write to {}: {err}", export.path.display());
+    }
+  }
+  export.step += 1;
+}
@@ -0,0 +1,86 @@
+use bevy::{ecs::system::SystemParam, prelude::*};
+
+use crate::{
+  domain::{DomainId, Domains},
+  kernels::smoothing_kernel,
+  query::FluidQuery,
+  solver::{density_to_pressure, SimulationState},
+  Particle, SimulationConfig, SimulationMode,
+};
+
+/// Reads the fluid as a continuous field, for game logic and overlays that
+/// need values between the particles. Samples are SPH interpolations from the
+/// particles of the domain the point falls in, with that domain's parameters.
+/// Candidates come from the `FluidQuery` grid, so a sample only visits the
+/// particles within a smoothing radius of where they were last frame.
+#[derive(SystemParam)]
+pub struct FluidField<'w, 's> {
+  particles: Query<'w, 's, (&'static Particle, &'static DomainId)>,
+  nearby: FluidQuery<'w>,
+  state: Res<'w, SimulationState>,
+  mode: Res<'w, SimulationMode>,
+  domains: Res<'w, Domains>,
+  config: Res<'w, SimulationConfig>,
+}
+
+/// Everything `FluidField` interpolates at one point.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct FieldSample {
+  pub density: f32,
+  pub pressure: f32,
+  pub velocity: Vec3,
+}
+
+impl FluidField<'_, '_> {
+  /// The domain sampled at `point`; the 3D box is always the first.
+  pub fn domain_at(&self, point: Vec3) -> Option<DomainId> {
+    match *self.mode {
+      SimulationMode::TwoD => self.domains.containing(point.truncate()).map(DomainId),
+      SimulationMode::ThreeD => Some(DomainId(0)),
+    }
+  }
+
+  pub fn sample_density(&self, point: Vec3) -> f32 {
+    self.sample(point).density
+  }
+
+  /// Kernel-weighted average of the particle velocities around `point`.
+  pub fn sample_velocity(&self, point: Vec3) -> Vec3 {
+    self.sample(point).velocity
+  }
+
+  pub fn sample_pressure(&self, point: Vec3) -> f32 {
+    self.sample(point).pressure
+  }
+
+  /// Density, pressure and velocity at `point` from one pass over the
+  /// particles around it.
+  pub fn sample(&self, point: Vec3) -> FieldSample {
+    let Some(domain) = self.domain_at(point) else {
+      return FieldSample::default();
+    };
+    let config = self.domains.config(domain.0, &self.config);
+    let dimension = self.mode.dimension();
+    let mut sample = FieldSample::default();
+
+    for entity in self.nearby.particles_in_circle(point.truncate(), config.smoothing_radius) {
+      let Ok((particle, particle_domain)) = self.particles.get(entity) else {
+        continue;
+      };
+      if *particle_domain != domain {
+        continue;
+      }
+      let influence = smoothing_kernel(dimension, config.smoothing_radius, point.distance(particle.position));
+      sample.density += config.mass * influence;
+
+      let particle_density = self.state.density_of(entity);
+      if particle_density > 0.0 {
+        sample.velocity += particle.velocity * config.mass / particle_density * influence;
+      }
+    }
+
+    let scale = self.domains.params[domain.0].pressure_scale;
+    sample.pressure = density_to_pressure(sample.density, dimension, config) * scale;
+    sample
+  }
+}
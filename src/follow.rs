@@ -0,0 +1,114 @@
+use bevy::{prelude::*, input::mouse::AccumulatedMouseScroll, window::PrimaryWindow};
+
+use crate::{theme::Theme, Particle};
+
+pub const PICK_RADIUS: f32 = 10.0;
+const FOLLOW_RATE: f32 = 5.0;
+const ZOOM_PER_SCROLL: f32 = 0.1;
+pub const MIN_ZOOM: f32 = 0.05;
+pub const MAX_ZOOM: f32 = 2.0;
+
+/// The particle the 2D camera is following.
+#[derive(Component)]
+pub struct Tagged;
+
+/// The main 2D camera, as opposed to the offscreen splat and minimap ones.
+#[derive(Component)]
+pub struct FollowCamera;
+
+/// Where the camera settles when no particle is tagged. Panning moves it.
+#[derive(Resource, Default)]
+pub struct CameraAnchor {
+  pub position: Vec2,
+}
+
+pub fn cursor_world_position(
+  window_query: &Query<&Window, With<PrimaryWindow>>,
+  camera_query: &Query<(&Camera, &GlobalTransform), With<FollowCamera>>,
+) -> Option<Vec2> {
+  let window = window_query.get_single().ok()?;
+  let (camera, camera_transform) = camera_query.get_single().ok()?;
+  let cursor = window.cursor_position()?;
+  camera.viewport_to_world_2d(camera_transform, cursor).ok()
+}
+
+/// The particle closest to `cursor`, if any is within picking distance.
+pub fn nearest_particle(particles: impl Iterator<Item = (Entity, Vec2)>, cursor: Vec2) -> Option<Entity> {
+  particles
+    .map(|(entity, position)| (entity, position.distance(cursor)))
+    .filter(|&(_, dist)| dist < PICK_RADIUS)
+    .min_by(|a, b| a.1.total_cmp(&b.1))
+    .map(|(entity, _)| entity)
+}
+
+/// Left click tags the nearest particle under the cursor, clicking empty
+/// space or pressing Escape releases it.
+pub fn tag_particle(
+  mut commands: Commands,
+  mouse_buttons: Res<ButtonInput<MouseButton>>,
+  keys: Res<ButtonInput<KeyCode>>,
+  window_query: Query<&Window, With<PrimaryWindow>>,
+  camera_query: Query<(&Camera, &GlobalTransform), With<FollowCamera>>,
+  particle_query: Query<(Entity, &Particle)>,
+  tagged_query: Query<Entity, With<Tagged>>,
+) {
+  let clicked = mouse_buttons.just_pressed(MouseButton::Left);
+  if !clicked && !keys.just_pressed(KeyCode::Escape) {
+    return;
+  }
+
+  for entity in &tagged_query {
+    commands.entity(entity).remove::<Tagged>();
+  }
+
+  if !clicked {
+    return;
+  }
+
+  let Some(cursor) = cursor_world_position(&window_query, &camera_query) else {
+    return;
+  };
+
+  let particles = particle_query.iter().map(|(entity, particle)| (entity, particle.position.truncate()));
+  if let Some(entity) = nearest_particle(particles, cursor) {
+    commands.entity(entity).insert(Tagged);
+  }
+}
+
+pub fn follow_tagged(
+  mut camera_query: Query<&mut Transform, With<FollowCamera>>,
+  tagged_query: Query<&Particle, With<Tagged>>,
+  anchor: Res<CameraAnchor>,
+  mut gizmos: Gizmos,
+  theme: Res<Theme>,
+  time: Res<Time>,
+) {
+  let target = match tagged_query.get_single() {
+    Ok(particle) => {
+      gizmos.circle_2d(particle.position.truncate(), PICK_RADIUS, theme.foreground);
+      particle.position.truncate()
+    }
+    Err(_) => anchor.position,
+  };
+
+  let blend = 1.0 - (-FOLLOW_RATE * time.delta_secs()).exp();
+  for mut transform in &mut camera_query {
+    let current = transform.translation.truncate();
+    transform.translation = current.lerp(target, blend).extend(transform.translation.z);
+  }
+}
+
+/// Scrolling zooms the 2D camera in and out around whatever it is centred on.
+pub fn zoom_camera(
+  mut projection_query: Query<&mut OrthographicProjection, (With<Camera2d>, With<FollowCamera>)>,
+  mouse_scroll: Res<AccumulatedMouseScroll>,
+) {
+  if mouse_scroll.delta.y == 0.0 {
+    return;
+  }
+
+  for mut projection in &mut projection_query {
+    let zoom = projection.scale * (1.0 - mouse_scroll.delta.y * ZOOM_PER_SCROLL);
+    projection.scale = zoom.clamp(MIN_ZOOM, MAX_ZOOM);
+  }
+}
@@ -0,0 +1,53 @@
+use bevy::prelude::*;
+
+use crate::Particle;
+
+// how quickly particles are brought up to the wind's speed, per second
+const WIND_DRAG: f32 = 2.0;
+
+/// An external force acting on particles, on top of gravity and the fluid
+/// forces.
+#[derive(Component, Clone, Debug)]
+pub enum ForceField {
+  /// Pushes particles along `velocity` until they move with it, without
+  /// slowing anything already faster or moving across it.
+  Wind { velocity: Vec2, drag: f32 },
+}
+
+impl ForceField {
+  fn acceleration(&self, particle: &Particle) -> Vec2 {
+    match *self {
+      ForceField::Wind { velocity, drag } => {
+        let speed = velocity.length();
+        if speed == 0.0 {
+          return Vec2::ZERO;
+        }
+        let direction = velocity / speed;
+        let shortfall = speed - particle.velocity.truncate().dot(direction);
+        direction * drag * shortfall.max(0.0)
+      }
+    }
+  }
+}
+
+/// The wind set from the wind panel, blowing over every domain.
+#[derive(Component)]
+pub struct GlobalWind;
+
+pub fn spawn_global_wind(mut commands: Commands) {
+  commands.spawn((ForceField::Wind { velocity: Vec2::ZERO, drag: WIND_DRAG }, GlobalWind));
+}
+
+pub fn apply_force_fields(
+  field_query: Query<&ForceField>,
+  mut particle_query: Query<&mut Particle>,
+  time: Res<Time>,
+) {
+  let dt = time.delta_secs();
+  for mut particle in &mut particle_query {
+    let acceleration: Vec2 = field_query.iter().map(|field| field.acceleration(&particle)).sum();
+    if acceleration != Vec2::ZERO {
+      particle.velocity += acceleration.extend(0.0) * dt;
+    }
+  }
+}
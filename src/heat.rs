@@ -0,0 +1,54 @@
+use bevy::prelude::*;
+
+use crate::{
+  domain::{DomainId, Domains},
+  kernels::smoothing_kernel,
+  pin::Pinned,
+  solver::SimulationState,
+  Particle, SimulationConfig, SimulationMode,
+};
+
+/// Diffuses heat between neighbours, lets it leak back towards ambient, and
+/// lifts warm particles against gravity (Boussinesq buoyancy), so a heated
+/// patch rises as a plume. Each domain uses its own parameters. Pinned
+/// particles still conduct heat but are not lifted.
+pub fn update_temperature(
+  mut particle_query: Query<(&mut Particle, &DomainId, Has<Pinned>)>,
+  time: Res<Time>,
+  state: Res<SimulationState>,
+  mode: Res<SimulationMode>,
+  domains: Res<Domains>,
+  config: Res<SimulationConfig>,
+) {
+  // the whole fluid at ambient is the common case, nothing to do
+  if particle_query.iter().all(|(particle, ..)| particle.temperature == 0.0) {
+    return;
+  }
+
+  let dimension = mode.dimension();
+  let dt = time.delta_secs();
+
+  let particle_data: Vec<(Vec3, f32)> =
+    particle_query.iter().map(|(particle, ..)| (particle.position, particle.temperature)).collect();
+
+  for (i, (mut particle, domain, pinned)) in particle_query.iter_mut().enumerate() {
+    let config = domains.config(domain.0, &config);
+    let mut exchange = 0.0;
+    for &j in state.neighbours(i) {
+      let density = state.densities.get(j).copied().unwrap_or_default();
+      if j == i || density <= 0.0 || !state.layers[j].interacts(state.layers[i]) {
+        continue;
+      }
+      let (position, temperature) = particle_data[j];
+      let influence = smoothing_kernel(dimension, config.smoothing_radius, position.distance(particle.position));
+      exchange += (temperature - particle.temperature) * config.mass / density * influence;
+    }
+
+    let temperature = particle.temperature;
+    particle.temperature += (exchange * config.thermal_diffusion - temperature * config.cooling) * dt;
+    if !pinned {
+      let up = -config.gravity_vector().normalize_or_zero();
+      particle.velocity += up * config.buoyancy * temperature * dt;
+    }
+  }
+}
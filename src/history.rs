@@ -0,0 +1,132 @@
+use bevy::prelude::*;
+
+use crate::{drain::Drain, emitter::Emitter, obstacle::{Kinematic, Obstacle}, zone::Zone};
+
+/// Sent once a scene edit is finished, e.g. when an obstacle drag is released.
+/// `Loaded` starts a fresh history instead of recording an undo step.
+#[derive(Event, Clone, Copy, PartialEq, Eq, Debug)]
+pub enum SceneEdited {
+  Edit,
+  Loaded,
+}
+
+/// Every obstacle, emitter, drain and painted zone in the scene. Particles and code-driven
+/// obstacles like the paddle are not part of it, undoing an edit never
+/// touches them.
+#[derive(Default, Clone)]
+struct SceneSnapshot {
+  obstacles: Vec<(Obstacle, Transform)>,
+  emitters: Vec<Emitter>,
+  drains: Vec<Drain>,
+  zones: Vec<Zone>,
+}
+
+/// Undo and redo stacks of scene snapshots around `current`, the scene as of
+/// the last recorded edit.
+#[derive(Resource, Default)]
+pub struct SceneHistory {
+  undo: Vec<SceneSnapshot>,
+  redo: Vec<SceneSnapshot>,
+  current: SceneSnapshot,
+}
+
+fn capture(
+  obstacle_query: &Query<(&Obstacle, &Transform), Without<Kinematic>>,
+  emitter_query: &Query<&Emitter>,
+  drain_query: &Query<&Drain>,
+  zone_query: &Query<&Zone>,
+) -> SceneSnapshot {
+  SceneSnapshot {
+    obstacles: obstacle_query.iter().map(|(obstacle, transform)| (*obstacle, *transform)).collect(),
+    emitters: emitter_query.iter().cloned().collect(),
+    drains: drain_query.iter().cloned().collect(),
+    zones: zone_query.iter().cloned().collect(),
+  }
+}
+
+/// Runs after the frame's commands are applied, so the snapshot includes
+/// whatever the edit spawned or removed.
+pub fn record_scene_edits(
+  mut edits: EventReader<SceneEdited>,
+  obstacle_query: Query<(&Obstacle, &Transform), Without<Kinematic>>,
+  emitter_query: Query<&Emitter>,
+  drain_query: Query<&Drain>,
+  zone_query: Query<&Zone>,
+  mut history: ResMut<SceneHistory>,
+) {
+  let mut edited = false;
+  let mut loaded = false;
+  for edit in edits.read() {
+    match edit {
+      SceneEdited::Edit => edited = true,
+      SceneEdited::Loaded => loaded = true,
+    }
+  }
+  if !edited && !loaded {
+    return;
+  }
+
+  let snapshot = capture(&obstacle_query, &emitter_query, &drain_query, &zone_query);
+  if loaded {
+    *history = SceneHistory { current: snapshot, ..default() };
+  } else {
+    let previous = std::mem::replace(&mut history.current, snapshot);
+    history.undo.push(previous);
+    history.redo.clear();
+  }
+}
+
+pub fn capture_initial_scene(
+  obstacle_query: Query<(&Obstacle, &Transform), Without<Kinematic>>,
+  emitter_query: Query<&Emitter>,
+  drain_query: Query<&Drain>,
+  zone_query: Query<&Zone>,
+  mut history: ResMut<SceneHistory>,
+) {
+  history.current = capture(&obstacle_query, &emitter_query, &drain_query, &zone_query);
+}
+
+/// Ctrl+Z and Ctrl+Y (or Ctrl+Shift+Z) step through the history.
+pub fn undo_redo(
+  mut commands: Commands,
+  keys: Res<ButtonInput<KeyCode>>,
+  fixture_query: Query<Entity, (Or<(With<Obstacle>, With<Emitter>, With<Drain>, With<Zone>)>, Without<Kinematic>)>,
+  mut history: ResMut<SceneHistory>,
+) {
+  if !keys.any_pressed([KeyCode::ControlLeft, KeyCode::ControlRight]) {
+    return;
+  }
+  let shift = keys.any_pressed([KeyCode::ShiftLeft, KeyCode::ShiftRight]);
+  let undo = keys.just_pressed(KeyCode::KeyZ) && !shift;
+  let redo = keys.just_pressed(KeyCode::KeyY) || (keys.just_pressed(KeyCode::KeyZ) && shift);
+
+  let history = &mut *history;
+  let (from, to) = if undo {
+    (&mut history.undo, &mut history.redo)
+  } else if redo {
+    (&mut history.redo, &mut history.undo)
+  } else {
+    return;
+  };
+
+  let Some(snapshot) = from.pop() else {
+    return;
+  };
+  to.push(std::mem::replace(&mut history.current, snapshot.clone()));
+
+  for entity in &fixture_query {
+    commands.entity(entity).despawn_recursive();
+  }
+  for (obstacle, transform) in snapshot.obstacles {
+    commands.spawn((obstacle, transform));
+  }
+  for emitter in snapshot.emitters {
+    commands.spawn(emitter);
+  }
+  for drain in snapshot.drains {
+    commands.spawn(drain);
+  }
+  for zone in snapshot.zones {
+    commands.spawn(zone);
+  }
+}
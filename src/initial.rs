@@ -0,0 +1,118 @@
+use std::{fs, io, path::Path};
+
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+  domain::{DomainId, Domains},
+  ParticleSpawn, SimulationConfig, COLOR,
+};
+
+/// One particle of a starting layout, in world space. Without a `domain`,
+/// it goes in whichever domain's column contains it.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default)]
+pub struct InitialParticle {
+  pub x: f32,
+  pub y: f32,
+  #[serde(default)]
+  pub z: f32,
+  #[serde(default)]
+  pub vx: f32,
+  #[serde(default)]
+  pub vy: f32,
+  #[serde(default)]
+  pub vz: f32,
+  #[serde(default)]
+  pub domain: Option<usize>,
+}
+
+impl InitialParticle {
+  pub fn position(&self) -> Vec3 {
+    Vec3::new(self.x, self.y, self.z)
+  }
+
+  pub fn velocity(&self) -> Vec3 {
+    Vec3::new(self.vx, self.vy, self.vz)
+  }
+}
+
+/// Particle positions and velocities loaded from a file, spawned instead of
+/// a scenario's random fill, e.g. for a logo or a pre-settled pool.
+///
+/// A `.ron` file is a list of `(x: 0.0, y: 10.0, vy: -50.0)` entries. A
+/// `.csv` file has a header naming its columns: `x` and `y`, and optionally
+/// `z`, `vx`, `vy`, `vz` and `domain`; any others are ignored. If it has a
+/// `step` column, like the files `--export` writes, only the last step is
+/// used, so a settled run can be picked up where it ended.
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+pub struct InitialConditions(pub Vec<InitialParticle>);
+
+impl InitialConditions {
+  pub fn load(path: &Path) -> io::Result<Self> {
+    let text = fs::read_to_string(path)?;
+    match path.extension().and_then(|ext| ext.to_str()) {
+      Some("ron") => ron::from_str(&text).map(InitialConditions).map_err(io::Error::other),
+      Some("csv") => parse_csv(&text).map(InitialConditions).map_err(io::Error::other),
+      _ => Err(io::Error::other("expected a .ron or .csv file")),
+    }
+  }
+
+  pub fn spawn(
+    &self,
+    commands: &mut Commands,
+    meshes: &mut Assets<Mesh>,
+    materials: &mut Assets<ColorMaterial>,
+    domains: &Domains,
+    config: &SimulationConfig,
+  ) {
+    for particle in &self.0 {
+      let domain = particle
+        .domain
+        .or_else(|| domains.containing(particle.position().truncate()))
+        .filter(|&domain| domain < domains.params.len())
+        .unwrap_or_default();
+      ParticleSpawn {
+        position: particle.position(),
+        velocity: particle.velocity(),
+        domain: DomainId(domain),
+        color: COLOR,
+        size: config.particle_size,
+      }.spawn(commands, meshes, materials);
+    }
+  }
+}
+
+fn parse_csv(text: &str) -> Result<Vec<InitialParticle>, String> {
+  let mut lines = text.lines().enumerate().filter(|(_, line)| !line.trim().is_empty());
+  let (_, header) = lines.next().ok_or("the file is empty")?;
+  let columns: Vec<&str> = header.split(',').map(str::trim).collect();
+  let column = |name: &str| columns.iter().position(|column| *column == name);
+  let (Some(x), Some(y)) = (column("x"), column("y")) else {
+    return Err("the header needs x and y columns".to_owned());
+  };
+  let optional = ["z", "vx", "vy", "vz"].map(column);
+  let (domain, step) = (column("domain"), column("step"));
+
+  let mut rows = Vec::new();
+  for (n, line) in lines {
+    let fields: Vec<&str> = line.split(',').map(str::trim).collect();
+    let number = |index: usize| -> Result<f32, String> {
+      let field = fields.get(index).ok_or_else(|| format!("line {}: missing {}", n + 1, columns[index]))?;
+      field.parse().map_err(|_| format!("line {}: {} is not a number: {field}", n + 1, columns[index]))
+    };
+    let [z, vx, vy, vz] = optional.map(|index| index.map_or(Ok(0.0), number));
+    let particle = InitialParticle {
+      x: number(x)?,
+      y: number(y)?,
+      z: z?,
+      vx: vx?,
+      vy: vy?,
+      vz: vz?,
+      domain: domain.map(&number).transpose()?.map(|domain| domain as usize),
+    };
+    rows.push((step.map(&number).transpose()?.unwrap_or_default(), particle));
+  }
+
+  let last = rows.iter().map(|(step, _)| *step).fold(f32::MIN, f32::max);
+  Ok(rows.into_iter().filter(|(step, _)| *step == last).map(|(_, particle)| particle).collect())
+}
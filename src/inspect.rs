@@ -0,0 +1,128 @@
+use bevy::prelude::*;
+#[cfg(feature = "render")]
+use bevy::window::PrimaryWindow;
+#[cfg(feature = "egui")]
+use bevy_egui::{egui, EguiContexts};
+
+#[cfg(feature = "render")]
+use crate::{
+  follow::{cursor_world_position, nearest_particle, FollowCamera},
+  Particle,
+};
+#[cfg(feature = "egui")]
+use crate::{domain::DomainId, follow::PICK_RADIUS, solver::SimulationState, theme::Theme, SimulationConfig};
+
+/// The particle shown in the inspector, with the accelerations the solver
+/// applied to it in the last step.
+#[derive(Component, Default)]
+pub struct Inspected {
+  pub pressure: Vec3,
+  pub viscosity: Vec3,
+}
+
+#[cfg(feature = "render")]
+/// With the inspect tool, left click moves the inspector to the particle
+/// under the cursor, or closes it over empty space.
+pub fn pick_inspected(
+  mut commands: Commands,
+  mouse_buttons: Res<ButtonInput<MouseButton>>,
+  window_query: Query<&Window, With<PrimaryWindow>>,
+  camera_query: Query<(&Camera, &GlobalTransform), With<FollowCamera>>,
+  particle_query: Query<(Entity, &Particle)>,
+  inspected_query: Query<Entity, With<Inspected>>,
+) {
+  if !mouse_buttons.just_pressed(MouseButton::Left) {
+    return;
+  }
+
+  for entity in &inspected_query {
+    commands.entity(entity).remove::<Inspected>();
+  }
+
+  let Some(cursor) = cursor_world_position(&window_query, &camera_query) else {
+    return;
+  };
+
+  let particles = particle_query.iter().map(|(entity, particle)| (entity, particle.position.truncate()));
+  if let Some(entity) = nearest_particle(particles, cursor) {
+    commands.entity(entity).insert(Inspected::default());
+  }
+}
+
+#[cfg(feature = "egui")]
+pub fn inspector_panel(
+  mut commands: Commands,
+  mut contexts: EguiContexts,
+  particle_query: Query<(Entity, &Particle, &DomainId, Option<&Inspected>)>,
+  state: Res<SimulationState>,
+  config: Res<SimulationConfig>,
+  mut gizmos: Gizmos,
+  theme: Res<Theme>,
+) {
+  let Some((entity, particle, domain, Some(inspected))) = particle_query
+    .iter()
+    .find(|(_, _, _, inspected)| inspected.is_some())
+  else {
+    return;
+  };
+
+  let neighbours = particle_query
+    .iter()
+    .filter(|(other, other_particle, other_domain, _)| {
+      *other != entity
+        && *other_domain == domain
+        && other_particle.position.distance(particle.position) < config.smoothing_radius
+    })
+    .count();
+
+  gizmos.circle_2d(particle.position.truncate(), PICK_RADIUS, theme.foreground);
+  gizmos.circle_2d(particle.position.truncate(), config.smoothing_radius, theme.foreground.with_alpha(0.3));
+
+  let vector = |v: Vec3| format!("({:.1}, {:.1})", v.x, v.y);
+  let mut open = true;
+
+  egui::Window::new("Inspector")
+    .open(&mut open)
+    .default_pos([10.0, 480.0])
+    .resizable(false)
+    .show(contexts.ctx_mut(), |ui| {
+      egui::Grid::new("inspector").num_columns(2).show(ui, |ui| {
+        ui.label("entity");
+        ui.label(format!("{entity}"));
+        ui.end_row();
+        ui.label("index");
+        ui.label(state.index_of(entity).map_or_else(|| "-".to_string(), |index| index.to_string()));
+        ui.end_row();
+        ui.label("domain");
+        ui.label(format!("{}", domain.0));
+        ui.end_row();
+        ui.label("position");
+        ui.label(vector(particle.position));
+        ui.end_row();
+        ui.label("velocity");
+        ui.label(vector(particle.velocity));
+        ui.end_row();
+        ui.label("density");
+        ui.label(format!("{:.4}", state.density_of(entity)));
+        ui.end_row();
+        ui.label("neighbours");
+        ui.label(format!("{neighbours}"));
+        ui.end_row();
+        ui.label("gravity");
+        ui.label(vector(config.gravity_vector()));
+        ui.end_row();
+        ui.label("pressure accel");
+        ui.label(vector(inspected.pressure));
+        ui.end_row();
+        if config.viscosity > 0.0 {
+          ui.label("viscosity accel");
+          ui.label(vector(inspected.viscosity));
+          ui.end_row();
+        }
+      });
+    });
+
+  if !open {
+    commands.entity(entity).remove::<Inspected>();
+  }
+}
@@ -0,0 +1,3 @@
+//! The SPH kernels, which live in `fluid-core` with the rest of the maths.
+
+pub use fluid_core::kernels::*;
@@ -0,0 +1,53 @@
+use bevy::prelude::*;
+
+/// Which groups a particle or obstacle belongs to, and which groups it
+/// interacts with. Two things only interact when each one's filters include
+/// the other's memberships. Without the component, a particle or obstacle is
+/// in the default group and interacts with everything.
+///
+/// Particles in different groups still push each other apart with pressure,
+/// but viscosity only acts within a group. Two fluids in separate groups
+/// therefore stay immiscible and slide past each other.
+#[derive(Component, Reflect, Clone, Copy, PartialEq, Eq, Debug, serde::Serialize, serde::Deserialize)]
+#[reflect(Component)]
+pub struct CollisionLayers {
+  pub memberships: u32,
+  pub filters: u32,
+}
+
+impl CollisionLayers {
+  pub const DEFAULT_GROUP: u32 = 1;
+  /// The container walls. They only stop particles whose filters include it.
+  pub const WALLS: u32 = 1 << 31;
+  pub const ALL: u32 = u32::MAX;
+
+  pub const DEFAULT: CollisionLayers = CollisionLayers { memberships: Self::DEFAULT_GROUP, filters: Self::ALL };
+  /// Interacts with nothing, not even the walls, e.g. for debug markers.
+  pub const NONE: CollisionLayers = CollisionLayers { memberships: 0, filters: 0 };
+
+  pub fn new(memberships: u32, filters: u32) -> Self {
+    CollisionLayers { memberships, filters }
+  }
+
+  pub fn of(layers: Option<&CollisionLayers>) -> Self {
+    layers.copied().unwrap_or_default()
+  }
+
+  pub fn interacts(self, other: CollisionLayers) -> bool {
+    self.filters & other.memberships != 0 && other.filters & self.memberships != 0
+  }
+
+  pub fn shares_group(self, other: CollisionLayers) -> bool {
+    self.memberships & other.memberships != 0
+  }
+
+  pub fn hits_walls(self) -> bool {
+    self.filters & Self::WALLS != 0
+  }
+}
+
+impl Default for CollisionLayers {
+  fn default() -> Self {
+    CollisionLayers::DEFAULT
+  }
+}
@@ -0,0 +1,820 @@
+//! A particle-based (SPH) fluid simulation for Bevy. `ParticlePlugin` adds
+//! the solver, rendering, tools and egui panels to an app; its fields pick
+//! the starting parameters, which stay tunable at runtime through the
+//! `SimulationConfig` resource. The panels need bevy_egui's `EguiPlugin`.
+//!
+//! The panels, the 3D box, the data export and the metrics endpoint sit
+//! behind the default `egui`, `3d`, `export` and `metrics` features, so a host
+//! app can leave out what it doesn't use. Everything that draws or takes
+//! input, `ParticlePlugin` with it, is the default `render` feature; without
+//! it only the solver is left. The opt-in `hot-reload` feature reloads the
+//! WGSL shaders when their files change.
+
+// bevy system signatures routinely exceed clippy's type complexity and argument count thresholds
+#![allow(clippy::type_complexity, clippy::too_many_arguments)]
+
+use bevy::{ecs::schedule::SystemConfigs, math::ops, prelude::*};
+#[cfg(feature = "render")]
+use bevy::render::view::RenderLayers;
+
+#[cfg(feature = "render")]
+pub mod alternate;
+#[cfg(feature = "render")]
+pub mod boat;
+pub mod boundaries;
+#[cfg(feature = "render")]
+pub mod capture;
+#[cfg(feature = "render")]
+pub mod checkpoint;
+#[cfg(feature = "render")]
+pub mod colormap;
+pub mod config_file;
+#[cfg(feature = "render")]
+pub mod console;
+pub mod determinism;
+#[cfg(feature = "render")]
+pub mod diagnostics;
+pub mod domain;
+#[cfg(feature = "render")]
+pub mod drag;
+#[cfg(feature = "render")]
+pub mod drain;
+#[cfg(feature = "render")]
+pub mod emitter;
+#[cfg(feature = "export")]
+pub mod export;
+#[cfg(feature = "render")]
+pub mod field;
+#[cfg(feature = "render")]
+pub mod follow;
+pub mod force_field;
+pub mod heat;
+#[cfg(feature = "render")]
+pub mod history;
+#[cfg(feature = "render")]
+pub mod initial;
+pub mod inspect;
+pub mod kernels;
+pub mod layers;
+pub mod material;
+#[cfg(feature = "render")]
+pub mod menu;
+#[cfg(feature = "render")]
+pub mod metaballs;
+#[cfg(feature = "metrics")]
+pub mod metrics;
+#[cfg(feature = "render")]
+pub mod minimap;
+pub mod obstacle;
+#[cfg(feature = "render")]
+pub mod paddle;
+#[cfg(feature = "egui")]
+pub mod panels;
+#[cfg(feature = "render")]
+pub mod pause;
+pub mod pin;
+#[cfg(feature = "render")]
+pub mod population;
+#[cfg(feature = "render")]
+pub mod presets;
+pub mod pressure;
+#[cfg(feature = "render")]
+pub mod probe;
+#[cfg(feature = "render")]
+pub mod query;
+#[cfg(feature = "render")]
+pub mod render;
+#[cfg(feature = "render")]
+pub mod replay;
+pub mod rng;
+#[cfg(feature = "render")]
+pub mod rollback;
+#[cfg(feature = "render")]
+pub mod scenario;
+#[cfg(feature = "render")]
+pub mod scene;
+pub mod schema;
+#[cfg(feature = "render")]
+pub mod script;
+#[cfg(feature = "hot-reload")]
+pub mod shaders;
+#[cfg(feature = "render")]
+pub mod slots;
+pub mod solver;
+#[cfg(feature = "render")]
+pub mod spawn;
+#[cfg(feature = "render")]
+pub mod theme;
+#[cfg(feature = "3d")]
+pub mod three_d;
+#[cfg(feature = "render")]
+pub mod tilt;
+#[cfg(feature = "render")]
+pub mod timeline;
+#[cfg(feature = "render")]
+pub mod tools;
+#[cfg(feature = "render")]
+pub mod touch;
+#[cfg(feature = "render")]
+pub mod ui;
+pub mod zone;
+
+use domain::{DomainId, Domains, DEFAULT_DOMAIN_SIZE};
+use kernels::Dimension;
+use solver::{
+  apply_pressure_force, apply_viscosity, gravity, index_particles, resolve_boundaries, update_density, SimulationState,
+};
+
+const PARTICLE_SIZE: f32 = 2.0;
+const GRAVITY_FACTOR: f32 = 500.0;
+const COLLISION_DAMPENING: f32 = 0.5; // [0,1]
+const RESTITUTION: f32 = 1.0; // [0,1]
+const SMOOTHING_RADIUS: f32 = 20.0;
+const MASS: f32 = 1.0;
+const TARGET_DENSITY: f32 = 0.4;
+const PRESSURE_MULTIPLIER: f32 = 6500.0;
+const VISCOSITY: f32 = 0.0;
+const BUOYANCY: f32 = 20.0;
+const THERMAL_DIFFUSION: f32 = 2.0;
+const COOLING: f32 = 0.1;
+const COLOR: Color = Color::hsl(190.0, 1.0, 0.5);
+
+#[derive(Resource, Clone, Copy, PartialEq, Eq, Debug)]
+pub enum SimulationMode {
+  TwoD,
+  ThreeD,
+}
+
+impl SimulationMode {
+  pub fn dimension(self) -> Dimension {
+    match self {
+      SimulationMode::TwoD => Dimension::Two,
+      SimulationMode::ThreeD => Dimension::Three,
+    }
+  }
+}
+
+/// Physics parameters the systems read every frame, so they can be tuned
+/// while the simulation runs. Densities and stiffness are 2D reference values
+/// that `Dimension` rescales for 3D. Saved presets leave out fields added
+/// since, which fall back to their defaults.
+#[derive(Resource, Reflect, Clone, PartialEq, Debug, serde::Serialize, serde::Deserialize)]
+#[reflect(Resource)]
+#[serde(default)]
+pub struct SimulationConfig {
+  pub gravity: f32,
+  pub gravity_angle: f32, // radians anticlockwise from straight down
+  pub smoothing_radius: f32,
+  pub target_density: f32,
+  pub pressure_multiplier: f32,
+  pub viscosity: f32,
+  pub restitution: f32, // [0,1]
+  pub collision_dampening: f32, // [0,1]
+  pub buoyancy: f32, // upward acceleration per degree above ambient
+  pub thermal_diffusion: f32,
+  pub cooling: f32, // fraction of excess heat lost per second
+  pub particle_size: f32, // radius particles are drawn and collide at
+  pub mass: f32, // each particle's contribution to density
+  pub container: domain::ContainerShape,
+  pub pressure_solver: pressure::PressureMethod,
+}
+
+impl SimulationConfig {
+  pub const FIELDS: [&'static str; 13] = [
+    "gravity", "gravity_angle", "smoothing_radius", "target_density", "pressure_multiplier", "viscosity",
+    "restitution", "collision_dampening", "buoyancy", "thermal_diffusion", "cooling", "particle_size", "mass",
+  ];
+
+  /// A parameter by its field name, for setting it from the console.
+  pub fn field_mut(&mut self, name: &str) -> Option<&mut f32> {
+    match name {
+      "gravity" => Some(&mut self.gravity),
+      "gravity_angle" => Some(&mut self.gravity_angle),
+      "smoothing_radius" => Some(&mut self.smoothing_radius),
+      "target_density" => Some(&mut self.target_density),
+      "pressure_multiplier" => Some(&mut self.pressure_multiplier),
+      "viscosity" => Some(&mut self.viscosity),
+      "restitution" => Some(&mut self.restitution),
+      "collision_dampening" => Some(&mut self.collision_dampening),
+      "buoyancy" => Some(&mut self.buoyancy),
+      "thermal_diffusion" => Some(&mut self.thermal_diffusion),
+      "cooling" => Some(&mut self.cooling),
+      "particle_size" => Some(&mut self.particle_size),
+      "mass" => Some(&mut self.mass),
+      _ => None,
+    }
+  }
+
+  pub fn gravity_vector(&self) -> Vec3 {
+    let (sin, cos) = ops::sin_cos(self.gravity_angle);
+    Vec3::new(sin, -cos, 0.0) * self.gravity
+  }
+}
+
+impl Default for SimulationConfig {
+  fn default() -> Self {
+    SimulationConfig {
+      gravity: GRAVITY_FACTOR,
+      gravity_angle: 0.0,
+      smoothing_radius: SMOOTHING_RADIUS,
+      target_density: TARGET_DENSITY,
+      pressure_multiplier: PRESSURE_MULTIPLIER,
+      viscosity: VISCOSITY,
+      restitution: RESTITUTION,
+      collision_dampening: COLLISION_DAMPENING,
+      buoyancy: BUOYANCY,
+      thermal_diffusion: THERMAL_DIFFUSION,
+      cooling: COOLING,
+      particle_size: PARTICLE_SIZE,
+      mass: MASS,
+      container: default(),
+      pressure_solver: default(),
+    }
+  }
+}
+
+/// Where per-particle forces accumulate each frame, after the frame's input
+/// and scene edits and before `FluidSet::Integrate` moves the particles. Systems
+/// added to it, from this crate or another, add `acceleration * dt` to
+/// `Particle::velocity`; it only runs while the simulation does.
+#[derive(SystemSet, Clone, PartialEq, Eq, Hash, Debug)]
+pub struct ForceSet;
+
+/// The solver's stages, which run one after another each simulated frame in
+/// the order `Integrate`, `Collide`, `BuildGrid`, `Density`, `Forces`, and
+/// then `Sync` on every frame, paused or not. Order your own systems against
+/// these rather than against the solver's systems.
+#[derive(SystemSet, Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum FluidSet {
+  /// Sorts the particles into their domains for the neighbour loops.
+  BuildGrid,
+  /// Estimates each particle's density at its predicted position.
+  Density,
+  /// Pressure, viscosity, surface tension and heat, from the densities.
+  Forces,
+  /// Moves the particles by their velocities; `ForceSet` runs just before.
+  Integrate,
+  /// Holds particles in their container and out of obstacles, and is where
+  /// `solver::detect_collisions` belongs if you schedule it.
+  Collide,
+  /// Copies `Particle::position` to the `Transform` for drawing.
+  Sync,
+}
+
+#[cfg(feature = "render")]
+/// The whole simulation as one plugin. The fields are only starting values,
+/// inserted as resources that every system reads back each frame.
+pub struct ParticlePlugin {
+  pub config: SimulationConfig,
+  pub spawn: scenario::SpawnOptions,
+  pub mode: SimulationMode,
+  pub domains: Domains,
+}
+
+#[cfg(feature = "render")]
+impl Default for ParticlePlugin {
+  fn default() -> Self {
+    ParticlePlugin {
+      config: default(),
+      spawn: default(),
+      mode: SimulationMode::TwoD,
+      domains: Domains::single(DEFAULT_DOMAIN_SIZE),
+    }
+  }
+}
+
+/// Just the physics, for headless hosts like the tests and the Python
+/// bindings: the starting resources, the solver's state and events, and
+/// `solver_systems` in `Update`. `ParticlePlugin` builds on it, so both step
+/// the same solver; the host brings its own clock.
+pub struct SolverPlugin {
+  pub config: SimulationConfig,
+  pub mode: SimulationMode,
+  pub domains: Domains,
+}
+
+impl Default for SolverPlugin {
+  fn default() -> Self {
+    SolverPlugin {
+      config: default(),
+      mode: SimulationMode::TwoD,
+      domains: Domains::single(DEFAULT_DOMAIN_SIZE),
+    }
+  }
+}
+
+impl Plugin for SolverPlugin {
+  fn build(&self, app: &mut App) {
+    app
+      .insert_resource(self.config.clone())
+      .insert_resource(self.mode)
+      .insert_resource(self.domains.clone())
+      .init_resource::<SimulationState>()
+      // for bevy-inspector-egui and other reflection-based tooling
+      .register_type::<Particle>()
+      .register_type::<DomainId>()
+      .register_type::<layers::CollisionLayers>()
+      .register_type::<material::FluidMaterial>()
+      .register_type::<SimulationConfig>()
+      .register_type::<SimulationState>()
+      .add_event::<solver::ParticleCollisionEvent>()
+      .add_event::<solver::BoundaryHitEvent>()
+      .add_systems(Update, solver_systems());
+  }
+}
+
+/// The solver's systems, each in its `FluidSet` and chained in that order so
+/// apps that don't configure the sets step them in the same order as
+/// `ParticlePlugin`.
+pub fn solver_systems() -> SystemConfigs {
+  (
+    gravity.in_set(FluidSet::Integrate),
+    resolve_boundaries.in_set(FluidSet::Collide),
+    index_particles.in_set(FluidSet::BuildGrid),
+    update_density.in_set(FluidSet::Density),
+    (apply_pressure_force, apply_viscosity, solver::apply_surface_tension, heat::update_temperature)
+      .chain()
+      .in_set(FluidSet::Forces),
+  )
+    .chain()
+}
+
+#[cfg(feature = "render")]
+impl Plugin for ParticlePlugin {
+  fn build(&self, app: &mut App) {
+    app
+      .add_plugins(SolverPlugin { config: self.config.clone(), mode: self.mode, domains: self.domains.clone() })
+      .insert_resource(self.spawn.clone())
+      .insert_resource(rng::SimulationRng::new(self.spawn.seed))
+      .init_resource::<theme::Theme>()
+      .init_resource::<scenario::Scenario>()
+      .add_plugins((
+        bevy::sprite::Material2dPlugin::<metaballs::SplatMaterial>::default(),
+        bevy::sprite::Material2dPlugin::<metaballs::FluidSurfaceMaterial>::default(),
+        bevy::sprite::Material2dPlugin::<obstacle::ObstacleMaterial>::default(),
+      ))
+      .insert_gizmo_config(minimap::MinimapGizmos, minimap::minimap_gizmo_config())
+      .init_resource::<ui::UiFocus>()
+      .init_resource::<timeline::Timeline>()
+      .init_resource::<rollback::RollbackBuffer>()
+      .init_resource::<alternate::AlternateConfig>()
+      .init_state::<pause::SimState>()
+      .init_resource::<pause::SingleStep>()
+      .init_resource::<pause::AutoPause>()
+      .init_resource::<drag::DragCursor>()
+      .init_resource::<follow::CameraAnchor>()
+      .init_resource::<domain::ResizePolicy>()
+      .init_resource::<diagnostics::DiagnosticsHistory>()
+      .init_resource::<population::PopulationTarget>()
+      .init_resource::<menu::ScenarioMenu>()
+      .init_resource::<presets::Presets>()
+      .add_event::<scenario::LoadScenario>()
+      .init_resource::<scene::LoadedScene>()
+      .add_event::<scene::LoadScene>()
+      .add_event::<history::SceneEdited>()
+      .init_resource::<history::SceneHistory>()
+      .add_systems(PostStartup, history::capture_initial_scene)
+      .add_systems(PostUpdate, history::record_scene_edits)
+      .init_resource::<probe::Probe>()
+      .init_resource::<slots::StateSlots>()
+      .init_resource::<tools::Tool>()
+      .init_resource::<tools::ObstacleTool>()
+      .init_resource::<tools::EmitterTool>()
+      .init_resource::<tools::DrainTool>()
+      .init_resource::<tools::ZoneTool>()
+      .init_resource::<tools::PinTool>()
+      .init_resource::<tools::ImpulseTool>()
+      .init_resource::<tools::StirTool>()
+      .init_resource::<tools::HeatTool>()
+      .init_resource::<tools::RulerTool>()
+      .init_resource::<query::ParticleIndex>()
+      .add_event::<ResetSimulation>()
+      .add_event::<drain::DrainedEvent>()
+      .add_event::<checkpoint::CheckpointCommand>()
+      .add_event::<console::ConsoleCommand>()
+      .add_event::<spawn::SpawnParticles>()
+      .add_event::<spawn::DespawnParticles>()
+      .init_resource::<console::Console>()
+      .init_resource::<replay::InputRecorder>()
+      .add_systems(
+        PreUpdate,
+        replay::replay_input
+          .after(bevy::input::InputSystem)
+          .run_if(resource_exists::<replay::InputReplay>),
+      )
+      .add_systems(Last, (
+        replay::record_input.run_if(not(resource_exists::<replay::InputReplay>)),
+        replay::advance_replay.run_if(resource_exists::<replay::InputReplay>),
+      ))
+      .init_resource::<render::RenderSettings>()
+      .init_resource::<render::TransformSync>()
+      .init_resource::<colormap::ColorMode>()
+      .init_resource::<colormap::ColorRange>()
+      .configure_sets(
+        Update,
+        (
+          (FluidSet::Integrate, FluidSet::Collide, FluidSet::BuildGrid, FluidSet::Density, FluidSet::Forces)
+            .chain()
+            .after(pause::toggle_pause)
+            .run_if(pause::simulation_running),
+          FluidSet::Sync.after(FluidSet::Forces),
+        ),
+      )
+      .configure_sets(
+        Update,
+        ForceSet.after(reset_simulation).before(FluidSet::Integrate).run_if(pause::simulation_running),
+      )
+      .add_systems(Startup, (
+        setup.run_if(resource_equals(SimulationMode::TwoD)),
+        domain::setup_labels,
+        force_field::spawn_global_wind,
+        colormap::setup_legend,
+      ))
+      .add_systems(Update, (
+        (pause::toggle_pause, touch::double_tap_pause.after(ui::track_pointer)),
+        pause::pause_on_focus_loss,
+        pause::time_scale_hotkeys,
+        replay::toggle_recording
+          .before(scenario::load_scenario)
+          .run_if(not(resource_exists::<replay::InputReplay>)),
+        tilt::rotate_gravity.before(gravity),
+        alternate::hold_alternate
+          .before(ui::PanelSet)
+          .run_if(not(resource_exists::<replay::InputReplay>)),
+        (script::watch_script, script::run_script.run_if(pause::simulation_running))
+          .chain()
+          .before(console::run_console_commands)
+          .run_if(resource_exists::<script::SceneScript>),
+        (reset_hotkey, 
+          console::toggle_console.run_if(ui::windowed),
+          (timeline::toggle_timeline, timeline::scrub_timeline)
+            .chain()
+            .run_if(ui::windowed)
+            .run_if(resource_equals(SimulationMode::TwoD)),
+          ui::track_pointer.run_if(ui::windowed),
+          console::run_console_commands,
+          reset_simulation).chain().before(gravity),
+        (
+          emitter::emit_particles.before(FluidSet::BuildGrid),
+          emitter::expire_particles.before(FluidSet::BuildGrid),
+          drain::drain_particles.after(FluidSet::Collide).before(FluidSet::BuildGrid),
+          paddle::drive_paddle.before(gravity),
+          force_field::apply_force_fields.in_set(ForceSet),
+          population::adjust_particle_count
+            .before(FluidSet::BuildGrid)
+            .run_if(resource_equals(SimulationMode::TwoD)),
+          boat::update_boats.before(gravity),
+          rollback::record_rollback
+            .after(apply_viscosity)
+            .run_if(resource_equals(SimulationMode::TwoD)),
+          timeline::record_timeline
+            .after(apply_viscosity)
+            .run_if(resource_equals(SimulationMode::TwoD)),
+        ).after(pause::toggle_pause).run_if(pause::simulation_running),
+        domain::draw_domain_bounds.run_if(resource_equals(SimulationMode::TwoD)),
+        domain::handle_window_resize.before(FluidSet::Integrate).run_if(resource_equals(SimulationMode::TwoD)),
+        diagnostics::record_diagnostics
+          .after(FluidSet::Forces)
+          .run_if(pause::simulation_running)
+          .run_if(diagnostics::diagnostics_open),
+        render::toggle_render_settings,
+        capture::screenshot_hotkey,
+        (
+          render::sync_transforms.in_set(FluidSet::Sync),
+          render::orient_particles.after(render::sync_transforms),
+          query::index_regions.in_set(FluidSet::Sync),
+        ),
+        (render::blend_source_colors,
+          colormap::apply_color_mode.after(update_density),
+          render::update_particle_materials).chain(),
+        colormap::cycle_color_mode,
+        colormap::update_legend.after(colormap::apply_color_mode),
+        ))
+      .add_systems(Update, (spawn::despawn_particles, spawn::spawn_particles)
+        .chain()
+        .after(console::run_console_commands)
+        .after(scene::load_scene)
+        .after(population::adjust_particle_count)
+        .before(FluidSet::BuildGrid))
+      .add_systems(Last, pause::finish_step)
+      .add_systems(Startup, (
+        metaballs::setup_metaballs,
+        theme::setup_background,
+        minimap::setup_minimap,
+      ).after(setup).run_if(resource_equals(SimulationMode::TwoD)))
+      .add_systems(Update, (
+        theme::cycle_theme,
+        theme::apply_theme.after(theme::cycle_theme),
+        theme::fit_background,
+      ))
+      .add_systems(Update, (
+        metaballs::attach_splats,
+        metaballs::toggle_metaballs.after(metaballs::attach_splats),
+        metaballs::resize_density_texture,
+        obstacle::attach_obstacle_visuals,
+        obstacle::update_obstacle_materials,
+      ).run_if(resource_equals(SimulationMode::TwoD)))
+      .add_systems(Update, (
+        follow::tag_particle
+          .after(ui::track_pointer)
+          .run_if(ui::pointer_free)
+          .run_if(resource_equals(tools::Tool::Follow)),
+        inspect::pick_inspected
+          .after(ui::track_pointer)
+          .run_if(ui::pointer_free)
+          .run_if(resource_equals(tools::Tool::Inspect)),
+        (
+          tools::obstacle_tool
+            .after(ui::track_pointer)
+            .run_if(resource_equals(tools::Tool::Obstacle)),
+          tools::emitter_tool
+            .after(ui::track_pointer)
+            .run_if(resource_equals(tools::Tool::Emitter)),
+          tools::drain_tool
+            .after(ui::track_pointer)
+            .run_if(resource_equals(tools::Tool::Drain)),
+          tools::impulse_tool
+            .after(ui::track_pointer)
+            .before(gravity)
+            .run_if(resource_equals(tools::Tool::Impulse)),
+          tools::stir_tool
+            .after(ui::track_pointer)
+            .before(gravity)
+            .run_if(ui::pointer_free)
+            .run_if(pause::simulation_running)
+            .run_if(resource_equals(tools::Tool::Stir)),
+          tools::heat_tool
+            .after(ui::track_pointer)
+            .before(gravity)
+            .run_if(ui::pointer_free)
+            .run_if(pause::simulation_running)
+            .run_if(resource_equals(tools::Tool::Heat)),
+          tools::ruler_tool
+            .after(ui::track_pointer)
+            .run_if(resource_equals(tools::Tool::Ruler)),
+          tools::zone_tool
+            .after(ui::track_pointer)
+            .run_if(resource_equals(tools::Tool::Zone)),
+          tools::pin_tool
+            .after(ui::track_pointer)
+            .run_if(resource_equals(tools::Tool::Pin)),
+          tools::resize_brush
+            .after(ui::track_pointer)
+            .run_if(ui::pointer_free)
+            .run_if(tools::brush_active),
+        ),
+        drain::draw_drains,
+        zone::draw_zones,
+        pin::draw_pinned,
+        menu::toggle_menu.run_if(ui::windowed).before(scenario::load_scenario),
+        probe::toggle_probe,
+        slots::quick_slots.before(gravity),
+        rollback::apply_rollback.after(console::run_console_commands).before(gravity),
+        (checkpoint::checkpoint_hotkey, checkpoint::run_checkpoints)
+          .chain()
+          .after(console::run_console_commands)
+          .before(gravity),
+        history::undo_redo.after(ui::track_pointer),
+        (
+          scenario::scenario_hotkeys,
+          scenario::load_scenario,
+          scene::load_scene,
+          scene::continue_scene_load.run_if(resource_exists::<scene::SceneLoading>),
+        )
+          .chain()
+          .after(ui::track_pointer)
+          .before(gravity),
+        drag::drag_particle.after(ui::track_pointer).before(gravity),
+        follow::follow_tagged.after(follow::tag_particle),
+        follow::zoom_camera
+          .after(ui::track_pointer)
+          .run_if(ui::pointer_free)
+          .run_if(not(tools::brush_active)),
+        touch::touch_interaction
+          .after(ui::track_pointer)
+          .before(follow::follow_tagged)
+          .before(gravity),
+        minimap::update_minimap.after(follow::follow_tagged).after(follow::zoom_camera),
+      ).run_if(resource_equals(SimulationMode::TwoD)));
+
+    #[cfg(feature = "egui")]
+    app
+      .configure_sets(
+        Update,
+        ui::PanelSet
+          .after(reset_hotkey)
+          .after(console::toggle_console)
+          .after(timeline::toggle_timeline)
+          .before(timeline::scrub_timeline)
+          .before(ui::track_pointer)
+          .run_if(ui::windowed),
+      )
+      .add_systems(
+        PreUpdate,
+        panels::release_keyboard
+          .after(bevy::input::InputSystem)
+          .after(bevy_egui::EguiPreUpdateSet::BeginPass)
+          .before(replay::replay_input)
+          .run_if(ui::windowed),
+      )
+      .add_systems(Update, (
+        (
+          (panels::parameter_panel, panels::tool_panel, panels::wind_panel).chain(),
+          console::console_panel,
+          timeline::timeline_panel.run_if(resource_equals(SimulationMode::TwoD)),
+        ).chain().in_set(ui::PanelSet),
+        (
+          menu::scenario_menu
+            .after(menu::toggle_menu)
+            .after(panels::tool_panel)
+            .before(ui::track_pointer)
+            .before(scenario::load_scenario),
+          inspect::inspector_panel.after(apply_viscosity),
+          (diagnostics::toggle_diagnostics, diagnostics::diagnostics_panel.after(apply_viscosity)).chain(),
+          probe::probe_readout.after(update_density).after(ui::track_pointer),
+          scene::loading_panel.run_if(resource_exists::<scene::SceneLoading>),
+        ).run_if(ui::windowed).run_if(resource_equals(SimulationMode::TwoD)),
+      ));
+
+    #[cfg(feature = "3d")]
+    app
+      .add_systems(Startup, three_d::setup_3d.run_if(resource_equals(SimulationMode::ThreeD)))
+      .add_systems(Update, (
+        three_d::orbit_camera.after(ui::track_pointer).run_if(ui::pointer_free),
+        three_d::draw_box,
+      ).run_if(resource_equals(SimulationMode::ThreeD)));
+
+    #[cfg(feature = "hot-reload")]
+    app.add_systems(Update, shaders::log_shader_reloads);
+
+    #[cfg(target_arch = "wasm32")]
+    app
+      .add_systems(Startup, tilt::listen_device_orientation)
+      .add_systems(Update, tilt::apply_device_tilt.after(tilt::rotate_gravity).before(gravity));
+  }
+}
+
+/// A particle's physical state. `position` is the one source of truth for
+/// where it is: systems move particles by writing it, and
+/// `render::sync_transforms` copies it to the `Transform` for drawing.
+#[derive(Component, Reflect, Clone, serde::Serialize, serde::Deserialize)]
+#[reflect(Component)]
+pub struct Particle {
+  pub position: Vec3,
+  pub velocity: Vec3,
+  pub predicted_position: Vec3,
+  /// Where the last simulated step started, for interpolated drawing.
+  #[serde(default)]
+  pub previous_position: Vec3,
+  pub mass: f32,
+  pub temperature: f32, // relative to ambient
+}
+
+impl Particle {
+  /// A still particle at ambient temperature, with nothing predicted yet.
+  pub fn at_rest(position: Vec3, mass: f32) -> Self {
+    Self {
+      position,
+      velocity: Vec3::ZERO,
+      predicted_position: position,
+      previous_position: position,
+      mass,
+      temperature: 0.0,
+    }
+  }
+}
+
+#[cfg(feature = "render")]
+/// Everything needed to spawn a 2D particle, used both at startup and by
+/// systems that add particles while running.
+pub struct ParticleSpawn {
+  pub position: Vec3,
+  pub velocity: Vec3,
+  pub domain: DomainId,
+  pub color: Color,
+  pub size: f32,
+}
+
+#[cfg(feature = "render")]
+impl ParticleSpawn {
+  pub fn spawn(
+    self,
+    commands: &mut Commands,
+    meshes: &mut Assets<Mesh>,
+    materials: &mut Assets<ColorMaterial>,
+  ) -> Entity {
+    let particle = Particle { velocity: self.velocity, ..Particle::at_rest(self.position, self.size) };
+
+    let shape = meshes.add(Circle::new(self.size));
+
+    commands.spawn((
+      particle,
+      self.domain,
+      render::ParticleColor::new(self.color),
+      Mesh2d(shape),
+      MeshMaterial2d(materials.add(self.color)),
+      Transform::from_translation(self.position)
+    )).id()
+  }
+}
+
+#[cfg(feature = "render")]
+pub fn setup(
+  mut commands: Commands,
+  mut meshes: ResMut<Assets<Mesh>>,
+  mut materials: ResMut<Assets<ColorMaterial>>,
+  domains: Res<Domains>,
+  scenario: Res<scenario::Scenario>,
+  options: Res<scenario::SpawnOptions>,
+  config: Res<SimulationConfig>,
+  loaded: Res<scene::LoadedScene>,
+) {
+  commands.spawn((
+    Camera2d,
+    follow::FollowCamera,
+    domains.projection(),
+    RenderLayers::from_layers(&[0, render::MAIN_VIEW_LAYER]),
+  ));
+
+  match &loaded.0 {
+    Some(scene) => {
+      scene.spawn_particles(&mut commands, &mut meshes, &mut materials, &domains, &config);
+      scene.spawn_fixtures(&mut commands, &domains);
+    }
+    None => {
+      scenario.spawn_particles(&mut commands, &mut meshes, &mut materials, &domains, &config, &options);
+      scenario.spawn_fixtures(&mut commands, &domains, &config);
+    }
+  }
+
+  #[cfg(not(target_arch = "wasm32"))]
+  commands.spawn((
+    Text::new("Fluid Simulation"),
+    Node {
+      position_type: PositionType::Absolute,
+      top: Val::Px(12.0),
+      left: Val::Px(12.0),
+      ..default()
+    },
+  ));
+}
+
+#[cfg(feature = "render")]
+/// Despawns every particle and spawns the current scenario's starting
+/// particles again, keeping the config and any placed obstacles, emitters and
+/// drains. Sent by the R key and the parameter panel.
+#[derive(Event, Default)]
+pub struct ResetSimulation;
+
+#[cfg(feature = "render")]
+pub fn reset_hotkey(
+  keys: Res<ButtonInput<KeyCode>>,
+  mut resets: EventWriter<ResetSimulation>,
+) {
+  if keys.just_pressed(KeyCode::KeyR) {
+    resets.send_default();
+  }
+}
+
+#[cfg(feature = "render")]
+pub fn reset_simulation(
+  mut commands: Commands,
+  mut resets: EventReader<ResetSimulation>,
+  particle_query: Query<Entity, With<Particle>>,
+  mut meshes: ResMut<Assets<Mesh>>,
+  mut materials: ResMut<Assets<ColorMaterial>>,
+  #[cfg(feature = "3d")] mut standard_materials: ResMut<Assets<StandardMaterial>>,
+  mode: Res<SimulationMode>,
+  domains: Res<Domains>,
+  scenario: Res<scenario::Scenario>,
+  options: Res<scenario::SpawnOptions>,
+  config: Res<SimulationConfig>,
+  loaded: Res<scene::LoadedScene>,
+  mut rng: ResMut<rng::SimulationRng>,
+  loading: Option<Res<scene::SceneLoading>>,
+) {
+  if resets.is_empty() {
+    return;
+  }
+  resets.clear();
+  if loading.is_some() {
+    // the scene being loaded replaces the particles anyway
+    return;
+  }
+  rng.reseed(options.seed);
+
+  for entity in &particle_query {
+    commands.entity(entity).despawn_recursive();
+  }
+
+  match *mode {
+    SimulationMode::TwoD => match &loaded.0 {
+      Some(scene) => scene.spawn_particles(&mut commands, &mut meshes, &mut materials, &domains, &config),
+      None => scenario.spawn_particles(&mut commands, &mut meshes, &mut materials, &domains, &config, &options),
+    },
+    #[cfg(feature = "3d")]
+    SimulationMode::ThreeD => three_d::spawn_particles_3d(&mut commands, &mut meshes, &mut standard_materials, &options, &config),
+    #[cfg(not(feature = "3d"))]
+    SimulationMode::ThreeD => {}
+  }
+}
@@ -1,304 +1,264 @@
-use std::f32::consts::PI;
-use bevy::{prelude::*, window::PrimaryWindow, diagnostic::{FrameTimeDiagnosticsPlugin, LogDiagnosticsPlugin}};
-use rand::Rng;
+use std::time::Duration;
+
+use bevy::{
+  app::ScheduleRunnerPlugin,
+  diagnostic::{FrameTimeDiagnosticsPlugin, LogDiagnosticsPlugin},
+  prelude::*,
+  render::{settings::WgpuSettings, RenderPlugin},
+  time::TimeUpdateStrategy,
+  window::ExitCondition,
+  winit::WinitPlugin,
+};
+use clap::Parser;
+use fluid_simulation::{
+  capture, config_file,
+  determinism::{DeterminismPlugin, STRICT_MATH},
+  domain::{Domains, ResizePolicy, DEFAULT_DOMAIN_SIZE},
+  emitter,
+  initial::InitialConditions,
+  menu, obstacle, pause, replay, scenario,
+  scene::{LoadedScene, SceneFile},
+  script,
+  solver::{gravity, SimulationState},
+  ParticlePlugin, SimulationMode,
+};
+#[cfg(feature = "metrics")]
+use fluid_simulation::{
+  metrics::{self, MetricsEndpoint, SimulationMetrics},
+  FluidSet,
+};
+#[cfg(feature = "export")]
+use fluid_simulation::{
+  export::{self, DataExport, ExportFormat},
+  solver::apply_viscosity,
+};
+
+mod cli;
 
 fn main() {
-  App::new()
-    .add_plugins(DefaultPlugins)
-    .add_plugins(ParticlePlugin)
-    .add_plugins((FrameTimeDiagnosticsPlugin, LogDiagnosticsPlugin::default()))
-    .run();
-}
-
-const PARTICLE_SIZE: f32 = 2.0;
-const NUM_PARTICLES: i32 = 1500;
-const GRAVITY_FACTOR: f32 = 500.0;
-const COLLISION_DAMPENING: f32 = 0.5; // [0,1]
-const RESTITUTION: f32 = 1.0; // [0,1]
-const SMOOTHING_RADIUS: f32 = 20.0;
-const MASS: f32 = 1.0;
-const TARGET_DENSITY: f32 = 0.4;
-const PRESSURE_MULTIPLIER: f32 = 6500.0;
-const COLOR: Color = Color::hsl(190.0, 1.0, 0.5);
-
-
-#[derive(Resource)]
-pub struct SimulationState {
-  densities: Vec<f32>,
-}
-
-pub struct ParticlePlugin;
-
-impl Plugin for ParticlePlugin {
-  fn build(&self, app: &mut App) {
-    app
-      .insert_resource(SimulationState {
-          densities: vec![0.0; NUM_PARTICLES as usize],
-      })
-      .add_systems(Startup, setup)
-      .add_systems(Update, (
-        gravity, 
-        // detect_collisions,
-        (update_density, 
-          apply_pressure_force).chain(),
-        ));
+  // the browser has no command line, and panics would otherwise only say "unreachable"
+  #[cfg(target_arch = "wasm32")]
+  let args = {
+    console_error_panic_hook::set_once();
+    cli::Args::parse_from(["fluid-simulation"])
+  };
+  #[cfg(not(target_arch = "wasm32"))]
+  let args = cli::Args::parse();
+
+  #[cfg(feature = "3d")]
+  let mode = if args.three_d {
+    SimulationMode::ThreeD
+  } else {
+    SimulationMode::TwoD
+  };
+  #[cfg(not(feature = "3d"))]
+  let mode = SimulationMode::TwoD;
+
+  let session = args.replay.as_ref().map(|path| {
+    replay::Session::load(path).unwrap_or_else(|err| panic!("could not read session {}: {err}", path.display()))
+  });
+
+  let mut scenario = args.scenario.unwrap_or_default();
+  let mut scene_file = args.scene.as_ref().map(|path| {
+    SceneFile::load(path).unwrap_or_else(|err| panic!("could not read scene {}: {err}", path.display()))
+  });
+  if let Some(session) = &session {
+    (scenario, scene_file) = session.scene();
   }
-}
-
-#[derive(Component)]
-pub struct Particle {
-  pub position: Vec3,
-  pub velocity: Vec3,
-  pub predicted_position: Vec3,
-  pub mass: f32,
-}
 
-pub fn setup(
-  mut commands: Commands,
-  mut meshes: ResMut<Assets<Mesh>>,
-  mut materials: ResMut<Assets<ColorMaterial>>,
-  window_query: Query<&Window, With<PrimaryWindow>>
-) {
-  commands.spawn(Camera2d);
-
-  let window = window_query.get_single().unwrap();
-  let window_width = window.width();
-  let window_height = window.height();
-
-  for _ in 0..NUM_PARTICLES {
-    
-    let x = rand::thread_rng().gen_range(- window_width / 2.0 .. window_width / 2.0);
-    let y = rand::thread_rng().gen_range(- window_height / 2.0 .. window_height / 2.0);
-
-    let particle = Particle {
-      position: Vec3::new(x, y, 0.0),
-      velocity: Vec3::ZERO,
-      predicted_position: Vec3::ZERO,
-      mass: PARTICLE_SIZE
-    };
-
-    let shape = meshes.add(Circle::new(PARTICLE_SIZE));
-    
-    commands.spawn((
-      particle,
-      Mesh2d(shape),
-      MeshMaterial2d(materials.add(COLOR)),
-      Transform::from_xyz(x, y,0.0)
-    ));
-    
-    #[cfg(not(target_arch = "wasm32"))]
-    commands.spawn((
-      Text::new("Fluid Simulation"),
-      Node {
-        position_type: PositionType::Absolute,
-        top: Val::Px(12.0),
-        left: Val::Px(12.0),
-        ..default()
-      },
-    ));
+  let domain_size = session
+    .as_ref()
+    .and_then(|session| session.domain())
+    .or(args.domain)
+    .or(scene_file.as_ref().and_then(|scene| scene.domain))
+    .unwrap_or(DEFAULT_DOMAIN_SIZE);
+  let domains = if mode == SimulationMode::TwoD && args.compare {
+    Domains::comparison(domain_size)
+  } else if mode == SimulationMode::TwoD && args.tanks {
+    Domains::tanks(domain_size)
+  } else {
+    Domains::single(domain_size)
+  };
+
+  let mut config = scene_file.as_ref().and_then(|scene| scene.config.clone()).unwrap_or_default();
+  if let Some(gravity) = args.gravity {
+    config.gravity = gravity;
   }
-}
-
-pub fn gravity(
-  mut particle_query: Query<(&mut Transform, &mut Particle)>,
-  window_query: Query<&Window, With<PrimaryWindow>>,
-  time: Res<Time>,
-) {
-  for (mut transform, mut particle) in &mut particle_query {
-    particle.velocity += Vec3::NEG_Y * GRAVITY_FACTOR * time.delta_secs();
-
-    let velocity = particle.velocity;
-    particle.position += velocity * time.delta_secs();
-    transform.translation = particle.position;
-    
-    particle.predicted_position = particle.position + particle.velocity * time.delta_secs();
-
-    detect_boundaries(&mut particle, &window_query);
+  if let Some(solver) = args.solver {
+    config.pressure_solver = solver;
+  }
+  if let Some(container) = args.container.clone() {
+    config.container = container;
   }
-}
 
-fn detect_boundaries(
-  particle: &mut Particle, 
-  window_query: &Query<&Window, With<PrimaryWindow>>
-) {
-
-  let window = window_query.get_single().unwrap();
-  let window_width = window.width() / 2.0 - (2.0 * PARTICLE_SIZE);
-  let window_height = window.height() / 2.0 - (2.0 * PARTICLE_SIZE);
-  
-  if particle.position.y.abs() > window_height {
-    particle.position.y = window_height * particle.position.y.signum();
-    particle.velocity.y *= -COLLISION_DAMPENING;
+  let seed = args.seed.or(args.deterministic.then_some(0));
+  let mut spawn = scenario::SpawnOptions { seed, ..default() };
+  if let Some(particles) = args.particles {
+    spawn.particles = particles;
   }
 
-  if particle.position.x.abs() > window_width {
-    particle.position.x = window_width * particle.position.x.signum();
-    particle.velocity.x *= -COLLISION_DAMPENING;
+  if let Some(session) = &session {
+    (spawn, config) = session.start();
   }
-}
 
-pub fn detect_collisions(
-  mut particle_query: Query<(Entity, &Transform, &mut Particle)>,
-) {
-  let entities: Vec<(Entity, Vec3, Vec3, f32)> = particle_query
-    .iter()
-    .map(|(entity, transform, particle)| {
-        (entity, transform.translation, particle.velocity, particle.mass)
-    })
-    .collect();
-
-  let mut collisions = Vec::new();
-
-  for i in 0..entities.len() {
-    for j in (i + 1)..entities.len() {
-      let (e1, pos1, vel1, mass1) = entities[i];
-      let (e2, pos2, vel2, mass2) = entities[j];
-
-      let delta = pos1 - pos2;
-      let dist = delta.length();
-
-      // Check for collision
-      if dist < (mass1 + mass2) {
-        collisions.push((e1, e2, pos1, pos2, vel1, vel2, mass1, mass2));
-      }
-    }
+  // after the session, so sessions recorded before initial conditions were
+  // saved with them can still be given theirs
+  if let Some(path) = &args.initial {
+    let initial = InitialConditions::load(path)
+      .unwrap_or_else(|err| panic!("could not read initial conditions {}: {err}", path.display()));
+    spawn.initial = Some(initial);
   }
 
-  for (e1, e2, pos1, pos2, vel1, vel2, mass1, mass2) in collisions {
-    let (new_vel1, new_vel2) = elastic_collision(
-      mass1, mass2,
-      vel1, vel2,
-      pos1, pos2
-    );
+  let mut app = App::new();
+  app
+    .insert_resource(args.theme.clone().unwrap_or_default())
+    .insert_resource(scenario)
+    .insert_resource(LoadedScene(scene_file));
 
-    if let Ok((_, _, mut particle)) = particle_query.get_mut(e1) {
-      particle.velocity = new_vel1;
-    }
-    if let Ok((_, _, mut particle)) = particle_query.get_mut(e2) {
-      particle.velocity = new_vel2;
-    }
+  if args.headless {
+    // no window and no GPU: the simulation runs as fast as it can, which is
+    // what benchmarks and scripted runs want
+    app
+      .add_plugins(
+        DefaultPlugins
+          .set(WindowPlugin { primary_window: None, exit_condition: ExitCondition::DontExit, ..default() })
+          .set(RenderPlugin {
+            render_creation: WgpuSettings { backends: None, ..default() }.into(),
+            ..default()
+          })
+          .disable::<WinitPlugin>(),
+      )
+      .add_plugins(ScheduleRunnerPlugin::run_loop(Duration::ZERO))
+      .insert_resource(menu::ScenarioMenu { open: false })
+      .insert_resource(cli::HeadlessRun::new(args.frames))
+      .add_systems(Last, cli::count_headless_frames);
+  } else {
+    app.add_plugins(DefaultPlugins.set(WindowPlugin { primary_window: Some(primary_window()), ..default() }));
   }
-}
 
-fn elastic_collision(
-  m1: f32, m2: f32,
-  v1: Vec3, v2: Vec3,
-  r1: Vec3, r2: Vec3
-) -> (Vec3, Vec3) {
-
-  let n = (r1 - r2).normalize();
-  
-  let v_rel = (v1 - v2).dot(n);
-  
-  if v_rel > 0.0 {
-    return (v1, v2);
-  }
+  #[cfg(feature = "egui")]
+  app.add_plugins(bevy_egui::EguiPlugin);
 
-  let j = -(1.0 + RESTITUTION) * v_rel / (1.0/m1 + 1.0/m2);
-  
-  let v1f = v1 + (j / m1) * n;
-  let v2f = v2 - (j / m2) * n;
+  #[cfg(feature = "inspector")]
+  if !args.headless {
+    app.add_plugins(
+      bevy_inspector_egui::quick::WorldInspectorPlugin::new()
+        .run_if(bevy::input::common_conditions::input_toggle_active(false, KeyCode::F8)),
+    );
+  }
 
-  (v1f, v2f)
-}
+  app
+    .add_plugins(ParticlePlugin { config, spawn, mode, domains })
+    .add_plugins((FrameTimeDiagnosticsPlugin, LogDiagnosticsPlugin::default()));
 
+  if let Some(search) = args.neighbours {
+    app.world_mut().resource_mut::<SimulationState>().search = search;
+  }
 
-pub fn apply_pressure_force(
-  mut particle_query: Query<(&Transform, &mut Particle)>,
-  time: Res<Time>,
-  state: Res<SimulationState>,
-) {
+  if args.rescale_domain {
+    app.insert_resource(ResizePolicy::RescaleDomain);
+  }
 
-  // collect positions first to avoid conflicts
-  let particle_data: Vec<(Vec3, usize)> = particle_query
-    .iter()
-    .enumerate()
-    .map(|(i, (_, particle))| (particle.predicted_position, i))
-    .collect();
+  if args.run_unfocused {
+    app.insert_resource(pause::AutoPause { on_focus_loss: false });
+  }
 
-  for (i, (_, mut particle)) in particle_query.iter_mut().enumerate() {
-    let pressure_force = calculate_pressure_force(&particle_data, &particle, &state, i);
-    let pressure_acceleration = pressure_force / state.densities[i];
-    particle.velocity += pressure_acceleration * time.delta_secs();
+  if args.emitters {
+    app.add_systems(Startup, emitter::spawn_demo_emitters);
   }
-}
 
+  if args.obstacles {
+    app.add_systems(Startup, obstacle::spawn_demo_obstacles);
+  }
 
-fn smoothing_kernel(radius: f32, dist: f32) -> f32 {
-  let volume = (PI * radius.powf(4.0)) / 6.0;
-  (0.0 as f32).max(radius - dist).powf(2.0) / volume
-}
+  if let Some(path) = args.config {
+    app
+      .insert_resource(config_file::ConfigFile::new(path))
+      .add_systems(Startup, config_file::create_config_file)
+      .add_systems(Update, config_file::watch_config_file.before(gravity));
+  }
 
-fn smoothing_kernel_dx(radius: f32, dist: f32) -> f32 {
+  if let Some(path) = args.script {
+    app.insert_resource(script::SceneScript::new(path));
+  }
 
-  if dist >= radius {
-    return 0.0;
+  #[cfg(feature = "export")]
+  if let Some(path) = args.export {
+    let format = ExportFormat::from_path(&path).expect("checked when parsing the arguments");
+    let export = DataExport::create(&path, format, args.export_every)
+      .unwrap_or_else(|err| panic!("could not create {}: {err}", path.display()));
+    app
+      .insert_resource(export)
+      .add_systems(Update, export::export_particle_data.after(apply_viscosity).run_if(pause::simulation_running));
   }
 
-  let scale = 12.0 / (radius.powf(4.0) * PI);
-  (radius - dist) * scale
-}
+  #[cfg(feature = "metrics")]
+  if let Some(addr) = args.metrics {
+    let endpoint = MetricsEndpoint::bind(&addr).unwrap_or_else(|err| panic!("could not listen on {addr}: {err}"));
+    app
+      .insert_resource(endpoint)
+      .init_resource::<SimulationMetrics>()
+      .add_systems(
+        Update,
+        (metrics::start_step_timer.before(FluidSet::Integrate), metrics::collect_metrics.after(FluidSet::Forces))
+          .run_if(pause::simulation_running),
+      )
+      .add_systems(Last, metrics::publish_metrics);
+  }
 
-fn calculate_density(
-  particle_query: &Query<(&Transform, &Particle)>,
-  sample_particle: &Particle, 
-) -> f32 {
-  let mut density: f32 = 0.0;
-  
-  for (_, particle) in particle_query {
-    let dist = particle.predicted_position.distance(sample_particle.predicted_position);
-    let influence = smoothing_kernel(SMOOTHING_RADIUS, dist);
-    
-    density += MASS * influence;
+  if args.deterministic {
+    app.add_plugins(DeterminismPlugin);
+    if !STRICT_MATH {
+      warn!("built without the deterministic feature, so runs only repeat on the same kind of machine");
+    }
   }
 
-  density
-}
+  let dt = args.dt.or(args.deterministic.then_some(1.0 / 60.0));
+  if let Some(dt) = dt {
+    app.insert_resource(TimeUpdateStrategy::ManualDuration(Duration::from_secs_f64(dt)));
+  }
 
-fn update_density(
-  particle_query: Query<(&Transform, &Particle)>,
-  mut state: ResMut<SimulationState>,
-) {
-  for (i, (_, sample_particle)) in particle_query.iter().enumerate() {
-      state.densities[i] = calculate_density(&particle_query, sample_particle);
+  if let Some(session) = session {
+    app
+      .insert_resource(TimeUpdateStrategy::ManualDuration(session.first_frame_duration()))
+      .insert_resource(menu::ScenarioMenu { open: false })
+      .insert_resource(replay::InputReplay::new(session));
   }
-}
 
+  if let Some(dir) = args.record {
+    std::fs::create_dir_all(&dir).expect("could not create frame export directory");
 
-fn calculate_pressure_force(
-  particle_data: &[(Vec3, usize)],
-  sample_particle: &Particle,
-  state: &SimulationState,
-  sample_index: usize,
-) -> Vec3 {
-  let mut pressure_force = Vec3::ZERO;
-
-  for &(predicted_position, i) in particle_data {
-    if i != sample_index {
-      let dist = predicted_position.distance(sample_particle.predicted_position);
-
-      if dist > 0.0 {
-        let dir = (predicted_position - sample_particle.predicted_position) / dist;
-        let slope = smoothing_kernel_dx(SMOOTHING_RADIUS, dist);
-        let density = state.densities[i];
-        let pressure = shared_pressure(density, state.densities[sample_index]);
-        
-        pressure_force += pressure * dir * slope * MASS / density;
-      }
-    }
+    // --dt still wins, the export rate only sets the default step
+    let dt = args.dt.unwrap_or(1.0 / capture::EXPORT_FRAME_RATE);
+    app
+      .insert_resource(TimeUpdateStrategy::ManualDuration(Duration::from_secs_f64(dt)))
+      // a recording should show the simulation, not the scenario picker
+      .insert_resource(menu::ScenarioMenu { open: false })
+      .insert_resource(capture::FrameExport {
+        dir,
+        frames: args.frames,
+        ffmpeg: args.ffmpeg,
+        frame: 0,
+      })
+      .add_systems(Update, capture::export_frames);
   }
-  pressure_force
-}
 
+  app.run();
+}
 
-fn density_to_pressure(density: f32) -> f32 {
-  let density_err = density - TARGET_DENSITY;  
-  let pressure = density_err * PRESSURE_MULTIPLIER;
-  pressure
+#[cfg(not(target_arch = "wasm32"))]
+fn primary_window() -> Window {
+  Window::default()
 }
 
-fn shared_pressure(density: f32, other_density: f32) -> f32 {
-  let p1 = density_to_pressure(density);
-  let p2 = density_to_pressure(other_density);
-  (p1 + p2) / 2.0
-}
\ No newline at end of file
+/// Draws into the page's `#bevy` canvas and follows its parent's size, as
+/// `index.html` lays it out, keeping touches and keys from scrolling the page.
+#[cfg(target_arch = "wasm32")]
+fn primary_window() -> Window {
+  Window {
+    canvas: Some("#bevy".into()),
+    fit_canvas_to_parent: true,
+    prevent_default_event_handling: true,
+    ..default()
+  }
+}
@@ -0,0 +1,41 @@
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::{SimulationConfig, COLOR};
+
+/// A fluid's own coefficients, for particles that shouldn't take them from
+/// their domain's `SimulationConfig`, so oil and water can share one solver.
+/// Particles without it behave exactly as before. `color` is what particles
+/// spawned from it are drawn in, and `surface_tension` pulls neighbouring
+/// particles of the same group together, rounding drops off; the config has
+/// no equivalent, so it defaults to none.
+#[derive(Component, Reflect, Clone, Copy, PartialEq, Debug, Serialize, Deserialize)]
+#[reflect(Component)]
+#[serde(default)]
+pub struct FluidMaterial {
+  pub rest_density: f32,
+  pub stiffness: f32,
+  pub viscosity: f32,
+  pub color: Color,
+  pub surface_tension: f32,
+}
+
+impl FluidMaterial {
+  /// The material the particles of a domain with `config` already behave as,
+  /// to start a custom one from.
+  pub fn from_config(config: &SimulationConfig) -> Self {
+    FluidMaterial {
+      rest_density: config.target_density,
+      stiffness: config.pressure_multiplier,
+      viscosity: config.viscosity,
+      color: COLOR,
+      surface_tension: 0.0,
+    }
+  }
+}
+
+impl Default for FluidMaterial {
+  fn default() -> Self {
+    FluidMaterial::from_config(&SimulationConfig::default())
+  }
+}
@@ -0,0 +1,149 @@
+use bevy::prelude::*;
+#[cfg(feature = "egui")]
+use bevy_egui::{egui, EguiContexts};
+
+use crate::follow::Tagged;
+#[cfg(feature = "egui")]
+use crate::scenario::{LoadScenario, Scenario};
+
+#[cfg(feature = "egui")]
+const THUMBNAIL_SIZE: egui::Vec2 = egui::vec2(128.0, 72.0);
+#[cfg(feature = "egui")]
+const CARDS_PER_ROW: usize = 4;
+
+/// The scenario picker, open at startup and toggled with Escape.
+#[derive(Resource)]
+pub struct ScenarioMenu {
+  pub open: bool,
+}
+
+impl Default for ScenarioMenu {
+  fn default() -> Self {
+    ScenarioMenu { open: true }
+  }
+}
+
+/// Escape closes the menu, or opens it unless it is releasing a tagged
+/// particle instead.
+pub fn toggle_menu(
+  keys: Res<ButtonInput<KeyCode>>,
+  tagged_query: Query<(), With<Tagged>>,
+  mut menu: ResMut<ScenarioMenu>,
+) {
+  if keys.just_pressed(KeyCode::Escape) && (menu.open || tagged_query.is_empty()) {
+    menu.open = !menu.open;
+  }
+}
+
+#[cfg(feature = "egui")]
+pub fn scenario_menu(
+  mut contexts: EguiContexts,
+  mut menu: ResMut<ScenarioMenu>,
+  current: Res<Scenario>,
+  mut loads: EventWriter<LoadScenario>,
+) {
+  if !menu.open {
+    return;
+  }
+
+  let mut open = true;
+  egui::Window::new("Scenarios")
+    .open(&mut open)
+    .anchor(egui::Align2::CENTER_CENTER, [0.0, 0.0])
+    .collapsible(false)
+    .resizable(false)
+    .show(contexts.ctx_mut(), |ui| {
+      egui::Grid::new("scenarios").spacing([12.0, 12.0]).show(ui, |ui| {
+        for (i, scenario) in Scenario::ALL.into_iter().enumerate() {
+          let clicked = ui
+            .vertical(|ui| {
+              ui.set_width(THUMBNAIL_SIZE.x);
+              let (response, painter) = ui.allocate_painter(THUMBNAIL_SIZE, egui::Sense::click());
+              sketch(scenario, &painter, response.rect, *current == scenario);
+
+              let title = ui.add(egui::Button::new(format!("{}  {}", i + 1, scenario.label())).frame(false));
+              ui.add(egui::Label::new(egui::RichText::new(scenario.description()).small()).wrap());
+              response.clicked() || title.clicked()
+            })
+            .inner;
+
+          if clicked {
+            loads.send(LoadScenario(scenario));
+            menu.open = false;
+          }
+          if (i + 1) % CARDS_PER_ROW == 0 {
+            ui.end_row();
+          }
+        }
+      });
+    });
+
+  if !open {
+    menu.open = false;
+  }
+}
+
+#[cfg(feature = "egui")]
+/// A schematic of the scenario's layout: fluid as dots, obstacles, emitters
+/// and drains as outlines. Coordinates are fractions of the thumbnail with y
+/// pointing up, like the world.
+fn sketch(scenario: Scenario, painter: &egui::Painter, rect: egui::Rect, selected: bool) {
+  let at = |x: f32, y: f32| rect.lerp_inside(egui::vec2(x, 1.0 - y));
+  let fluid = egui::Color32::from_rgb(0, 200, 255);
+  let solid = egui::Color32::from_rgb(150, 120, 90);
+  let line = egui::Stroke::new(1.5, egui::Color32::LIGHT_GRAY);
+
+  painter.rect_filled(rect, 4.0, egui::Color32::from_gray(20));
+  let border = if selected { egui::Stroke::new(2.0, fluid) } else { egui::Stroke::new(1.0, egui::Color32::DARK_GRAY) };
+  painter.rect_stroke(rect, 4.0, border, egui::StrokeKind::Inside);
+
+  // fixed pseudo-random scatter, the same every frame
+  let scatter = |count: u32, y_max: f32| {
+    for n in 0..count {
+      let x = ((n * 7919) % 1000) as f32 / 1000.0;
+      let y = ((n * 104_729) % 1000) as f32 / 1000.0 * y_max;
+      painter.circle_filled(at(0.04 + x * 0.92, 0.04 + y * 0.92), 1.2, fluid);
+    }
+  };
+  let pool = |depth: f32| {
+    painter.rect_filled(egui::Rect::from_two_pos(at(0.02, 0.02), at(0.98, depth)), 1.0, fluid.gamma_multiply(0.6));
+  };
+  let arrow = |from: (f32, f32), to: (f32, f32)| {
+    let start = at(from.0, from.1);
+    painter.arrow(start, at(to.0, to.1) - start, line);
+  };
+
+  match scenario {
+    Scenario::Default => scatter(120, 1.0),
+    Scenario::Rain => {
+      pool(0.2);
+      for n in 0..8 {
+        let x = (n as f32 + 0.5) / 8.0;
+        arrow((x, 0.95), (x, 0.7));
+      }
+    }
+    Scenario::Obstacles => {
+      scatter(120, 1.0);
+      painter.circle_filled(at(0.3, 0.3), 9.0, solid);
+      painter.rect_filled(egui::Rect::from_two_pos(at(0.7, 0.1), at(0.85, 0.3)), 1.0, solid);
+    }
+    Scenario::Crossfire => {
+      scatter(80, 1.0);
+      arrow((0.05, 0.5), (0.35, 0.6));
+      arrow((0.95, 0.5), (0.65, 0.6));
+    }
+    Scenario::FillAndDrain => {
+      pool(0.35);
+      arrow((0.05, 0.8), (0.35, 0.74));
+      painter.circle_stroke(at(0.9, 0.1), 7.0, line);
+    }
+    Scenario::Paddle => {
+      scatter(120, 1.0);
+      painter.rect_filled(egui::Rect::from_two_pos(at(0.4, 0.33), at(0.6, 0.38)), 1.0, solid);
+    }
+    Scenario::Boat => {
+      pool(0.45);
+      painter.rect_filled(egui::Rect::from_two_pos(at(0.4, 0.42), at(0.6, 0.52)), 1.0, solid);
+    }
+  }
+}
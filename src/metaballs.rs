@@ -0,0 +1,275 @@
+use bevy::{
+  core_pipeline::tonemapping::Tonemapping,
+  prelude::*,
+  render::{
+    camera::RenderTarget,
+    mesh::MeshVertexBufferLayoutRef,
+    render_asset::RenderAssetUsages,
+    renderer::RenderDevice,
+    render_resource::{
+      AsBindGroup, BlendComponent, BlendFactor, BlendOperation, BlendState, Extent3d,
+      RenderPipelineDescriptor, ShaderRef, SpecializedMeshPipelineError, TextureDimension,
+      TextureFormat, TextureUsages,
+    },
+    view::RenderLayers,
+  },
+  sprite::{AlphaMode2d, Material2d, Material2dKey},
+  window::PrimaryWindow,
+};
+
+use crate::{domain::Domains, follow::FollowCamera, render::{RenderSettings, MAIN_VIEW_LAYER, SPLAT_LAYER}, Particle, COLOR};
+
+const SPLAT_RADIUS: f32 = 8.0;
+const SPLAT_STRENGTH: f32 = 0.5;
+const SURFACE_THRESHOLD: f32 = 1.0;
+const SURFACE_SOFTNESS: f32 = 0.15;
+
+/// Radial falloff drawn once per particle into the density texture with
+/// additive blending, so overlapping splats sum.
+#[derive(Asset, TypePath, AsBindGroup, Clone)]
+pub struct SplatMaterial {
+  #[uniform(0)]
+  pub params: Vec4,
+}
+
+impl Material2d for SplatMaterial {
+  fn fragment_shader() -> ShaderRef {
+    "shaders/splat.wgsl".into()
+  }
+
+  fn alpha_mode(&self) -> AlphaMode2d {
+    AlphaMode2d::Blend
+  }
+
+  fn specialize(
+    descriptor: &mut RenderPipelineDescriptor,
+    _layout: &MeshVertexBufferLayoutRef,
+    _key: Material2dKey<Self>,
+  ) -> Result<(), SpecializedMeshPipelineError> {
+    let additive = BlendComponent {
+      src_factor: BlendFactor::One,
+      dst_factor: BlendFactor::One,
+      operation: BlendOperation::Add,
+    };
+
+    if let Some(fragment) = &mut descriptor.fragment {
+      for target in fragment.targets.iter_mut().flatten() {
+        target.blend = Some(BlendState { color: additive, alpha: additive });
+      }
+    }
+    Ok(())
+  }
+}
+
+/// Full-screen quad that thresholds the density texture into a fluid surface.
+#[derive(Asset, TypePath, AsBindGroup, Clone)]
+pub struct FluidSurfaceMaterial {
+  #[uniform(0)]
+  pub color: LinearRgba,
+  #[uniform(1)]
+  pub params: Vec4,
+  #[texture(2)]
+  #[sampler(3)]
+  pub density: Handle<Image>,
+}
+
+impl Material2d for FluidSurfaceMaterial {
+  fn fragment_shader() -> ShaderRef {
+    "shaders/fluid_surface.wgsl".into()
+  }
+
+  fn alpha_mode(&self) -> AlphaMode2d {
+    AlphaMode2d::Blend
+  }
+}
+
+#[derive(Resource)]
+pub struct DensityTexture(pub Handle<Image>);
+
+#[derive(Component)]
+pub struct SplatCamera;
+
+#[derive(Component)]
+pub struct FluidSurface;
+
+#[derive(Component)]
+pub struct Splat;
+
+#[derive(Resource)]
+pub struct SplatAssets {
+  mesh: Handle<Mesh>,
+  material: Handle<SplatMaterial>,
+}
+
+/// The density texture's size for a window of `size` pixels, scaled down to
+/// fit the GPU's texture limit, which on WebGL2 is smaller than many phone
+/// screens. The device may not exist yet in the browser, where it is created
+/// asynchronously, nor at all when headless.
+fn texture_size(size: UVec2, device: Option<&RenderDevice>) -> UVec2 {
+  let size = size.max(UVec2::ONE);
+  let Some(limit) = device.map(|device| device.limits().max_texture_dimension_2d) else {
+    return size;
+  };
+  let scale = (limit as f32 / size.max_element() as f32).min(1.0);
+  (size.as_vec2() * scale).as_uvec2().clamp(UVec2::ONE, UVec2::splat(limit))
+}
+
+fn density_image(size: UVec2) -> Image {
+  let mut image = Image::new_fill(
+    Extent3d { width: size.x.max(1), height: size.y.max(1), depth_or_array_layers: 1 },
+    TextureDimension::D2,
+    &[0; 8],
+    TextureFormat::Rgba16Float,
+    RenderAssetUsages::default(),
+  );
+  image.texture_descriptor.usage =
+    TextureUsages::TEXTURE_BINDING | TextureUsages::COPY_DST | TextureUsages::RENDER_ATTACHMENT;
+  image
+}
+
+/// The splat camera and surface quad are children of the main camera, so they
+/// track it when it follows a particle.
+pub fn setup_metaballs(
+  mut commands: Commands,
+  mut images: ResMut<Assets<Image>>,
+  mut meshes: ResMut<Assets<Mesh>>,
+  mut splat_materials: ResMut<Assets<SplatMaterial>>,
+  mut surface_materials: ResMut<Assets<FluidSurfaceMaterial>>,
+  window_query: Query<&Window, With<PrimaryWindow>>,
+  camera_query: Query<Entity, With<FollowCamera>>,
+  domains: Res<Domains>,
+  device: Option<Res<RenderDevice>>,
+) {
+  // no window when running headless, and so nothing to draw
+  let Ok(window) = window_query.get_single() else {
+    return;
+  };
+  let Ok(main_camera) = camera_query.get_single() else {
+    return;
+  };
+
+  let density = images.add(density_image(texture_size(window.physical_size(), device.as_deref())));
+
+  let splat_camera = commands.spawn((
+    SplatCamera,
+    Camera2d,
+    Camera {
+      target: RenderTarget::Image(density.clone()),
+      order: -1,
+      hdr: true,
+      clear_color: ClearColorConfig::Custom(Color::NONE),
+      is_active: false,
+      ..default()
+    },
+    Tonemapping::None,
+    domains.projection(),
+    RenderLayers::layer(SPLAT_LAYER),
+  )).id();
+
+  let surface = commands.spawn((
+    FluidSurface,
+    Mesh2d(meshes.add(Rectangle::new(1.0, 1.0))),
+    MeshMaterial2d(surface_materials.add(FluidSurfaceMaterial {
+      color: COLOR.to_linear(),
+      params: Vec4::new(SURFACE_THRESHOLD, SURFACE_SOFTNESS, 0.0, 0.0),
+      density: density.clone(),
+    })),
+    Transform::from_xyz(0.0, 0.0, 1.0).with_scale(domains.size.extend(1.0)),
+    Visibility::Hidden,
+    RenderLayers::layer(MAIN_VIEW_LAYER),
+  )).id();
+
+  commands.entity(main_camera).add_children(&[splat_camera, surface]);
+
+  commands.insert_resource(DensityTexture(density));
+  commands.insert_resource(SplatAssets {
+    mesh: meshes.add(Rectangle::new(2.0 * SPLAT_RADIUS, 2.0 * SPLAT_RADIUS)),
+    material: splat_materials.add(SplatMaterial {
+      params: Vec4::new(SPLAT_STRENGTH, 0.0, 0.0, 0.0),
+    }),
+  });
+}
+
+pub fn attach_splats(
+  mut commands: Commands,
+  particle_query: Query<Entity, (Added<Particle>, With<Mesh2d>)>,
+  assets: Option<Res<SplatAssets>>,
+  settings: Res<RenderSettings>,
+) {
+  let Some(assets) = assets else {
+    return;
+  };
+
+  let visibility = if settings.metaballs { Visibility::Visible } else { Visibility::Hidden };
+
+  for entity in &particle_query {
+    let splat = commands.spawn((
+      Splat,
+      Mesh2d(assets.mesh.clone()),
+      MeshMaterial2d(assets.material.clone()),
+      Transform::default(),
+      visibility,
+      RenderLayers::layer(SPLAT_LAYER),
+    )).id();
+    commands.entity(entity).add_child(splat);
+  }
+}
+
+/// Swaps between drawing particle circles and the splatted surface. Splats
+/// are explicitly `Visible` so they still draw while their parent is hidden.
+pub fn toggle_metaballs(
+  settings: Res<RenderSettings>,
+  mut camera_query: Query<&mut Camera, With<SplatCamera>>,
+  mut visibility_query: Query<(&mut Visibility, Has<Splat>, Has<FluidSurface>, Has<Particle>)>,
+) {
+  if !settings.is_changed() {
+    return;
+  }
+
+  let enabled = settings.metaballs;
+  for mut camera in &mut camera_query {
+    camera.is_active = enabled;
+  }
+
+  let shown = if enabled { Visibility::Visible } else { Visibility::Hidden };
+  let particles = if enabled { Visibility::Hidden } else { Visibility::Inherited };
+
+  for (mut visibility, is_splat, is_surface, is_particle) in &mut visibility_query {
+    if is_splat || is_surface {
+      *visibility = shown;
+    } else if is_particle {
+      *visibility = particles;
+    }
+  }
+}
+
+/// Keeps the density texture at the window's resolution, or the largest the
+/// GPU allows, and the surface quad
+/// covering whatever world area the main camera currently shows.
+pub fn resize_density_texture(
+  window_query: Query<&Window, (With<PrimaryWindow>, Changed<Window>)>,
+  projection_query: Query<&OrthographicProjection, (With<FollowCamera>, Changed<OrthographicProjection>)>,
+  texture: Option<Res<DensityTexture>>,
+  mut images: ResMut<Assets<Image>>,
+  mut surface_query: Query<&mut Transform, With<FluidSurface>>,
+  device: Option<Res<RenderDevice>>,
+) {
+  let Some(texture) = texture else {
+    return;
+  };
+
+  if let Ok(window) = window_query.get_single() {
+    let size = texture_size(window.physical_size(), device.as_deref());
+    if let Some(image) = images.get_mut(&texture.0) {
+      if image.size() != size {
+        image.resize(Extent3d { width: size.x, height: size.y, depth_or_array_layers: 1 });
+      }
+    }
+  }
+
+  if let Ok(projection) = projection_query.get_single() {
+    for mut transform in &mut surface_query {
+      transform.scale = projection.area.size().extend(1.0);
+    }
+  }
+}
@@ -0,0 +1,137 @@
+use std::{
+  fmt::Write as _,
+  io::{self, BufRead, BufReader, Write},
+  net::{TcpListener, TcpStream, ToSocketAddrs},
+  sync::{Arc, Mutex},
+  thread,
+  time::{Duration, Instant},
+};
+
+use bevy::prelude::*;
+
+use crate::{
+  domain::{DomainId, Domains},
+  solver::SimulationState,
+  Particle, SimulationConfig, SimulationMode,
+};
+
+/// Statistics of the last simulated step, for watching long runs from
+/// outside the app.
+#[derive(Resource, Default, Clone, Debug)]
+pub struct SimulationMetrics {
+  pub steps: u64,
+  /// Wall time the solver took for the step.
+  pub step_time: Duration,
+  pub particles: usize,
+  /// Mean of every particle's density error relative to its rest density.
+  pub density_error: f32,
+  pub kinetic_energy: f32,
+  started: Option<Instant>,
+}
+
+impl SimulationMetrics {
+  /// The metrics in Prometheus' text format.
+  pub fn render(&self) -> String {
+    let mut text = String::new();
+    let mut metric = |name: &str, kind: &str, help: &str, value: String| {
+      let _ = writeln!(text, "# HELP {name} {help}");
+      let _ = writeln!(text, "# TYPE {name} {kind}");
+      let _ = writeln!(text, "{name} {value}");
+    };
+    metric("fluid_steps_total", "counter", "Simulated steps.", self.steps.to_string());
+    metric("fluid_step_seconds", "gauge", "Wall time of the last step.", self.step_time.as_secs_f64().to_string());
+    metric("fluid_particles", "gauge", "Particles simulated.", self.particles.to_string());
+    metric("fluid_density_error", "gauge", "Mean relative density error.", self.density_error.to_string());
+    metric("fluid_kinetic_energy", "gauge", "Total kinetic energy.", self.kinetic_energy.to_string());
+    text
+  }
+}
+
+/// Runs before the solver, to time the step.
+pub fn start_step_timer(mut metrics: ResMut<SimulationMetrics>) {
+  metrics.started = Some(Instant::now());
+}
+
+/// Runs after the solver, once the densities are fresh.
+pub fn collect_metrics(
+  mut metrics: ResMut<SimulationMetrics>,
+  particle_query: Query<(Entity, &Particle, &DomainId)>,
+  state: Res<SimulationState>,
+  mode: Res<SimulationMode>,
+  domains: Res<Domains>,
+  config: Res<SimulationConfig>,
+) {
+  let dimension = mode.dimension();
+  let (mut error, mut energy) = (0.0, 0.0);
+  for (entity, particle, domain) in &particle_query {
+    let config = domains.config(domain.0, &config);
+    let rest_density = state.material_of(entity).map_or(config.target_density, |material| material.rest_density);
+    let target = dimension.target_density(rest_density, config.smoothing_radius);
+    let density = state.density_of(entity);
+    if target > 0.0 {
+      error += (density - target).abs() / target;
+    }
+    energy += 0.5 * config.mass * particle.velocity.length_squared();
+  }
+
+  let particles = particle_query.iter().len();
+  metrics.steps += 1;
+  metrics.step_time = metrics.started.take().map(|started| started.elapsed()).unwrap_or_default();
+  metrics.particles = particles;
+  metrics.density_error = if particles > 0 { error / particles as f32 } else { 0.0 };
+  metrics.kinetic_energy = energy;
+}
+
+/// A small HTTP server answering `GET /metrics` with the latest
+/// `SimulationMetrics`, from its own thread so scrapes never wait on a frame.
+#[derive(Resource)]
+pub struct MetricsEndpoint {
+  body: Arc<Mutex<String>>,
+}
+
+impl MetricsEndpoint {
+  pub fn bind(addr: impl ToSocketAddrs) -> io::Result<Self> {
+    let listener = TcpListener::bind(addr)?;
+    let body = Arc::new(Mutex::new(String::new()));
+    let served = body.clone();
+    thread::spawn(move || {
+      // a thread per connection, so a client that never sends its request
+      // only holds up itself until the read times out
+      for stream in listener.incoming().flatten() {
+        let served = served.clone();
+        thread::spawn(move || {
+          if let Err(err) = respond(stream, &served) {
+            warn!("could not answer a metrics request: {err}");
+          }
+        });
+      }
+    });
+    Ok(MetricsEndpoint { body })
+  }
+}
+
+fn respond(mut stream: TcpStream, body: &Mutex<String>) -> io::Result<()> {
+  stream.set_read_timeout(Some(Duration::from_secs(5)))?;
+  let mut request = String::new();
+  BufReader::new(&stream).read_line(&mut request)?;
+
+  let path = request.split_whitespace().nth(1).unwrap_or_default();
+  let (status, body) = if path == "/metrics" {
+    ("200 OK", body.lock().map(|body| body.clone()).unwrap_or_default())
+  } else {
+    ("404 Not Found", String::new())
+  };
+  write!(
+    stream,
+    "HTTP/1.1 {status}\r\nContent-Type: text/plain; version=0.0.4\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{body}",
+    body.len(),
+  )
+}
+
+pub fn publish_metrics(endpoint: Res<MetricsEndpoint>, metrics: Res<SimulationMetrics>) {
+  if metrics.is_changed() {
+    if let Ok(mut body) = endpoint.body.lock() {
+      *body = metrics.render();
+    }
+  }
+}
@@ -0,0 +1,115 @@
+use bevy::{
+  prelude::*,
+  render::{
+    camera::{RenderTarget, ScalingMode},
+    render_resource::{Extent3d, TextureDimension, TextureFormat, TextureUsages},
+    render_asset::RenderAssetUsages,
+    view::RenderLayers,
+  },
+};
+
+use crate::{diagnostics::DiagnosticsWindow, domain::Domains, follow::FollowCamera, render::MINIMAP_LAYER, theme::Theme};
+
+const MINIMAP_WIDTH: f32 = 240.0;
+
+/// Gizmos only the minimap camera draws, i.e. the main viewport outline.
+#[derive(Default, Reflect, GizmoConfigGroup)]
+pub struct MinimapGizmos;
+
+pub fn minimap_gizmo_config() -> GizmoConfig {
+  GizmoConfig {
+    render_layers: RenderLayers::layer(MINIMAP_LAYER),
+    ..default()
+  }
+}
+
+#[derive(Component)]
+pub struct MinimapCamera;
+
+#[derive(Component)]
+pub struct Minimap;
+
+pub fn setup_minimap(
+  mut commands: Commands,
+  mut images: ResMut<Assets<Image>>,
+  domains: Res<Domains>,
+) {
+  let size = Vec2::new(MINIMAP_WIDTH, MINIMAP_WIDTH * domains.size.y / domains.size.x);
+
+  let mut image = Image::new_fill(
+    Extent3d { width: size.x as u32, height: size.y as u32, depth_or_array_layers: 1 },
+    TextureDimension::D2,
+    &[0; 4],
+    TextureFormat::Bgra8UnormSrgb,
+    RenderAssetUsages::default(),
+  );
+  image.texture_descriptor.usage =
+    TextureUsages::TEXTURE_BINDING | TextureUsages::COPY_DST | TextureUsages::RENDER_ATTACHMENT;
+  let target = images.add(image);
+
+  commands.spawn((
+    MinimapCamera,
+    Camera2d,
+    Camera {
+      target: RenderTarget::Image(target.clone()),
+      order: -2,
+      is_active: false,
+      ..default()
+    },
+    OrthographicProjection {
+      scaling_mode: ScalingMode::Fixed { width: domains.size.x, height: domains.size.y },
+      ..OrthographicProjection::default_2d()
+    },
+    RenderLayers::from_layers(&[0, MINIMAP_LAYER]),
+  ));
+
+  commands.spawn((
+    Minimap,
+    ImageNode::new(target),
+    Node {
+      position_type: PositionType::Absolute,
+      bottom: Val::Px(12.0),
+      left: Val::Px(12.0),
+      width: Val::Px(size.x),
+      height: Val::Px(size.y),
+      border: UiRect::all(Val::Px(1.0)),
+      ..default()
+    },
+    BorderColor(Color::WHITE),
+    Visibility::Hidden,
+  ));
+}
+
+/// Shows the minimap only while the main view leaves part of the domain off
+/// screen, and outlines that view on it. While the diagnostics window is open
+/// the minimap is drawn there instead, all the time.
+pub fn update_minimap(
+  main_query: Query<(&GlobalTransform, &OrthographicProjection), With<FollowCamera>>,
+  diagnostics_query: Query<(), With<DiagnosticsWindow>>,
+  mut minimap_camera_query: Query<&mut Camera, With<MinimapCamera>>,
+  mut minimap_query: Query<(&mut Visibility, &mut BorderColor), With<Minimap>>,
+  mut gizmos: Gizmos<MinimapGizmos>,
+  domains: Res<Domains>,
+  theme: Res<Theme>,
+) {
+  let Some((transform, projection)) = main_query.iter().next() else {
+    return;
+  };
+
+  let view = Rect::from_center_size(transform.translation().truncate(), projection.area.size());
+  let domain = Rect::from_center_size(Vec2::ZERO, domains.size);
+  let zoomed_in = view.union(domain) != view;
+  let apart = !diagnostics_query.is_empty();
+
+  for mut camera in &mut minimap_camera_query {
+    camera.is_active = zoomed_in || apart;
+  }
+  for (mut visibility, mut border) in &mut minimap_query {
+    *visibility = if zoomed_in && !apart { Visibility::Inherited } else { Visibility::Hidden };
+    border.0 = theme.foreground;
+  }
+
+  if zoomed_in {
+    gizmos.rect_2d(Isometry2d::from_translation(view.center()), view.size(), theme.foreground);
+  }
+}
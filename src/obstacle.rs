@@ -0,0 +1,208 @@
+use bevy::prelude::*;
+#[cfg(feature = "render")]
+use bevy::{
+  render::render_resource::{AsBindGroup, ShaderRef},
+  sprite::{AlphaMode2d, Material2d},
+};
+
+use crate::{domain::Domains, layers::CollisionLayers, Particle, SimulationConfig};
+#[cfg(feature = "render")]
+use crate::{render::RenderSettings, theme::Theme};
+
+#[cfg(feature = "render")]
+const GLOW_WIDTH: f32 = 12.0;
+#[cfg(feature = "render")]
+const OUTLINE_WIDTH: f32 = 1.5;
+#[cfg(feature = "render")]
+const FILL: Color = Color::hsl(30.0, 0.15, 0.35);
+
+#[derive(Clone, Copy, PartialEq, Debug, serde::Serialize, serde::Deserialize)]
+pub enum ObstacleShape {
+  Circle { radius: f32 },
+  Box { half_size: Vec2 },
+}
+
+impl ObstacleShape {
+  /// Signed distance from the obstacle's surface, negative inside, for a
+  /// point relative to its centre.
+  pub fn sdf(&self, local: Vec2) -> f32 {
+    match *self {
+      ObstacleShape::Circle { radius } => local.length() - radius,
+      ObstacleShape::Box { half_size } => {
+        let d = local.abs() - half_size;
+        d.max(Vec2::ZERO).length() + d.x.max(d.y).min(0.0)
+      }
+    }
+  }
+
+  pub fn normal(&self, local: Vec2) -> Vec2 {
+    let eps = 0.5;
+    Vec2::new(
+      self.sdf(local + Vec2::X * eps) - self.sdf(local - Vec2::X * eps),
+      self.sdf(local + Vec2::Y * eps) - self.sdf(local - Vec2::Y * eps),
+    ).normalize_or(Vec2::Y)
+  }
+
+  #[cfg(feature = "render")]
+  fn half_extents(&self) -> Vec2 {
+    match *self {
+      ObstacleShape::Circle { radius } => Vec2::splat(radius),
+      ObstacleShape::Box { half_size } => half_size,
+    }
+  }
+}
+
+/// Static solid geometry particles collide with, positioned by its `Transform`.
+#[derive(Component, Clone, Copy, Debug, serde::Serialize, serde::Deserialize)]
+pub struct Obstacle {
+  pub shape: ObstacleShape,
+}
+
+/// An obstacle moved by code rather than standing still. Particles it runs
+/// into are pushed along at its velocity.
+#[derive(Component, Clone, Copy, Default, Debug)]
+pub struct Kinematic {
+  pub velocity: Vec2,
+}
+
+/// A kinematic obstacle that is also pushed back by the particles hitting
+/// it, so it can float and be carried by the flow.
+#[derive(Component, Clone, Copy, Debug)]
+pub struct Floating {
+  pub mass: f32,
+}
+
+/// Pushes a particle out of any obstacle it has entered and reflects the
+/// part of its velocity moving into the surface, relative to the obstacle's
+/// own `(obstacle, centre, velocity, layers)`, damped like the domain walls.
+/// Obstacles whose layers don't interact with the particle's are passed
+/// through. The momentum each obstacle gives the particle is subtracted
+/// from `impulses`.
+pub fn collide_obstacles(
+  particle: &mut Particle,
+  layers: CollisionLayers,
+  obstacles: &[(Obstacle, Vec2, Vec2, CollisionLayers)],
+  impulses: &mut [Vec2],
+  config: &SimulationConfig,
+) {
+  let size = config.particle_size;
+  for ((obstacle, center, velocity, obstacle_layers), impulse) in obstacles.iter().zip(impulses) {
+    if !layers.interacts(*obstacle_layers) {
+      continue;
+    }
+    let local = particle.position.truncate() - *center;
+    let dist = obstacle.shape.sdf(local);
+
+    if dist < size {
+      let normal = obstacle.shape.normal(local).extend(0.0);
+      particle.position += normal * (size - dist);
+
+      let inward = (particle.velocity - velocity.extend(0.0)).dot(normal);
+      if inward < 0.0 {
+        let change = -(1.0 + config.collision_dampening) * inward * normal;
+        particle.velocity += change;
+        *impulse -= change.truncate() * particle.mass;
+      }
+    }
+  }
+}
+
+/// A circle and a box sitting in the lower half of every domain.
+pub fn spawn_demo_obstacles(mut commands: Commands, domains: Res<Domains>) {
+  demo_obstacles(&mut commands, &domains);
+}
+
+pub fn demo_obstacles(commands: &mut Commands, domains: &Domains) {
+  for i in 0..domains.params.len() {
+    let (center, half) = domains.bounds(i);
+
+    commands.spawn((
+      Obstacle { shape: ObstacleShape::Circle { radius: half.y * 0.15 } },
+      Transform::from_translation((center + Vec2::new(-half.x * 0.4, -half.y * 0.4)).extend(-1.0)),
+    ));
+    commands.spawn((
+      Obstacle { shape: ObstacleShape::Box { half_size: Vec2::new(half.x * 0.15, half.y * 0.1) } },
+      Transform::from_translation((center + Vec2::new(half.x * 0.35, -half.y * 0.6)).extend(-1.0)),
+    ));
+  }
+}
+
+#[cfg(feature = "render")]
+/// Fills the shape, outlines its surface, and optionally adds a glow that
+/// falls off with distance from it, all evaluated from the SDF in the shader.
+#[derive(Asset, TypePath, AsBindGroup, Clone)]
+pub struct ObstacleMaterial {
+  #[uniform(0)]
+  pub fill: LinearRgba,
+  #[uniform(1)]
+  pub outline: LinearRgba,
+  #[uniform(2)]
+  pub shape: Vec4,
+  #[uniform(3)]
+  pub extent: Vec4,
+}
+
+#[cfg(feature = "render")]
+impl Material2d for ObstacleMaterial {
+  fn fragment_shader() -> ShaderRef {
+    "shaders/obstacle.wgsl".into()
+  }
+
+  fn alpha_mode(&self) -> AlphaMode2d {
+    AlphaMode2d::Blend
+  }
+}
+
+#[cfg(feature = "render")]
+impl ObstacleMaterial {
+  fn new(shape: ObstacleShape, theme: &Theme, glow: bool) -> Self {
+    let (kind, size) = match shape {
+      ObstacleShape::Circle { radius } => (0.0, Vec2::splat(radius)),
+      ObstacleShape::Box { half_size } => (1.0, half_size),
+    };
+    let quad = shape.half_extents() + Vec2::splat(3.0 * GLOW_WIDTH);
+
+    ObstacleMaterial {
+      fill: FILL.to_linear(),
+      outline: theme.foreground.to_linear(),
+      shape: Vec4::new(kind, size.x, size.y, GLOW_WIDTH),
+      extent: Vec4::new(quad.x, quad.y, if glow { 1.0 } else { 0.0 }, OUTLINE_WIDTH),
+    }
+  }
+}
+
+#[cfg(feature = "render")]
+pub fn attach_obstacle_visuals(
+  mut commands: Commands,
+  obstacle_query: Query<(Entity, &Obstacle), Changed<Obstacle>>,
+  mut meshes: ResMut<Assets<Mesh>>,
+  mut materials: ResMut<Assets<ObstacleMaterial>>,
+  theme: Res<Theme>,
+  settings: Res<RenderSettings>,
+) {
+  for (entity, obstacle) in &obstacle_query {
+    let quad = obstacle.shape.half_extents() + Vec2::splat(3.0 * GLOW_WIDTH);
+    commands.entity(entity).insert((
+      Mesh2d(meshes.add(Rectangle::from_size(quad * 2.0))),
+      MeshMaterial2d(materials.add(ObstacleMaterial::new(obstacle.shape, &theme, settings.obstacle_glow))),
+    ));
+  }
+}
+
+#[cfg(feature = "render")]
+pub fn update_obstacle_materials(
+  obstacle_query: Query<(&Obstacle, &MeshMaterial2d<ObstacleMaterial>)>,
+  mut materials: ResMut<Assets<ObstacleMaterial>>,
+  theme: Res<Theme>,
+  settings: Res<RenderSettings>,
+) {
+  if !theme.is_changed() && !settings.is_changed() {
+    return;
+  }
+
+  for (obstacle, handle) in &obstacle_query {
+    if let Some(material) = materials.get_mut(handle) {
+      *material = ObstacleMaterial::new(obstacle.shape, &theme, settings.obstacle_glow);
+    }
+  }
+}
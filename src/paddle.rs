@@ -0,0 +1,80 @@
+use bevy::{math::ops, prelude::*};
+
+use crate::{
+  domain::{DomainId, Domains},
+  obstacle::{Kinematic, Obstacle, ObstacleShape},
+};
+
+const PADDLE_HALF_SIZE: Vec2 = Vec2::new(40.0, 6.0);
+const PADDLE_SPEED: f32 = 400.0;
+// how quickly the paddle reaches the commanded speed
+const PADDLE_RESPONSE: f32 = 12.0;
+const STICK_DEADZONE: f32 = 0.15;
+
+/// A box obstacle steered with WASD or a gamepad's right stick, confined to
+/// its domain.
+#[derive(Component)]
+pub struct Paddle {
+  pub domain: DomainId,
+}
+
+pub fn spawn_paddles(commands: &mut Commands, domains: &Domains) {
+  for i in 0..domains.params.len() {
+    let (center, half) = domains.bounds(i);
+    commands.spawn((
+      Paddle { domain: DomainId(i) },
+      Obstacle { shape: ObstacleShape::Box { half_size: PADDLE_HALF_SIZE } },
+      Kinematic::default(),
+      Transform::from_translation((center - Vec2::Y * half.y * 0.3).extend(-1.0)),
+    ));
+  }
+}
+
+/// WASD or any gamepad's right stick, clamped to unit length. The keys are
+/// ignored while Ctrl is held, so shortcuts like Ctrl+S don't steer.
+pub fn steering_input(keys: &ButtonInput<KeyCode>, gamepads: &Query<&Gamepad>) -> Vec2 {
+  let mut input = Vec2::ZERO;
+  let shortcut = keys.any_pressed([KeyCode::ControlLeft, KeyCode::ControlRight]);
+  for (key, direction) in [
+    (KeyCode::KeyW, Vec2::Y),
+    (KeyCode::KeyS, Vec2::NEG_Y),
+    (KeyCode::KeyA, Vec2::NEG_X),
+    (KeyCode::KeyD, Vec2::X),
+  ] {
+    if !shortcut && keys.pressed(key) {
+      input += direction;
+    }
+  }
+  for gamepad in gamepads {
+    let stick = gamepad.right_stick();
+    if stick.length() > STICK_DEADZONE {
+      input += stick;
+    }
+  }
+  input.clamp_length_max(1.0)
+}
+
+pub fn drive_paddle(
+  keys: Res<ButtonInput<KeyCode>>,
+  gamepads: Query<&Gamepad>,
+  mut paddle_query: Query<(&Paddle, &mut Kinematic, &mut Transform)>,
+  domains: Res<Domains>,
+  time: Res<Time>,
+) {
+  let target = steering_input(&keys, &gamepads) * PADDLE_SPEED;
+  let dt = time.delta_secs();
+  let blend = 1.0 - ops::exp(-PADDLE_RESPONSE * dt);
+
+  for (paddle, mut kinematic, mut transform) in &mut paddle_query {
+    let (center, half) = domains.bounds(paddle.domain.0);
+    let limit = half - PADDLE_HALF_SIZE;
+
+    let velocity = kinematic.velocity.lerp(target, blend);
+    let position = transform.translation.truncate() + velocity * dt;
+    let clamped = position.clamp(center - limit, center + limit);
+
+    // stopping at a wall should not keep shoving particles at full speed
+    kinematic.velocity = if dt > 0.0 { (clamped - transform.translation.truncate()) / dt } else { velocity };
+    transform.translation = clamped.extend(transform.translation.z);
+  }
+}
@@ -0,0 +1,287 @@
+use bevy::prelude::*;
+use bevy_egui::{egui, EguiContexts};
+
+use crate::{
+  alternate::AlternateConfig,
+  domain::ContainerShape,
+  force_field::{ForceField, GlobalWind},
+  pause::{MAX_TIME_SCALE, MIN_TIME_SCALE},
+  population::PopulationTarget,
+  presets::Presets,
+  pressure::PressureMethod,
+  scenario::SpawnOptions,
+  tools::{
+    DrainTool, EmitterTool, HeatTool, ImpulseTool, ObstacleTool, RulerTool, StirTool, Tool, ZoneTool,
+    DRAIN_RADIUS_RANGE, HEAT_RADIUS_RANGE, STIR_RADIUS_RANGE, ZONE_RADIUS_RANGE,
+  },
+  zone::ZoneOverrides,
+  ResetSimulation, SimulationConfig, SimulationMode,
+};
+
+pub fn parameter_panel(
+  mut contexts: EguiContexts,
+  mut config: ResMut<SimulationConfig>,
+  mut time: ResMut<Time<Virtual>>,
+  mut resets: EventWriter<ResetSimulation>,
+  mut presets: ResMut<Presets>,
+  mut spawn: ResMut<SpawnOptions>,
+  mut population: ResMut<PopulationTarget>,
+  mut alternate: ResMut<AlternateConfig>,
+  mode: Res<SimulationMode>,
+) {
+  let ctx = contexts.ctx_mut();
+
+  egui::Window::new("Parameters")
+    .default_pos([10.0, 60.0])
+    .resizable(false)
+    .show(ctx, |ui| {
+      if alternate.active {
+        ui.label("editing set B, release X to return to A");
+      }
+      ui.add(egui::Slider::new(&mut config.gravity, 0.0..=2000.0).text("gravity"));
+      ui.horizontal(|ui| {
+        ui.drag_angle(&mut config.gravity_angle);
+        ui.label("gravity direction");
+      });
+      ui.add(egui::Slider::new(&mut config.smoothing_radius, 5.0..=60.0).text("smoothing radius"));
+      ui.add(
+        egui::Slider::new(&mut config.target_density, 0.01..=5.0)
+          .logarithmic(true)
+          .text("target density"),
+      );
+      ui.add(
+        egui::Slider::new(&mut config.pressure_multiplier, 100.0..=100000.0)
+          .logarithmic(true)
+          .text("pressure multiplier"),
+      );
+      ui.add(egui::Slider::new(&mut config.viscosity, 0.0..=20.0).text("viscosity"));
+      ui.add(egui::Slider::new(&mut config.restitution, 0.0..=1.0).text("restitution"));
+      ui.add(egui::Slider::new(&mut config.collision_dampening, 0.0..=1.0).text("dampening"));
+      ui.add(egui::Slider::new(&mut config.buoyancy, 0.0..=100.0).text("buoyancy"));
+      ui.add(egui::Slider::new(&mut config.thermal_diffusion, 0.0..=20.0).text("thermal diffusion"));
+      ui.add(egui::Slider::new(&mut config.cooling, 0.0..=2.0).text("cooling"));
+      ui.add(egui::Slider::new(&mut config.particle_size, 0.5..=8.0).text("particle size"))
+        .on_hover_text("collision radius; particles spawned from now on are drawn at it too");
+      ui.add(egui::Slider::new(&mut config.mass, 0.1..=10.0).text("mass"));
+      egui::ComboBox::from_label("container")
+        .selected_text(config.container.label())
+        .show_ui(ui, |ui| {
+          for shape in ContainerShape::PRESETS {
+            let label = shape.label();
+            ui.selectable_value(&mut config.container, shape, label);
+          }
+        });
+      egui::ComboBox::from_label("pressure solver")
+        .selected_text(config.pressure_solver.label())
+        .show_ui(ui, |ui| {
+          for method in PressureMethod::ALL {
+            ui.selectable_value(&mut config.pressure_solver, method, method.label());
+          }
+        });
+
+      if *mode == SimulationMode::TwoD {
+        let mut particles = spawn.particles;
+        let slider = egui::Slider::new(&mut particles, 0..=MAX_PARTICLES).text("particles");
+        if ui.add(slider).changed() {
+          spawn.particles = particles;
+          population.active = true;
+        }
+      }
+
+      let mut speed = time.relative_speed();
+      let slider = egui::Slider::new(&mut speed, MIN_TIME_SCALE..=MAX_TIME_SCALE)
+        .logarithmic(true)
+        .text("time scale");
+      if ui.add(slider).changed() {
+        time.set_relative_speed(speed);
+      }
+
+      ui.horizontal(|ui| {
+        if ui.button("Reset").clicked() {
+          *config = SimulationConfig::default();
+        }
+        if ui.button("Restart").clicked() {
+          resets.send_default();
+        }
+        if ui.add_enabled(!alternate.active, egui::Button::new("Store as B")).clicked() {
+          alternate.config = config.clone();
+        }
+      });
+
+      ui.separator();
+      presets.ui(ui, &mut config);
+    });
+}
+
+/// While egui has keyboard focus, e.g. typing in the console or a preset
+/// name, keys are hidden from the hotkey systems.
+pub fn release_keyboard(mut contexts: EguiContexts, mut keys: ResMut<ButtonInput<KeyCode>>) {
+  if contexts.ctx_mut().wants_keyboard_input() {
+    keys.reset_all();
+  }
+}
+
+const MAX_PARTICLES: u32 = 5000;
+const DIAL_RADIUS: f32 = 28.0;
+const MAX_WIND_SPEED: f32 = 1000.0;
+
+/// Speed and direction of the global wind; click or drag on the dial to
+/// point it.
+pub fn wind_panel(
+  mut contexts: EguiContexts,
+  mut wind_query: Query<&mut ForceField, With<GlobalWind>>,
+  // remembered separately so the dial keeps its heading at zero speed
+  mut heading: Local<f32>,
+) {
+  let Ok(mut field) = wind_query.get_single_mut() else {
+    return;
+  };
+  let ForceField::Wind { velocity, .. } = field.bypass_change_detection();
+  let mut speed = velocity.length();
+  if speed > 0.0 {
+    *heading = velocity.to_angle();
+  }
+  let mut angle = *heading;
+
+  egui::Window::new("Wind")
+    .default_pos([10.0, 640.0])
+    .default_open(false)
+    .resizable(false)
+    .show(contexts.ctx_mut(), |ui| {
+      ui.horizontal(|ui| {
+        let (response, painter) = ui.allocate_painter(egui::Vec2::splat(DIAL_RADIUS * 2.0), egui::Sense::click_and_drag());
+        let center = response.rect.center();
+        // egui's y points down, the world's up
+        if let Some(pointer) = response.interact_pointer_pos() {
+          let offset = pointer - center;
+          if offset.length() > 2.0 {
+            angle = (-offset.y).atan2(offset.x);
+          }
+        }
+
+        let visuals = ui.visuals();
+        painter.circle_stroke(center, DIAL_RADIUS - 1.0, visuals.widgets.inactive.fg_stroke);
+        let tip = egui::vec2(angle.cos(), -angle.sin()) * (DIAL_RADIUS - 4.0) * (0.3 + 0.7 * speed / MAX_WIND_SPEED);
+        painter.arrow(center - tip * 0.5, tip, visuals.widgets.active.fg_stroke);
+
+        ui.vertical(|ui| {
+          ui.add(egui::Slider::new(&mut speed, 0.0..=MAX_WIND_SPEED).text("speed"));
+          ui.horizontal(|ui| {
+            ui.drag_angle(&mut angle);
+            ui.label("direction");
+          });
+        });
+      });
+    });
+
+  *heading = angle;
+  let updated = Vec2::from_angle(angle) * speed;
+  if updated != *velocity {
+    let ForceField::Wind { velocity, .. } = &mut *field;
+    *velocity = updated;
+  }
+}
+
+/// Picks what left clicks do, plus the active tool's options.
+pub fn tool_panel(
+  mut contexts: EguiContexts,
+  mut tool: ResMut<Tool>,
+  mut obstacle_tool: ResMut<ObstacleTool>,
+  mut emitter_tool: ResMut<EmitterTool>,
+  mut drain_tool: ResMut<DrainTool>,
+  mut impulse_tool: ResMut<ImpulseTool>,
+  mut stir_tool: ResMut<StirTool>,
+  mut heat_tool: ResMut<HeatTool>,
+  ruler_tool: Res<RulerTool>,
+  mut zone_tool: ResMut<ZoneTool>,
+  config: Res<SimulationConfig>,
+) {
+  egui::Window::new("Tools")
+    .default_pos([10.0, 320.0])
+    .resizable(false)
+    .show(contexts.ctx_mut(), |ui| {
+      ui.horizontal(|ui| {
+        for option in Tool::ALL {
+          ui.selectable_value(&mut *tool, option, option.label());
+        }
+      });
+
+      match *tool {
+        Tool::Follow => {}
+        Tool::Inspect => {
+          ui.label("click a particle to inspect it");
+        }
+        Tool::Obstacle => {
+          ui.horizontal(|ui| {
+            ui.radio_value(&mut obstacle_tool.place_box, false, "circle");
+            ui.radio_value(&mut obstacle_tool.place_box, true, "box");
+          });
+          ui.label("drag empty space to place, drag to move, drag the handle to resize, Delete to remove");
+        }
+        Tool::Emitter => {
+          ui.add(egui::Slider::new(&mut emitter_tool.rate, 1.0..=200.0).text("rate"));
+          ui.add(egui::Slider::new(&mut emitter_tool.speed, 0.0..=1000.0).text("speed"));
+          ui.label("click to place, drag to aim, Delete to remove");
+        }
+        Tool::Drain => {
+          ui.add(egui::Slider::new(&mut drain_tool.radius, DRAIN_RADIUS_RANGE).text("radius"));
+          ui.checkbox(&mut drain_tool.limited, "limit flow rate");
+          ui.add_enabled(
+            drain_tool.limited,
+            egui::Slider::new(&mut drain_tool.max_rate, 1.0..=500.0).text("particles/s"),
+          );
+          ui.label("click to place, scroll to resize, Delete to remove");
+        }
+        Tool::Impulse => {
+          ui.horizontal(|ui| {
+            ui.radio_value(&mut impulse_tool.lasso, false, "rectangle");
+            ui.radio_value(&mut impulse_tool.lasso, true, "lasso");
+          });
+          ui.horizontal(|ui| {
+            ui.radio_value(&mut impulse_tool.set_velocity, false, "add impulse");
+            ui.radio_value(&mut impulse_tool.set_velocity, true, "set velocity");
+          });
+          ui.horizontal(|ui| {
+            ui.add(egui::DragValue::new(&mut impulse_tool.velocity.x).prefix("x: "));
+            ui.add(egui::DragValue::new(&mut impulse_tool.velocity.y).prefix("y: "));
+          });
+          ui.label("drag to select, release to apply");
+        }
+        Tool::Stir => {
+          ui.add(egui::Slider::new(&mut stir_tool.radius, STIR_RADIUS_RANGE).text("radius"));
+          ui.add(egui::Slider::new(&mut stir_tool.strength, 0.0..=20000.0).text("strength"));
+          ui.checkbox(&mut stir_tool.clockwise, "clockwise");
+          ui.label("hold to stir, scroll to resize");
+        }
+        Tool::Heat => {
+          ui.add(egui::Slider::new(&mut heat_tool.radius, HEAT_RADIUS_RANGE).text("radius"));
+          ui.add(egui::Slider::new(&mut heat_tool.rate, -50.0..=50.0).text("degrees/s"));
+          ui.label("hold to heat, or cool with a negative rate; scroll to resize");
+        }
+        Tool::Ruler => match ruler_tool.length() {
+          Some(length) => {
+            ui.label(format!("{length:.1} units"));
+            ui.label(format!("{:.2} × smoothing radius", length / config.smoothing_radius));
+          }
+          None => {
+            ui.label("drag to measure; ticks mark each smoothing radius");
+          }
+        },
+        Tool::Zone => {
+          ui.horizontal(|ui| {
+            for (name, overrides) in ZoneOverrides::PRESETS {
+              ui.selectable_value(&mut zone_tool.overrides, overrides, name);
+            }
+          });
+          ui.add(egui::Slider::new(&mut zone_tool.radius, ZONE_RADIUS_RANGE).text("radius"));
+          ui.add(egui::Slider::new(&mut zone_tool.overrides.gravity_scale, 0.0..=2.0).text("gravity ×"));
+          ui.add(egui::Slider::new(&mut zone_tool.overrides.viscosity_scale, 0.0..=20.0).text("viscosity ×"));
+          ui.add(egui::Slider::new(&mut zone_tool.overrides.damping, 0.0..=10.0).text("damping/s"));
+          ui.label("drag to paint, Shift-drag to erase, scroll to resize");
+        }
+        Tool::Pin => {
+          ui.label("drag a rectangle to pin the particles inside, Shift to unpin");
+        }
+      }
+    });
+}
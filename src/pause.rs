@@ -0,0 +1,174 @@
+use bevy::{ecs::system::SystemParam, prelude::*, window::WindowFocused};
+
+use crate::replay::{InputRecorder, InputReplay};
+
+pub const MIN_TIME_SCALE: f32 = 0.1;
+pub const MAX_TIME_SCALE: f32 = 4.0;
+const TIME_SCALE_STEPS: [f32; 7] = [0.1, 0.25, 0.5, 1.0, 1.5, 2.0, 4.0];
+
+/// Whether the physics advances. Only `Running` steps it; `Paused` and
+/// `Editing` both hold it still, the latter for tools that edit the scene
+/// and want to tell a deliberate edit apart from a pause. `Loading` holds it
+/// while a scene file is read and spawned, and ends in `Running`. Rendering
+/// and camera controls keep running in every state.
+#[derive(States, Clone, Copy, PartialEq, Eq, Hash, Debug, Default)]
+pub enum SimState {
+  #[default]
+  Running,
+  Paused,
+  Editing,
+  Loading,
+}
+
+/// A single step requested while the simulation is held, cleared at the end
+/// of the frame it ran in.
+#[derive(Resource, Default)]
+pub struct SingleStep(bool);
+
+/// Whether losing window focus pauses the simulation until it comes back.
+/// Off with `--run-unfocused`, and never while recording or replaying, since
+/// the pause wouldn't be part of the session.
+#[derive(Resource)]
+pub struct AutoPause {
+  pub on_focus_loss: bool,
+}
+
+impl Default for AutoPause {
+  fn default() -> Self {
+    AutoPause { on_focus_loss: true }
+  }
+}
+
+/// Pausing, resuming and stepping, for the hotkeys, console and panels.
+/// None of them do anything while a scene loads.
+#[derive(SystemParam)]
+pub struct PauseControl<'w> {
+  state: Res<'w, State<SimState>>,
+  next: ResMut<'w, NextState<SimState>>,
+  step: ResMut<'w, SingleStep>,
+}
+
+impl PauseControl<'_> {
+  pub fn pause(&mut self) {
+    self.set(SimState::Paused);
+  }
+
+  pub fn resume(&mut self) {
+    self.set(SimState::Running);
+  }
+
+  pub fn edit(&mut self) {
+    self.set(SimState::Editing);
+  }
+
+  /// Pauses, after advancing one step this frame.
+  pub fn step(&mut self) {
+    if *self.state.get() != SimState::Loading {
+      self.next.set(SimState::Paused);
+      self.step.0 = true;
+    }
+  }
+
+  fn set(&mut self, state: SimState) {
+    if *self.state.get() != SimState::Loading {
+      self.next.set(state);
+    }
+  }
+}
+
+/// Space pauses and resumes the physics; while it is held, `.` advances it
+/// by a single step.
+pub fn toggle_pause(
+  keys: Res<ButtonInput<KeyCode>>,
+  state: Res<State<SimState>>,
+  mut control: PauseControl,
+) {
+  if *state.get() == SimState::Loading {
+    return;
+  }
+  if keys.just_pressed(KeyCode::Space) {
+    if *state.get() == SimState::Running {
+      control.pause();
+      info!("simulation paused");
+    } else {
+      control.resume();
+      info!("simulation resumed");
+    }
+  } else if *state.get() != SimState::Running && keys.just_pressed(KeyCode::Period) {
+    control.step();
+  }
+}
+
+/// Pauses when the app loses focus and resumes when it gets it back, unless
+/// the simulation was already held when focus went. Moving between the
+/// primary and diagnostics windows keeps it running.
+pub fn pause_on_focus_loss(
+  mut focus_events: EventReader<WindowFocused>,
+  window_query: Query<&Window>,
+  settings: Res<AutoPause>,
+  state: Res<State<SimState>>,
+  recorder: Res<InputRecorder>,
+  replay: Option<Res<InputReplay>>,
+  mut control: PauseControl,
+  mut auto_paused: Local<bool>,
+) {
+  if focus_events.read().last().is_none() {
+    return;
+  }
+  if !settings.on_focus_loss || recorder.recording() || replay.is_some() {
+    return;
+  }
+
+  let focused = window_query.iter().any(|window| window.focused);
+  if !focused && *state.get() == SimState::Running {
+    control.pause();
+    *auto_paused = true;
+  } else if focused && *auto_paused {
+    if *state.get() == SimState::Paused {
+      control.resume();
+    }
+    *auto_paused = false;
+  }
+}
+
+/// Run condition for every system that advances the physics. A state change
+/// asked for earlier in the frame counts straight away, so a pause from the
+/// console or a panel stops the very next step.
+pub fn simulation_running(
+  state: Res<State<SimState>>,
+  next: Res<NextState<SimState>>,
+  step: Res<SingleStep>,
+) -> bool {
+  let state = match *next {
+    NextState::Pending(next) => next,
+    NextState::Unchanged => *state.get(),
+  };
+  state == SimState::Running || step.0
+}
+
+pub fn finish_step(mut step: ResMut<SingleStep>) {
+  step.0 = false;
+}
+
+/// `[` and `]` step the speed of virtual time, which every physics system
+/// takes its dt from, down and up; `\` returns to real time.
+pub fn time_scale_hotkeys(
+  keys: Res<ButtonInput<KeyCode>>,
+  mut time: ResMut<Time<Virtual>>,
+) {
+  let speed = time.relative_speed();
+  let next = if keys.just_pressed(KeyCode::BracketLeft) {
+    TIME_SCALE_STEPS.iter().rev().find(|&&step| step < speed - 1e-3).copied()
+  } else if keys.just_pressed(KeyCode::BracketRight) {
+    TIME_SCALE_STEPS.iter().find(|&&step| step > speed + 1e-3).copied()
+  } else if keys.just_pressed(KeyCode::Backslash) {
+    Some(1.0)
+  } else {
+    None
+  };
+
+  if let Some(speed) = next {
+    time.set_relative_speed(speed);
+    info!("time scale {speed}x");
+  }
+}
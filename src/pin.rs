@@ -0,0 +1,17 @@
+use bevy::prelude::*;
+
+#[cfg(feature = "render")]
+use crate::{theme::Theme, Particle};
+
+/// A particle held in place: it still counts towards its neighbours'
+/// density and pushes on them, but nothing moves it, as if it had infinite
+/// mass.
+#[derive(Component)]
+pub struct Pinned;
+
+#[cfg(feature = "render")]
+pub fn draw_pinned(pinned_query: Query<&Particle, With<Pinned>>, mut gizmos: Gizmos, theme: Res<Theme>) {
+  for particle in &pinned_query {
+    gizmos.rect_2d(Isometry2d::from_translation(particle.position.truncate()), Vec2::splat(6.0), theme.foreground);
+  }
+}
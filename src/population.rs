@@ -0,0 +1,102 @@
+use bevy::prelude::*;
+use rand::Rng;
+
+use crate::{
+  domain::{DomainId, Domains},
+  kernels::smoothing_kernel,
+  rng::SimulationRng,
+  scenario::SpawnOptions,
+  solver::SimulationState,
+  spawn::{Region, SpawnParticles},
+  Particle, SimulationConfig, SimulationMode,
+};
+
+// particles added or removed per domain each frame, so a big change settles
+// in over a moment instead of all landing on one frame
+const MAX_CHANGE_PER_FRAME: usize = 40;
+// random spots tried for each new particle, the emptiest one wins
+const SPAWN_CANDIDATES: usize = 8;
+
+/// Set when the particle slider moves: every domain is brought to
+/// `SpawnOptions::particles`, then left alone so emitters and drains can
+/// change the count again.
+#[derive(Resource, Default)]
+pub struct PopulationTarget {
+  pub active: bool,
+}
+
+/// New particles go where the fluid is thinnest and the densest particles
+/// are the ones removed, so the fluid isn't disturbed more than it has to
+/// be. Runs before the density pass, which resizes `SimulationState` to the
+/// new count.
+pub fn adjust_particle_count(
+  mut commands: Commands,
+  particle_query: Query<(Entity, &Particle, &DomainId)>,
+  mut spawns: EventWriter<SpawnParticles>,
+  mut target: ResMut<PopulationTarget>,
+  spawn: Res<SpawnOptions>,
+  state: Res<SimulationState>,
+  domains: Res<Domains>,
+  mode: Res<SimulationMode>,
+  config: Res<SimulationConfig>,
+  mut rng: ResMut<SimulationRng>,
+) {
+  if !target.active {
+    return;
+  }
+
+  let wanted = spawn.particles as usize;
+  let dimension = mode.dimension();
+  let rng = &mut rng.0;
+  let mut settled = true;
+
+  for i in 0..domains.params.len() {
+    let domain = DomainId(i);
+    let mut members: Vec<(Entity, Vec2, f32)> = particle_query
+      .iter()
+      .filter(|(_, _, particle_domain)| **particle_domain == domain)
+      .map(|(entity, particle, _)| (entity, particle.position.truncate(), state.density_of(entity)))
+      .collect();
+
+    if members.len() > wanted {
+      let excess = members.len() - wanted;
+      members.sort_by(|a, b| b.2.total_cmp(&a.2));
+      for (entity, _, _) in members.iter().take(excess.min(MAX_CHANGE_PER_FRAME)) {
+        commands.entity(*entity).despawn_recursive();
+      }
+      settled &= excess <= MAX_CHANGE_PER_FRAME;
+      continue;
+    }
+
+    let missing = wanted - members.len();
+    let (center, half) = domains.bounds(i);
+    let config = domains.config(i, &config);
+    let mut positions: Vec<Vec2> = members.iter().map(|(_, position, _)| *position).collect();
+
+    for _ in 0..missing.min(MAX_CHANGE_PER_FRAME) {
+      let density_at = |point: Vec2| -> f32 {
+        positions
+          .iter()
+          .map(|position| smoothing_kernel(dimension, config.smoothing_radius, position.distance(point)))
+          .sum()
+      };
+
+      let best = (0..SPAWN_CANDIDATES)
+        .map(|_| Vec2::new(rng.gen_range(-half.x..half.x), rng.gen_range(-half.y..half.y)))
+        .filter(|offset| config.container.contains(*offset, half))
+        .map(|offset| center + offset)
+        .min_by(|a, b| density_at(*a).total_cmp(&density_at(*b)));
+      let Some(position) = best else {
+        continue;
+      };
+
+      spawns.send(SpawnParticles::new(Region::Point(position), 1));
+      positions.push(position);
+    }
+    settled &= missing <= MAX_CHANGE_PER_FRAME;
+  }
+
+  if settled {
+    target.active = false;
+  }
+}
@@ -0,0 +1,110 @@
+use std::{fs, io, path::PathBuf};
+
+use bevy::prelude::*;
+#[cfg(feature = "egui")]
+use bevy_egui::egui;
+
+use crate::{schema, SimulationConfig};
+
+const PRESET_DIR: &str = "presets";
+const PRESET_EXTENSION: &str = "ron";
+
+/// Named `SimulationConfig` files in `presets/`, plus the state of the
+/// save/load controls in the parameter panel.
+#[derive(Resource)]
+#[cfg_attr(not(feature = "egui"), allow(dead_code))]
+pub struct Presets {
+  name: String,
+  available: Vec<String>,
+  status: Option<String>,
+}
+
+impl Default for Presets {
+  fn default() -> Self {
+    Presets { name: "default".into(), available: list_presets(), status: None }
+  }
+}
+
+fn preset_path(name: &str) -> PathBuf {
+  PathBuf::from(PRESET_DIR).join(name).with_extension(PRESET_EXTENSION)
+}
+
+fn list_presets() -> Vec<String> {
+  let Ok(entries) = fs::read_dir(PRESET_DIR) else {
+    return Vec::new();
+  };
+
+  let mut names: Vec<String> = entries
+    .filter_map(|entry| Some(entry.ok()?.path()))
+    .filter(|path| path.extension().is_some_and(|ext| ext == PRESET_EXTENSION))
+    .filter_map(|path| Some(path.file_stem()?.to_str()?.to_owned()))
+    .collect();
+  names.sort();
+  names
+}
+
+pub fn save_preset(name: &str, config: &SimulationConfig) -> io::Result<()> {
+  let text = schema::to_string(config).map_err(io::Error::other)?;
+  fs::create_dir_all(PRESET_DIR)?;
+  fs::write(preset_path(name), text)
+}
+
+pub fn load_preset(name: &str) -> io::Result<SimulationConfig> {
+  let text = fs::read_to_string(preset_path(name))?;
+  schema::from_str(&text).map_err(io::Error::other)
+}
+
+#[cfg(feature = "egui")]
+impl Presets {
+  /// Name field, save button and a picker of saved presets.
+  pub fn ui(&mut self, ui: &mut egui::Ui, config: &mut SimulationConfig) {
+    ui.horizontal(|ui| {
+      ui.add(egui::TextEdit::singleline(&mut self.name).desired_width(120.0));
+
+      let name = self.name.trim().to_owned();
+      // names become file names, so keep them to a single path component
+      let valid = !name.is_empty() && !name.contains(['/', '\\', '.']);
+      if ui.add_enabled(valid, egui::Button::new("Save")).clicked() {
+        self.status = Some(match save_preset(&name, config) {
+          Ok(()) => {
+            info!("saved preset {name}");
+            self.available = list_presets();
+            format!("saved {name}")
+          }
+          Err(err) => {
+            warn!("could not save preset {name}: {err}");
+            format!("could not save: {err}")
+          }
+        });
+      }
+
+      egui::ComboBox::from_id_salt("presets")
+        .selected_text("Load")
+        .show_ui(ui, |ui| {
+          for preset in &self.available {
+            if ui.selectable_label(false, preset).clicked() {
+              self.status = Some(match load_preset(preset) {
+                Ok(loaded) => {
+                  info!("loaded preset {preset}");
+                  *config = loaded;
+                  self.name = preset.clone();
+                  format!("loaded {preset}")
+                }
+                Err(err) => {
+                  warn!("could not load preset {preset}: {err}");
+                  format!("could not load: {err}")
+                }
+              });
+            }
+          }
+          if self.available.is_empty() {
+            ui.label("no saved presets");
+          }
+        });
+    });
+
+    if let Some(status) = &self.status {
+      ui.small(status);
+    }
+  }
+}
@@ -0,0 +1,254 @@
+use std::any::Any;
+
+use bevy::prelude::*;
+use fluid_core::forces;
+
+use crate::{
+  kernels::{smoothing_kernel_dx, Dimension, SphVector},
+  solver::{calculate_pressure_force, SimulationState},
+  SimulationConfig, TARGET_DENSITY,
+};
+
+// correction passes per step for the iterative solvers
+const ITERATIONS: usize = 3;
+// softens the PBF constraint, relative to the gradient of one close neighbour
+const RELAXATION: f32 = 0.1;
+
+/// How the pressure step keeps the fluid near its rest density, chosen per
+/// domain in `SimulationConfig::pressure_solver`.
+#[derive(Clone, Copy, PartialEq, Eq, Default, Debug, Reflect, serde::Serialize, serde::Deserialize)]
+pub enum PressureMethod {
+  /// Pressure proportional to the density error, from `pressure_multiplier`.
+  #[default]
+  StateEquation,
+  /// Position based fluids: moves particles apart until the density
+  /// constraint holds, then turns the moves into velocity.
+  Pbf,
+  /// Predictive-corrective SPH: raises each pressure until the densities it
+  /// predicts for the next step are back at rest.
+  Pcisph,
+}
+
+impl PressureMethod {
+  pub const ALL: [PressureMethod; 3] = [PressureMethod::StateEquation, PressureMethod::Pbf, PressureMethod::Pcisph];
+
+  pub fn label(self) -> &'static str {
+    match self {
+      PressureMethod::StateEquation => "state-equation",
+      PressureMethod::Pbf => "pbf",
+      PressureMethod::Pcisph => "pcisph",
+    }
+  }
+
+  pub fn from_label(label: &str) -> Option<Self> {
+    Self::ALL.into_iter().find(|method| method.label() == label)
+  }
+
+  /// A solver with fresh buffers.
+  pub fn solver<V: SphVector>(self) -> Box<dyn PressureSolver<V>> {
+    match self {
+      PressureMethod::StateEquation => Box::new(StateEquation),
+      PressureMethod::Pbf => Box::<PositionBased<V>>::default(),
+      PressureMethod::Pcisph => Box::<Predictive<V>>::default(),
+    }
+  }
+}
+
+/// What a solver sees of one domain's fluid for a step. `positions` are
+/// every particle's predicted positions in query order, and `dt` the step
+/// the accelerations will be applied over.
+pub struct PressureStep<'a, V> {
+  pub positions: &'a [V],
+  pub state: &'a SimulationState,
+  pub config: &'a SimulationConfig,
+  pub pressure_scale: f32,
+  pub dt: f32,
+}
+
+impl<V: SphVector> PressureStep<'_, V> {
+  /// Neighbours of `sample` it interacts with, itself included.
+  fn interacting(&self, sample: usize) -> impl Iterator<Item = usize> + '_ {
+    let layers = &self.state.layers;
+    self.state.neighbours(sample).iter().copied().filter(move |&i| i == sample || layers[i].interacts(layers[sample]))
+  }
+
+  /// The iterative solvers' rest density: particles half a smoothing radius
+  /// apart, the usual SPH resolution, scaled by how far the particle's
+  /// target density is from the default. The state equation's targets sit
+  /// far above any density the fluid reaches, so enforcing them as
+  /// constraints would crush it.
+  fn rest_density(&self, sample: usize) -> f32 {
+    let target = self.state.materials[sample].map_or(self.config.target_density, |material| material.rest_density);
+    let spacing = self.config.smoothing_radius / 2.0;
+    let cell = match V::DIMENSION {
+      Dimension::Two => spacing * spacing,
+      Dimension::Three => spacing * spacing * spacing,
+    };
+    self.config.mass / cell * target / TARGET_DENSITY
+  }
+
+  /// The kernel gradient at `sample` for each other neighbour it interacts
+  /// with, pointing towards that neighbour, measured at `positions`.
+  fn gradients<'a>(&'a self, positions: &'a [V], sample: usize) -> impl Iterator<Item = (usize, V)> + 'a {
+    let radius = self.config.smoothing_radius;
+    self.interacting(sample).filter_map(move |i| {
+      let offset = positions[i] - positions[sample];
+      let dist = offset.length();
+      (i != sample && dist > 0.0).then(|| (i, offset / dist * smoothing_kernel_dx(V::DIMENSION, radius, dist)))
+    })
+  }
+}
+
+/// Turns a domain's densities into pressure accelerations. Solvers keep
+/// whatever buffers they need between steps; switching method replaces the
+/// solver, so they start over.
+pub trait PressureSolver<V: SphVector>: Send + Sync {
+  /// Writes the pressure acceleration of each of the domain's `members`.
+  fn solve(&mut self, step: &PressureStep<V>, members: &[usize], accelerations: &mut [V]);
+}
+
+pub struct StateEquation;
+
+impl<V: SphVector> PressureSolver<V> for StateEquation {
+  fn solve(&mut self, step: &PressureStep<V>, members: &[usize], accelerations: &mut [V]) {
+    for &i in members {
+      let force = calculate_pressure_force(step.positions, step.state.neighbours(i), step.state, i, step.config, step.pressure_scale);
+      accelerations[i] = force / step.state.densities[i];
+    }
+  }
+}
+
+/// Corrected positions and the constraint multipliers of the last pass.
+#[derive(Default)]
+pub struct PositionBased<V> {
+  positions: Vec<V>,
+  lambdas: Vec<f32>,
+  moves: Vec<V>,
+}
+
+impl<V: SphVector> PressureSolver<V> for PositionBased<V> {
+  fn solve(&mut self, step: &PressureStep<V>, members: &[usize], accelerations: &mut [V]) {
+    if step.dt <= 0.0 {
+      return;
+    }
+    let (radius, mass) = (step.config.smoothing_radius, step.config.mass);
+    self.positions.clear();
+    self.positions.extend_from_slice(step.positions);
+    self.lambdas.resize(step.positions.len(), 0.0);
+    self.moves.resize(step.positions.len(), V::default());
+
+    for _ in 0..ITERATIONS {
+      for &i in members {
+        let rest = step.rest_density(i);
+        let density = forces::density(&self.positions, step.interacting(i), i, radius, mass);
+        // only compression is corrected, or the fluid clumps
+        let constraint = (density / rest - 1.0).max(0.0);
+        let (mut own, mut others) = (V::default(), 0.0);
+        for (_, gradient) in step.gradients(&self.positions, i) {
+          let gradient = gradient * (mass / rest);
+          own += gradient;
+          others += squared(gradient);
+        }
+        let scale = mass * smoothing_kernel_dx(V::DIMENSION, radius, 0.0) / rest;
+        self.lambdas[i] = -constraint / (squared(own) + others + RELAXATION * scale * scale);
+      }
+      for &i in members {
+        let rest = step.rest_density(i);
+        self.moves[i] = step
+          .gradients(&self.positions, i)
+          .fold(V::default(), |total, (j, gradient)| total + gradient * ((self.lambdas[i] + self.lambdas[j]) * mass / rest));
+      }
+      for &i in members {
+        self.positions[i] += self.moves[i] * step.pressure_scale;
+      }
+    }
+
+    for &i in members {
+      accelerations[i] = (self.positions[i] - step.positions[i]) / (step.dt * step.dt);
+    }
+  }
+}
+
+/// Accumulated pressures, each particle's correction factor and the
+/// positions the current pressures predict.
+#[derive(Default)]
+pub struct Predictive<V> {
+  pressures: Vec<f32>,
+  factors: Vec<f32>,
+  predicted: Vec<V>,
+}
+
+impl<V: SphVector> PressureSolver<V> for Predictive<V> {
+  fn solve(&mut self, step: &PressureStep<V>, members: &[usize], accelerations: &mut [V]) {
+    if step.dt <= 0.0 {
+      return;
+    }
+    let (radius, mass) = (step.config.smoothing_radius, step.config.mass);
+    self.pressures.resize(step.positions.len(), 0.0);
+    self.factors.resize(step.positions.len(), 0.0);
+    self.predicted.clear();
+    self.predicted.extend_from_slice(step.positions);
+
+    for &i in members {
+      self.pressures[i] = 0.0;
+      accelerations[i] = V::default();
+      // how far the density falls per unit of pressure, from the current neighbourhood
+      let (mut sum, mut squares) = (V::default(), 0.0);
+      for (_, gradient) in step.gradients(step.positions, i) {
+        sum += gradient;
+        squares += squared(gradient);
+      }
+      let rest = step.rest_density(i);
+      let beta = 2.0 * (step.dt * mass / rest).powi(2);
+      let denominator = beta * (squared(sum) + squares);
+      self.factors[i] = if denominator > 0.0 { 1.0 / denominator } else { 0.0 };
+    }
+
+    for _ in 0..ITERATIONS {
+      for &i in members {
+        self.predicted[i] = step.positions[i] + accelerations[i] * (step.dt * step.dt);
+      }
+      for &i in members {
+        let density = forces::density(&self.predicted, step.interacting(i), i, radius, mass);
+        self.pressures[i] += self.factors[i] * (density - step.rest_density(i)).max(0.0);
+      }
+      for &i in members {
+        let rest = step.rest_density(i);
+        let push = step
+          .gradients(step.positions, i)
+          .fold(V::default(), |total, (j, gradient)| total + gradient * ((self.pressures[i] + self.pressures[j]) * mass / (rest * rest)));
+        accelerations[i] = push * -step.pressure_scale;
+      }
+    }
+  }
+}
+
+fn squared<V: SphVector>(vector: V) -> f32 {
+  let length = vector.length();
+  length * length
+}
+
+/// Each domain's method and solver as of the last pressure step, so a solver
+/// keeps its buffers until its domain switches method.
+#[derive(Default)]
+pub struct PressureSolvers {
+  solvers: Vec<Option<(PressureMethod, Box<dyn Any + Send + Sync>)>>,
+}
+
+impl PressureSolvers {
+  pub fn get<V: SphVector>(&mut self, domain: usize, method: PressureMethod) -> &mut dyn PressureSolver<V> {
+    if self.solvers.len() <= domain {
+      self.solvers.resize_with(domain + 1, || None);
+    }
+    let slot = &mut self.solvers[domain];
+    let current = slot.as_ref().is_some_and(|(active, solver)| *active == method && solver.is::<Box<dyn PressureSolver<V>>>());
+    if !current {
+      if slot.is_some() {
+        info!("domain {domain} switched to the {} pressure solver", method.label());
+      }
+      *slot = Some((method, Box::new(method.solver::<V>())));
+    }
+    let (_, solver) = slot.as_mut().expect("filled above");
+    solver.downcast_mut::<Box<dyn PressureSolver<V>>>().expect("checked above").as_mut()
+  }
+}
@@ -0,0 +1,65 @@
+use bevy::prelude::*;
+#[cfg(feature = "egui")]
+use bevy::window::PrimaryWindow;
+#[cfg(feature = "egui")]
+use bevy_egui::{egui, EguiContexts};
+
+#[cfg(feature = "egui")]
+use crate::{
+  field::{FieldSample, FluidField},
+  follow::{cursor_world_position, FollowCamera},
+};
+
+#[cfg(feature = "egui")]
+const READOUT_OFFSET: f32 = 16.0;
+
+/// Whether the cursor readout is shown, toggled with P.
+#[derive(Resource, Default)]
+pub struct Probe {
+  pub enabled: bool,
+}
+
+pub fn toggle_probe(keys: Res<ButtonInput<KeyCode>>, mut probe: ResMut<Probe>) {
+  if keys.just_pressed(KeyCode::KeyP) {
+    probe.enabled = !probe.enabled;
+  }
+}
+
+#[cfg(feature = "egui")]
+/// SPH interpolation of density, pressure and velocity at the cursor, from
+/// the particles of the domain under it, shown next to the cursor.
+pub fn probe_readout(
+  mut contexts: EguiContexts,
+  window_query: Query<&Window, With<PrimaryWindow>>,
+  camera_query: Query<(&Camera, &GlobalTransform), With<FollowCamera>>,
+  field: FluidField,
+  probe: Res<Probe>,
+) {
+  if !probe.enabled {
+    return;
+  }
+
+  let Some(screen) = window_query.get_single().ok().and_then(|window| window.cursor_position()) else {
+    return;
+  };
+  let Some(cursor) = cursor_world_position(&window_query, &camera_query) else {
+    return;
+  };
+  let point = cursor.extend(0.0);
+  if field.domain_at(point).is_none() {
+    return;
+  }
+
+  let FieldSample { density, pressure, velocity } = field.sample(point);
+
+  egui::Area::new(egui::Id::new("probe"))
+    .fixed_pos([screen.x + READOUT_OFFSET, screen.y + READOUT_OFFSET])
+    .interactable(false)
+    .show(contexts.ctx_mut(), |ui| {
+      egui::Frame::popup(ui.style()).show(ui, |ui| {
+        ui.label(format!("density  {density:.3}"));
+        ui.label(format!("pressure {pressure:.1}"));
+        ui.label(format!("velocity ({:.1}, {:.1})", velocity.x, velocity.y));
+      });
+    });
+}
@@ -0,0 +1,60 @@
+use bevy::{ecs::system::SystemParam, prelude::*};
+use fluid_core::neighbours::{NeighborSearch, UniformGrid};
+
+use crate::{spawn::Region, Particle, SimulationConfig};
+
+/// Every particle's entity and position in the xy plane as of the end of the
+/// last frame, in cells a smoothing radius wide, for `FluidQuery`.
+#[derive(Resource, Default)]
+pub struct ParticleIndex {
+  grid: UniformGrid,
+  entities: Vec<Entity>,
+  positions: Vec<Vec2>,
+}
+
+pub fn index_regions(
+  particle_query: Query<(Entity, &Particle)>,
+  mut index: ResMut<ParticleIndex>,
+  config: Res<SimulationConfig>,
+) {
+  let index = &mut *index;
+  index.entities = particle_query.iter().map(|(entity, _)| entity).collect();
+  index.positions = particle_query.iter().map(|(_, particle)| particle.position.truncate()).collect();
+  let members: Vec<usize> = (0..index.positions.len()).collect();
+  index.grid.build(&index.positions, &members, config.smoothing_radius);
+}
+
+/// Finds particles by area without visiting every one, for tools and game
+/// logic. Positions are those at the end of the last frame, so particles
+/// moved since, e.g. by another tool this frame, are found where they were;
+/// in 3D the areas are columns through the box. Results are in particle
+/// query order and may include particles despawned this frame.
+#[derive(SystemParam)]
+pub struct FluidQuery<'w> {
+  index: Res<'w, ParticleIndex>,
+}
+
+impl FluidQuery<'_> {
+  pub fn particles_in_rect(&self, center: Vec2, half_size: Vec2) -> Vec<Entity> {
+    self.matching(center - half_size, center + half_size, |_| true)
+  }
+
+  pub fn particles_in_circle(&self, center: Vec2, radius: f32) -> Vec<Entity> {
+    self.matching(center - radius, center + radius, |position| position.distance(center) <= radius)
+  }
+
+  pub fn particles_in(&self, region: Region) -> Vec<Entity> {
+    match region {
+      Region::Domains => self.index.entities.clone(),
+      Region::Point(at) => self.particles_in_rect(at, Vec2::ZERO),
+      Region::Rect { center, half_size } => self.particles_in_rect(center, half_size),
+      Region::Circle { center, radius } => self.particles_in_circle(center, radius),
+    }
+  }
+
+  fn matching(&self, min: Vec2, max: Vec2, keep: impl Fn(Vec2) -> bool) -> Vec<Entity> {
+    let mut found = Vec::new();
+    self.index.grid.query_box(&self.index.positions, min, max, &mut found);
+    found.into_iter().filter(|&i| keep(self.index.positions[i])).map(|i| self.index.entities[i]).collect()
+  }
+}
@@ -0,0 +1,202 @@
+use bevy::{prelude::*, sprite::AlphaMode2d};
+
+use crate::{domain::DomainId, Particle, SimulationConfig};
+
+/// Render layer layout: 0 is the world every camera sees, the rest split
+/// off content meant for a single camera.
+pub const SPLAT_LAYER: usize = 1;
+pub const MINIMAP_LAYER: usize = 2;
+pub const MAIN_VIEW_LAYER: usize = 3;
+
+// in particle radii
+const CONTACT_RADII: f32 = 3.0;
+const BLEND_RATE: f32 = 2.0;
+// how quickly ellipses elongate with speed, and the most they can
+const ANISOTROPY_PER_SPEED: f32 = 0.004;
+const MAX_ANISOTROPY: f32 = 4.0;
+
+#[derive(Resource, Default)]
+pub struct RenderSettings {
+  pub motion_blur: bool,
+  pub blend_source_colors: bool,
+  pub metaballs: bool,
+  pub anisotropic: bool,
+  pub obstacle_glow: bool,
+}
+
+/// Tint a particle was spawned with (its emitter's colour), the tint it
+/// currently has, which drifts towards its neighbours' when blending, and the
+/// colour it is drawn with under the active colour mode.
+#[derive(Component, Clone, serde::Serialize, serde::Deserialize)]
+pub struct ParticleColor {
+  pub source: Color,
+  pub current: Color,
+  pub shown: Color,
+}
+
+impl ParticleColor {
+  pub fn new(color: Color) -> Self {
+    ParticleColor { source: color, current: color, shown: color }
+  }
+}
+
+pub fn toggle_render_settings(
+  keys: Res<ButtonInput<KeyCode>>,
+  mut settings: ResMut<RenderSettings>,
+) {
+  if keys.just_pressed(KeyCode::KeyB) {
+    settings.motion_blur = !settings.motion_blur;
+  }
+  if keys.just_pressed(KeyCode::KeyM) {
+    settings.blend_source_colors = !settings.blend_source_colors;
+  }
+  if keys.just_pressed(KeyCode::KeyG) {
+    settings.metaballs = !settings.metaballs;
+  }
+  if keys.just_pressed(KeyCode::KeyV) {
+    settings.anisotropic = !settings.anisotropic;
+  }
+  if keys.just_pressed(KeyCode::KeyF) {
+    settings.obstacle_glow = !settings.obstacle_glow;
+  }
+}
+
+/// How `sync_transforms` places particles. With `interpolate`, for apps that
+/// step the solver from `FixedUpdate`, each particle is drawn between where
+/// its last step started and ended, by how far the frame is into the next
+/// fixed step, so motion stays smooth when frames and steps don't line up.
+#[derive(Resource, Default)]
+pub struct TransformSync {
+  pub interpolate: bool,
+}
+
+/// Copies each particle's position to its `Transform`, which nothing else in
+/// the simulation writes or reads, after the frame's physics.
+pub fn sync_transforms(
+  mut particle_query: Query<(&Particle, &mut Transform)>,
+  sync: Res<TransformSync>,
+  fixed: Res<Time<Fixed>>,
+) {
+  let alpha = if sync.interpolate { fixed.overstep_fraction() } else { 1.0 };
+  for (particle, mut transform) in &mut particle_query {
+    let translation = particle.previous_position.lerp(particle.position, alpha);
+    // only touch it when it moved, so paused particles don't count as changed
+    if transform.translation != translation {
+      transform.translation = translation;
+    }
+  }
+}
+
+fn stretch(particle: &Particle, size: f32, delta_secs: f32) -> f32 {
+  1.0 + (particle.velocity * delta_secs).length() / (2.0 * size)
+}
+
+/// Aligns each particle with its velocity and shapes it for the enabled
+/// effects. Anisotropic splatting turns it into an area-preserving ellipse
+/// that lengthens with speed, so jets and sheets look continuous; motion blur
+/// also smears it over the distance it covered last frame, which
+/// `update_particle_materials` fades to match. Any metaball splat attached to
+/// the particle inherits the same shape. Runs after `sync_transforms`, which
+/// resets the translation it offsets.
+pub fn orient_particles(
+  mut particle_query: Query<(&Particle, &mut Transform)>,
+  settings: Res<RenderSettings>,
+  config: Res<SimulationConfig>,
+  time: Res<Time>,
+) {
+  if !settings.motion_blur && !settings.anisotropic {
+    if settings.is_changed() {
+      for (_, mut transform) in &mut particle_query {
+        transform.rotation = Quat::IDENTITY;
+        transform.scale = Vec3::ONE;
+      }
+    }
+    return;
+  }
+
+  for (particle, mut transform) in &mut particle_query {
+    let speed = particle.velocity.length();
+    let mut scale = Vec2::ONE;
+
+    if speed > 0.0 {
+      transform.rotation = Quat::from_rotation_arc(Vec3::X, particle.velocity / speed);
+    }
+
+    if settings.anisotropic {
+      let elongation = (1.0 + speed * ANISOTROPY_PER_SPEED).min(MAX_ANISOTROPY);
+      scale *= Vec2::new(elongation, 1.0 / elongation);
+    }
+
+    if settings.motion_blur {
+      transform.translation -= particle.velocity * time.delta_secs() / 2.0;
+      scale.x *= stretch(particle, config.particle_size, time.delta_secs());
+    }
+
+    transform.scale = scale.extend(1.0);
+  }
+}
+
+/// Moves each particle's colour towards the average of the particles it is
+/// touching, so the tint shows how far streams from different emitters have
+/// mixed. Turning blending off snaps everything back to its source colour.
+pub fn blend_source_colors(
+  mut particle_query: Query<(&Particle, &DomainId, &mut ParticleColor)>,
+  settings: Res<RenderSettings>,
+  config: Res<SimulationConfig>,
+  time: Res<Time>,
+) {
+  if !settings.blend_source_colors {
+    if settings.is_changed() {
+      for (_, _, mut color) in &mut particle_query {
+        color.current = color.source;
+      }
+    }
+    return;
+  }
+
+  let colors: Vec<(Vec3, DomainId, LinearRgba)> = particle_query
+    .iter()
+    .map(|(particle, domain, color)| (particle.position, *domain, color.current.to_linear()))
+    .collect();
+
+  let blend = (BLEND_RATE * time.delta_secs()).min(1.0);
+  let contact_radius = CONTACT_RADII * config.particle_size;
+
+  for (particle, domain, mut color) in &mut particle_query {
+    let mut sum = LinearRgba::NONE;
+    let mut count = 0.0;
+
+    for &(position, other_domain, other_color) in &colors {
+      if other_domain == *domain && position.distance(particle.position) < contact_radius {
+        sum += other_color;
+        count += 1.0;
+      }
+    }
+
+    if count > 0.0 {
+      let average = Color::from(sum * (1.0 / count));
+      color.current = color.current.mix(&average, blend);
+    }
+  }
+}
+
+pub fn update_particle_materials(
+  particle_query: Query<(&Particle, &ParticleColor, &MeshMaterial2d<ColorMaterial>)>,
+  mut materials: ResMut<Assets<ColorMaterial>>,
+  settings: Res<RenderSettings>,
+  config: Res<SimulationConfig>,
+  time: Res<Time>,
+) {
+  for (particle, color, handle) in &particle_query {
+    let alpha = if settings.motion_blur {
+      1.0 / stretch(particle, config.particle_size, time.delta_secs())
+    } else {
+      1.0
+    };
+
+    if let Some(material) = materials.get_mut(handle) {
+      material.color = color.shown.with_alpha(alpha);
+      material.alpha_mode = if alpha < 1.0 { AlphaMode2d::Blend } else { AlphaMode2d::Opaque };
+    }
+  }
+}
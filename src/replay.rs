@@ -0,0 +1,390 @@
+use std::{
+  fs,
+  io,
+  path::Path,
+  time::Duration,
+};
+
+use bevy::{
+  input::mouse::{AccumulatedMouseMotion, AccumulatedMouseScroll},
+  prelude::*,
+  time::TimeUpdateStrategy,
+  utils::SystemTime,
+  window::PrimaryWindow,
+};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+  console::ConsoleCommand,
+  force_field::{ForceField, GlobalWind},
+  domain::Domains,
+  initial::InitialConditions,
+  population::PopulationTarget,
+  scenario::{LoadScenario, Scenario, SpawnOptions},
+  scene::{LoadScene, LoadedScene, SceneFile},
+  schema,
+  tools::Tool,
+  SimulationConfig,
+};
+
+const SESSION_DIR: &str = "sessions";
+const RECORD_KEY: KeyCode = KeyCode::F9;
+const REPLAY_EXTENSION: &str = "replay";
+
+/// One frame of input. `time` is when the frame ends, in seconds since the
+/// recording started; everything else only lists what changed.
+#[derive(Serialize, Deserialize, Clone, Default)]
+struct InputFrame {
+  time: f64,
+  #[serde(default, skip_serializing_if = "Vec::is_empty")]
+  pressed: Vec<KeyCode>,
+  #[serde(default, skip_serializing_if = "Vec::is_empty")]
+  released: Vec<KeyCode>,
+  #[serde(default, skip_serializing_if = "Vec::is_empty")]
+  buttons_pressed: Vec<MouseButton>,
+  #[serde(default, skip_serializing_if = "Vec::is_empty")]
+  buttons_released: Vec<MouseButton>,
+  // window position, like `Window::cursor_position`
+  #[serde(default)]
+  cursor: Option<Vec2>,
+  #[serde(default, skip_serializing_if = "is_zero")]
+  scroll: Vec2,
+  #[serde(default, skip_serializing_if = "is_zero")]
+  motion: Vec2,
+  #[serde(default)]
+  pointer_over_ui: bool,
+  #[serde(default, skip_serializing_if = "Vec::is_empty")]
+  commands: Vec<String>,
+  #[serde(default, skip_serializing_if = "Vec::is_empty")]
+  loads: Vec<Scenario>,
+  // the whole scene rather than its path, so the file works anywhere
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  scene: Option<SceneFile>,
+  // the panels are egui, which replayed clicks can't drive, so their
+  // results are recorded instead
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  config: Option<SimulationConfig>,
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  tool: Option<Tool>,
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  wind: Option<Vec2>,
+  // a new target from the particle slider
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  population: Option<u32>,
+}
+
+fn is_zero(v: &Vec2) -> bool {
+  *v == Vec2::ZERO
+}
+
+/// A recorded session, saved as a self-contained `.replay` file to share for
+/// bug reports and showcases: the seed, parameters, domain size, scenario or
+/// scene and initial conditions it started from, and every frame of input
+/// after. The first frame reloads the scenario or scene, so a replay starts
+/// from the same particles.
+#[derive(Serialize, Deserialize)]
+pub struct Session {
+  seed: u64,
+  particles: u32,
+  config: SimulationConfig,
+  // sessions saved before these were recorded start from the defaults
+  #[serde(default)]
+  domain: Option<Vec2>,
+  #[serde(default)]
+  scenario: Scenario,
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  scene: Option<SceneFile>,
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  initial: Option<InitialConditions>,
+  frames: Vec<InputFrame>,
+}
+
+impl Session {
+  pub fn load(path: &Path) -> io::Result<Self> {
+    let text = fs::read_to_string(path)?;
+    schema::from_str(&text).map_err(io::Error::other)
+  }
+
+  fn save(&self, path: &Path) -> io::Result<()> {
+    let text = schema::to_string(self).map_err(io::Error::other)?;
+    fs::create_dir_all(SESSION_DIR)?;
+    fs::write(path, text)
+  }
+
+  /// The spawn seed, initial conditions and parameters the session has to
+  /// start from.
+  pub fn start(&self) -> (SpawnOptions, SimulationConfig) {
+    let spawn = SpawnOptions { particles: self.particles, seed: Some(self.seed), initial: self.initial.clone() };
+    (spawn, self.config.clone())
+  }
+
+  /// The domain size the session was recorded in, if it says.
+  pub fn domain(&self) -> Option<Vec2> {
+    self.domain
+  }
+
+  /// The scenario and scene the session was recorded in.
+  pub fn scene(&self) -> (Scenario, Option<SceneFile>) {
+    (self.scenario, self.scene.clone())
+  }
+
+  /// How long frame `i` has to step, so the replay sees the recorded dt.
+  fn frame_duration(&self, i: usize) -> Option<Duration> {
+    let end = self.frames.get(i)?.time;
+    let start = i.checked_sub(1).map_or(0.0, |previous| self.frames[previous].time);
+    Some(Duration::from_secs_f64((end - start).max(0.0)))
+  }
+
+  pub fn first_frame_duration(&self) -> Duration {
+    self.frame_duration(0).unwrap_or_default()
+  }
+}
+
+/// F9 starts and stops recording to `sessions/`.
+#[derive(Resource, Default)]
+pub struct InputRecorder {
+  session: Option<Session>,
+  last_config: Option<SimulationConfig>,
+  last_tool: Option<Tool>,
+  last_wind: Option<Vec2>,
+}
+
+impl InputRecorder {
+  pub fn recording(&self) -> bool {
+    self.session.is_some()
+  }
+}
+
+/// Starting restarts the current scenario or scene from a seeded layout, so
+/// the recording has a start it can be replayed from.
+pub fn toggle_recording(
+  keys: Res<ButtonInput<KeyCode>>,
+  mut recorder: ResMut<InputRecorder>,
+  mut spawn: ResMut<SpawnOptions>,
+  config: Res<SimulationConfig>,
+  domains: Res<Domains>,
+  current: Res<Scenario>,
+  scene: Res<LoadedScene>,
+  mut loads: EventWriter<LoadScenario>,
+  mut scene_loads: EventWriter<LoadScene>,
+) {
+  if !keys.just_pressed(RECORD_KEY) {
+    return;
+  }
+
+  if let Some(session) = recorder.session.take() {
+    let timestamp = SystemTime::now()
+      .duration_since(SystemTime::UNIX_EPOCH)
+      .map(|duration| duration.as_millis())
+      .unwrap_or_default();
+    let path = Path::new(SESSION_DIR).join(format!("session-{timestamp}.{REPLAY_EXTENSION}"));
+
+    match session.save(&path) {
+      Ok(()) => info!("saved {} recorded frames to {}", session.frames.len(), path.display()),
+      Err(err) => warn!("could not save {}: {err}", path.display()),
+    }
+    return;
+  }
+
+  let seed = *spawn.seed.get_or_insert_with(rand::random);
+  info!("recording input with seed {seed}");
+  *recorder = InputRecorder {
+    session: Some(Session {
+      seed,
+      particles: spawn.particles,
+      config: config.clone(),
+      domain: Some(domains.size),
+      scenario: *current,
+      scene: scene.0.clone(),
+      initial: spawn.initial.clone(),
+      frames: Vec::new(),
+    }),
+    last_config: Some(config.clone()),
+    last_tool: None,
+    last_wind: None,
+  };
+  match &scene.0 {
+    Some(scene) => {
+      scene_loads.send(LoadScene::Inline(Box::new(scene.clone())));
+    }
+    None => {
+      loads.send(LoadScenario(*current));
+    }
+  }
+}
+
+pub fn record_input(
+  mut recorder: ResMut<InputRecorder>,
+  keys: Res<ButtonInput<KeyCode>>,
+  mouse_buttons: Res<ButtonInput<MouseButton>>,
+  mouse_scroll: Res<AccumulatedMouseScroll>,
+  mouse_motion: Res<AccumulatedMouseMotion>,
+  window_query: Query<&Window, With<PrimaryWindow>>,
+  mut console_commands: EventReader<ConsoleCommand>,
+  mut loads: EventReader<LoadScenario>,
+  scene: Res<LoadedScene>,
+  focus: Res<crate::ui::UiFocus>,
+  config: Res<SimulationConfig>,
+  tool: Res<Tool>,
+  wind_query: Query<&ForceField, With<GlobalWind>>,
+  population: Res<PopulationTarget>,
+  spawn: Res<SpawnOptions>,
+  time: Res<Time<Real>>,
+) {
+  let recorder = &mut *recorder;
+  let Some(session) = &mut recorder.session else {
+    console_commands.clear();
+    loads.clear();
+    return;
+  };
+
+  let time = session.frames.last().map_or(0.0, |frame| frame.time) + time.delta_secs_f64();
+  let mut frame = InputFrame {
+    time,
+    pressed: keys.get_just_pressed().filter(|key| **key != RECORD_KEY).copied().collect(),
+    released: keys.get_just_released().filter(|key| **key != RECORD_KEY).copied().collect(),
+    buttons_pressed: mouse_buttons.get_just_pressed().copied().collect(),
+    buttons_released: mouse_buttons.get_just_released().copied().collect(),
+    cursor: window_query.get_single().ok().and_then(|window| window.cursor_position()),
+    scroll: mouse_scroll.delta,
+    motion: mouse_motion.delta,
+    pointer_over_ui: focus.pointer,
+    commands: console_commands.read().map(|ConsoleCommand(line)| line.clone()).collect(),
+    loads: loads.read().map(|LoadScenario(scenario)| *scenario).collect(),
+    scene: scene.0.clone().filter(|_| scene.is_changed()),
+    ..default()
+  };
+
+  // the panels write through their resources every frame, so compare values
+  // rather than trusting change detection
+  if recorder.last_config.as_ref() != Some(&*config) {
+    frame.config = Some(config.clone());
+    recorder.last_config = Some(config.clone());
+  }
+  if recorder.last_tool != Some(*tool) {
+    frame.tool = Some(*tool);
+    recorder.last_tool = Some(*tool);
+  }
+  if let Ok(ForceField::Wind { velocity, .. }) = wind_query.get_single() {
+    if recorder.last_wind != Some(*velocity) {
+      frame.wind = Some(*velocity);
+      recorder.last_wind = Some(*velocity);
+    }
+  }
+
+  if population.is_changed() && population.active {
+    frame.population = Some(spawn.particles);
+  }
+
+  session.frames.push(frame);
+}
+
+/// A session passed with `--replay`, played back in place of live input.
+#[derive(Resource)]
+pub struct InputReplay {
+  session: Session,
+  frame: usize,
+  keys: ButtonInput<KeyCode>,
+  buttons: ButtonInput<MouseButton>,
+}
+
+impl InputReplay {
+  pub fn new(session: Session) -> Self {
+    InputReplay { session, frame: 0, keys: default(), buttons: default() }
+  }
+
+  /// Whether the pointer was over a panel in the frame being replayed.
+  pub fn pointer_over_ui(&self) -> bool {
+    self.session.frames.get(self.frame).is_some_and(|frame| frame.pointer_over_ui)
+  }
+}
+
+/// Overwrites this frame's input with the recorded frame, after bevy has
+/// read the live input.
+pub fn replay_input(
+  mut replay: ResMut<InputReplay>,
+  mut keys: ResMut<ButtonInput<KeyCode>>,
+  mut mouse_buttons: ResMut<ButtonInput<MouseButton>>,
+  mut mouse_scroll: ResMut<AccumulatedMouseScroll>,
+  mut mouse_motion: ResMut<AccumulatedMouseMotion>,
+  mut window_query: Query<&mut Window, With<PrimaryWindow>>,
+  mut console_commands: EventWriter<ConsoleCommand>,
+  mut loads: EventWriter<LoadScenario>,
+  mut scene_loads: EventWriter<LoadScene>,
+  mut config: ResMut<SimulationConfig>,
+  mut tool: ResMut<Tool>,
+  mut wind_query: Query<&mut ForceField, With<GlobalWind>>,
+  mut population: ResMut<PopulationTarget>,
+  mut spawn: ResMut<SpawnOptions>,
+) {
+  let replay = &mut *replay;
+  let Some(frame) = replay.session.frames.get(replay.frame) else {
+    return;
+  };
+
+  replay.keys.clear();
+  for key in &frame.pressed {
+    replay.keys.press(*key);
+  }
+  for key in &frame.released {
+    replay.keys.release(*key);
+  }
+  *keys = replay.keys.clone();
+
+  replay.buttons.clear();
+  for button in &frame.buttons_pressed {
+    replay.buttons.press(*button);
+  }
+  for button in &frame.buttons_released {
+    replay.buttons.release(*button);
+  }
+  *mouse_buttons = replay.buttons.clone();
+
+  mouse_scroll.delta = frame.scroll;
+  mouse_motion.delta = frame.motion;
+  if let Ok(mut window) = window_query.get_single_mut() {
+    window.set_cursor_position(frame.cursor);
+  }
+
+  for line in &frame.commands {
+    console_commands.send(ConsoleCommand(line.clone()));
+  }
+  for scenario in &frame.loads {
+    loads.send(LoadScenario(*scenario));
+  }
+  if let Some(scene) = &frame.scene {
+    scene_loads.send(LoadScene::Inline(Box::new(scene.clone())));
+  }
+  if let Some(recorded) = &frame.config {
+    *config = recorded.clone();
+  }
+  if let Some(recorded) = frame.tool {
+    *tool = recorded;
+  }
+  if let Some(recorded) = frame.population {
+    spawn.particles = recorded;
+    population.active = true;
+  }
+  if let (Some(recorded), Ok(mut field)) = (frame.wind, wind_query.get_single_mut()) {
+    let ForceField::Wind { velocity, .. } = &mut *field;
+    *velocity = recorded;
+  }
+}
+
+/// Moves on to the next recorded frame and steps time by its dt, handing
+/// control back once the session runs out.
+pub fn advance_replay(
+  mut commands: Commands,
+  mut replay: ResMut<InputReplay>,
+  mut strategy: ResMut<TimeUpdateStrategy>,
+) {
+  replay.frame += 1;
+  match replay.session.frame_duration(replay.frame) {
+    Some(dt) => *strategy = TimeUpdateStrategy::ManualDuration(dt),
+    None => {
+      info!("replay finished after {} frames", replay.frame);
+      *strategy = TimeUpdateStrategy::Automatic;
+      commands.remove_resource::<InputReplay>();
+    }
+  }
+}
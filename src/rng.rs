@@ -0,0 +1,22 @@
+use bevy::prelude::*;
+use rand::{rngs::StdRng, SeedableRng};
+
+/// The randomness everything stochastic draws from while the simulation
+/// runs: emitter spread, particle top-ups, console scatters and scripts. It is
+/// reseeded from `SpawnOptions::seed` whenever the particles are respawned,
+/// so a seeded run repeats exactly, and drawn from entropy without a seed.
+#[derive(Resource)]
+pub struct SimulationRng(pub StdRng);
+
+impl SimulationRng {
+  pub fn new(seed: Option<u64>) -> Self {
+    SimulationRng(match seed {
+      Some(seed) => StdRng::seed_from_u64(seed),
+      None => StdRng::from_entropy(),
+    })
+  }
+
+  pub fn reseed(&mut self, seed: Option<u64>) {
+    *self = SimulationRng::new(seed);
+  }
+}
@@ -0,0 +1,127 @@
+use std::collections::VecDeque;
+
+use bevy::prelude::*;
+
+use crate::{
+  render::ParticleColor,
+  scenario::LoadScenario,
+  slots::{self, ParticleSnapshot, SnapshotQuery},
+  Particle, ResetSimulation, SimulationConfig,
+};
+
+// simulated steps between checkpoints
+const DEFAULT_INTERVAL: u32 = 10;
+// ten seconds back at 60 steps a second and the interval above
+const DEFAULT_CAPACITY: usize = 60;
+
+struct RollbackPoint {
+  step: u64,
+  config: SimulationConfig,
+  particles: Vec<ParticleSnapshot>,
+}
+
+/// A ring of checkpoints of every particle and the parameters, taken every
+/// `interval` simulated steps, so the simulation can be rewound after an
+/// explosion or a bad edit. Unlike the timeline it always records, so it
+/// keeps fewer, sparser snapshots. Scene fixtures aren't part of it; undo
+/// covers those.
+#[derive(Resource)]
+pub struct RollbackBuffer {
+  pub interval: u32,
+  pub capacity: usize,
+  points: VecDeque<RollbackPoint>,
+  step: u64,
+  pending: Option<usize>,
+}
+
+impl Default for RollbackBuffer {
+  fn default() -> Self {
+    RollbackBuffer {
+      interval: DEFAULT_INTERVAL,
+      capacity: DEFAULT_CAPACITY,
+      points: VecDeque::new(),
+      step: 0,
+      pending: None,
+    }
+  }
+}
+
+impl RollbackBuffer {
+  /// Steps simulated since the buffer was last cleared.
+  pub fn step(&self) -> u64 {
+    self.step
+  }
+
+  /// Rewinds to the newest checkpoint at least `steps` steps old, or the
+  /// oldest one kept, before the next step runs. Checkpoints after it are
+  /// dropped. Returns how many steps back that is, or `None` while there
+  /// are no checkpoints.
+  pub fn rollback(&mut self, steps: u64) -> Option<u64> {
+    let target = self.step.saturating_sub(steps);
+    let index = self
+      .points
+      .iter()
+      .rposition(|point| point.step <= target)
+      .or((!self.points.is_empty()).then_some(0))?;
+    self.pending = Some(index);
+    Some(self.step - self.points[index].step)
+  }
+
+  fn clear(&mut self) {
+    self.points.clear();
+    self.step = 0;
+    self.pending = None;
+  }
+}
+
+/// Runs with the physics, so only simulated steps count. A scenario load or
+/// restart starts over.
+pub fn record_rollback(
+  mut buffer: ResMut<RollbackBuffer>,
+  particle_query: SnapshotQuery,
+  mut loads: EventReader<LoadScenario>,
+  mut resets: EventReader<ResetSimulation>,
+  config: Res<SimulationConfig>,
+) {
+  if loads.read().count() + resets.read().count() > 0 {
+    buffer.clear();
+  }
+
+  if buffer.step.is_multiple_of(buffer.interval.max(1) as u64) {
+    let point = RollbackPoint {
+      step: buffer.step,
+      config: config.clone(),
+      particles: ParticleSnapshot::capture_all(&particle_query),
+    };
+    buffer.points.push_back(point);
+    while buffer.points.len() > buffer.capacity.max(1) {
+      buffer.points.pop_front();
+    }
+  }
+  buffer.step += 1;
+}
+
+pub fn apply_rollback(
+  mut commands: Commands,
+  mut buffer: ResMut<RollbackBuffer>,
+  mut particle_query: Query<(Entity, &mut Particle, &mut ParticleColor)>,
+  mut meshes: ResMut<Assets<Mesh>>,
+  mut materials: ResMut<Assets<ColorMaterial>>,
+  mut config: ResMut<SimulationConfig>,
+) {
+  let Some(index) = buffer.pending.take() else {
+    return;
+  };
+
+  buffer.points.truncate(index + 1);
+  let point = &buffer.points[index];
+  *config = point.config.clone();
+  slots::restore_in_place(
+    &mut commands, &mut particle_query, &point.particles, config.particle_size, &mut meshes, &mut materials,
+  );
+
+  // the restored checkpoint is retaken on the next step
+  let step = point.step;
+  buffer.points.pop_back();
+  buffer.step = step;
+}
@@ -0,0 +1,282 @@
+use bevy::prelude::*;
+use rand::{rngs::StdRng, Rng};
+
+use crate::{
+  boat::spawn_boats,
+  domain::{DomainId, Domains},
+  drain::Drain,
+  emitter::{demo_emitters, Emitter, EMITTER_INSET},
+  history::SceneEdited,
+  initial::InitialConditions,
+  obstacle::{demo_obstacles, Obstacle},
+  paddle::spawn_paddles,
+  pause::SimState,
+  rng::SimulationRng,
+  scene::{LoadedScene, SceneLoading},
+  zone::Zone,
+  Particle, ParticleSpawn, SimulationConfig, COLOR,
+};
+
+const DIGIT_KEYS: [KeyCode; 9] = [
+  KeyCode::Digit1, KeyCode::Digit2, KeyCode::Digit3,
+  KeyCode::Digit4, KeyCode::Digit5, KeyCode::Digit6,
+  KeyCode::Digit7, KeyCode::Digit8, KeyCode::Digit9,
+];
+// particles a fill spawns in each domain unless told otherwise
+const DEFAULT_PARTICLES: u32 = 1500;
+const RAIN_EMITTERS: usize = 8;
+// rejection sampling gives up after this many tries per particle
+const MAX_SCATTER_ATTEMPTS: u32 = 50;
+
+/// Predefined 2D setups, loaded with the number keys in `ALL` order. The
+/// resource holds the one currently loaded.
+#[derive(Resource, Clone, Copy, PartialEq, Eq, Default, Debug, serde::Serialize, serde::Deserialize)]
+pub enum Scenario {
+  #[default]
+  Default,
+  Rain,
+  Obstacles,
+  Crossfire,
+  FillAndDrain,
+  Paddle,
+  Boat,
+}
+
+impl Scenario {
+  pub const ALL: [Scenario; 7] = [
+    Scenario::Default,
+    Scenario::Rain,
+    Scenario::Obstacles,
+    Scenario::Crossfire,
+    Scenario::FillAndDrain,
+    Scenario::Paddle,
+    Scenario::Boat,
+  ];
+
+  pub fn description(self) -> &'static str {
+    match self {
+      Scenario::Default => "Particles scattered over the whole domain settle into a pool.",
+      Scenario::Rain => "Emitters along the ceiling drip into an empty container.",
+      Scenario::Obstacles => "The default fill falling around a circle and a box.",
+      Scenario::Crossfire => "Two coloured jets fired at each other from opposite walls.",
+      Scenario::FillAndDrain => "An inlet fills the container while a rate-limited drain empties it.",
+      Scenario::Paddle => "Steer a paddle through the fluid with WASD or the right stick.",
+      Scenario::Boat => "A hull floating on the fluid, thrust left and right with A and D.",
+    }
+  }
+
+  /// The label as a command-line name, e.g. `fill-and-drain`.
+  pub fn name(self) -> String {
+    self.label().to_lowercase().replace(' ', "-")
+  }
+
+  pub fn from_name(name: &str) -> Option<Self> {
+    Scenario::ALL.into_iter().find(|scenario| scenario.name() == name)
+  }
+
+  pub fn label(self) -> &'static str {
+    match self {
+      Scenario::Default => "Default",
+      Scenario::Rain => "Rain",
+      Scenario::Obstacles => "Obstacles",
+      Scenario::Crossfire => "Crossfire",
+      Scenario::FillAndDrain => "Fill and drain",
+      Scenario::Paddle => "Paddle",
+      Scenario::Boat => "Boat",
+    }
+  }
+
+  /// Particles the scenario starts with, which a reset spawns again.
+  pub fn spawn_particles(
+    self,
+    commands: &mut Commands,
+    meshes: &mut Assets<Mesh>,
+    materials: &mut Assets<ColorMaterial>,
+    domains: &Domains,
+    config: &SimulationConfig,
+    options: &SpawnOptions,
+  ) {
+    match self {
+      Scenario::Default | Scenario::Obstacles | Scenario::Crossfire | Scenario::Paddle | Scenario::Boat => {
+        random_fill(commands, meshes, materials, domains, config, options)
+      }
+      Scenario::Rain | Scenario::FillAndDrain => {}
+    }
+  }
+
+  /// Obstacles, emitters and drains, only spawned when the scenario loads.
+  pub fn spawn_fixtures(self, commands: &mut Commands, domains: &Domains, config: &SimulationConfig) {
+    match self {
+      Scenario::Default => {}
+      Scenario::Paddle => spawn_paddles(commands, domains),
+      Scenario::Boat => spawn_boats(commands, domains, config),
+      Scenario::Obstacles => demo_obstacles(commands, domains),
+      Scenario::Crossfire => demo_emitters(commands, domains),
+      Scenario::Rain => {
+        for i in 0..domains.params.len() {
+          let (center, half) = domains.bounds(i);
+          let spacing = half.x * 2.0 / RAIN_EMITTERS as f32;
+
+          for n in 0..RAIN_EMITTERS {
+            let x = center.x - half.x + (n as f32 + 0.5) * spacing;
+            let top = Vec2::new(x, center.y + half.y - EMITTER_INSET);
+
+            let mut emitter = Emitter::new(top, Vec2::NEG_Y, 0, DomainId(i));
+            emitter.color = COLOR;
+            emitter.rate = 6.0;
+            emitter.speed = 50.0;
+            commands.spawn(emitter);
+          }
+        }
+      }
+      Scenario::FillAndDrain => {
+        for i in 0..domains.params.len() {
+          let (center, half) = domains.bounds(i);
+          let inlet = center + Vec2::new(-half.x + EMITTER_INSET, half.y * 0.6);
+          let outlet = center + Vec2::new(half.x * 0.8, -half.y * 0.9);
+
+          let mut emitter = Emitter::new(inlet, Vec2::new(1.0, -0.2), 0, DomainId(i));
+          emitter.rate = 40.0;
+          commands.spawn(emitter);
+          commands.spawn(Drain::new(outlet, 30.0, Some(25.0)));
+        }
+      }
+    }
+  }
+}
+
+/// How many particles a fill spawns in each domain, and the seed that places
+/// them. With a seed, every reset starts from the same layout. Initial
+/// conditions from a file replace the random fill altogether.
+#[derive(Resource, Clone)]
+pub struct SpawnOptions {
+  pub particles: u32,
+  pub seed: Option<u64>,
+  pub initial: Option<InitialConditions>,
+}
+
+impl Default for SpawnOptions {
+  fn default() -> Self {
+    SpawnOptions { particles: DEFAULT_PARTICLES, seed: None, initial: None }
+  }
+}
+
+impl SpawnOptions {
+  pub fn rng(&self) -> StdRng {
+    SimulationRng::new(self.seed).0
+  }
+}
+
+/// Loads a scenario from scratch: every particle, obstacle, emitter and drain
+/// is removed first.
+#[derive(Event)]
+pub struct LoadScenario(pub Scenario);
+
+fn random_fill(
+  commands: &mut Commands,
+  meshes: &mut Assets<Mesh>,
+  materials: &mut Assets<ColorMaterial>,
+  domains: &Domains,
+  config: &SimulationConfig,
+  options: &SpawnOptions,
+) {
+  if let Some(initial) = &options.initial {
+    initial.spawn(commands, meshes, materials, domains, config);
+    return;
+  }
+  scatter_particles(commands, meshes, materials, domains, config, options.particles, &mut options.rng());
+}
+
+/// `count` particles scattered uniformly over the container of every domain.
+pub fn scatter_particles(
+  commands: &mut Commands,
+  meshes: &mut Assets<Mesh>,
+  materials: &mut Assets<ColorMaterial>,
+  domains: &Domains,
+  config: &SimulationConfig,
+  count: u32,
+  rng: &mut impl Rng,
+) {
+  for (position, domain) in scatter_points(domains, config, count, rng) {
+    ParticleSpawn {
+      position: position.extend(0.0),
+      velocity: Vec3::ZERO,
+      domain: DomainId(domain),
+      color: COLOR,
+      size: config.particle_size,
+    }.spawn(commands, meshes, materials);
+  }
+}
+
+/// Where `scatter_particles` puts its particles, with the domain of each.
+pub fn scatter_points(domains: &Domains, config: &SimulationConfig, count: u32, rng: &mut impl Rng) -> Vec<(Vec2, usize)> {
+  let (_, half) = domains.bounds(0);
+
+  // every domain gets the same layout so their runs are comparable, as far
+  // as their containers allow
+  let offsets: Vec<Vec2> = (0..count * MAX_SCATTER_ATTEMPTS)
+    .map(|_| {
+      let x = rng.gen_range(-half.x..half.x);
+      let y = rng.gen_range(-half.y..half.y);
+      Vec2::new(x, y)
+    })
+    .collect();
+
+  let mut points = Vec::new();
+  for i in 0..domains.params.len() {
+    let (center, _) = domains.bounds(i);
+    let container = &domains.config(i, config).container;
+
+    let inside = offsets.iter().filter(|offset| container.contains(**offset, half));
+    points.extend(inside.take(count as usize).map(|offset| (center + *offset, i)));
+  }
+  points
+}
+
+pub fn scenario_hotkeys(
+  keys: Res<ButtonInput<KeyCode>>,
+  mut loads: EventWriter<LoadScenario>,
+) {
+  for (key, scenario) in DIGIT_KEYS.iter().zip(Scenario::ALL) {
+    if keys.just_pressed(*key) {
+      loads.send(LoadScenario(scenario));
+    }
+  }
+}
+
+pub fn load_scenario(
+  mut commands: Commands,
+  mut loads: EventReader<LoadScenario>,
+  teardown_query: Query<Entity, Or<(With<Particle>, With<Obstacle>, With<Emitter>, With<Drain>, With<Zone>)>>,
+  mut meshes: ResMut<Assets<Mesh>>,
+  mut materials: ResMut<Assets<ColorMaterial>>,
+  domains: Res<Domains>,
+  config: Res<SimulationConfig>,
+  options: Res<SpawnOptions>,
+  mut current: ResMut<Scenario>,
+  mut edits: EventWriter<SceneEdited>,
+  mut rng: ResMut<SimulationRng>,
+  mut scene: ResMut<LoadedScene>,
+  loading: Option<Res<SceneLoading>>,
+  mut next: ResMut<NextState<SimState>>,
+) {
+  let Some(LoadScenario(scenario)) = loads.read().last() else {
+    return;
+  };
+  if loading.is_some() {
+    commands.remove_resource::<SceneLoading>();
+    next.set(SimState::Running);
+  }
+  scene.0 = None;
+  rng.reseed(options.seed);
+
+  for entity in &teardown_query {
+    commands.entity(entity).despawn_recursive();
+  }
+
+  info!("loading scenario {}", scenario.label());
+  *current = *scenario;
+  scenario.spawn_particles(&mut commands, &mut meshes, &mut materials, &domains, &config, &options);
+  scenario.spawn_fixtures(&mut commands, &domains, &config);
+  edits.send(SceneEdited::Loaded);
+}
@@ -0,0 +1,477 @@
+use std::{
+  fs, io,
+  path::{Path, PathBuf},
+  sync::{
+    atomic::{AtomicUsize, Ordering},
+    Arc,
+  },
+};
+
+use bevy::{
+  prelude::*,
+  tasks::{block_on, futures_lite::future, AsyncComputeTaskPool, Task},
+};
+#[cfg(feature = "egui")]
+use bevy_egui::{egui, EguiContexts};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+  domain::{DomainId, Domains},
+  drain::Drain,
+  emitter::{Emitter, DEFAULT_RATE, DEFAULT_SPEED, EMITTER_PALETTE},
+  history::SceneEdited,
+  material::FluidMaterial,
+  obstacle::{Obstacle, ObstacleShape},
+  pause::SimState,
+  replay::{InputRecorder, InputReplay},
+  rng::SimulationRng,
+  scenario::SpawnOptions,
+  schema,
+  zone::Zone,
+  Particle, ParticleSpawn, SimulationConfig, COLOR,
+};
+
+const DEFAULT_SPACING: f32 = 8.0;
+// particles a background load spawns per frame once they are placed
+const SPAWN_BATCH: usize = 5000;
+
+/// A demo described as data: a RON file with the domain size, parameters,
+/// blocks of particles, emitters, drains and obstacles. Everything is left
+/// out by default. Positions are relative to the centre of each domain, and
+/// the scene is repeated in every domain, like the built-in scenarios.
+/// `version` is the `schema::SCHEMA_VERSION` the file was written for.
+///
+/// ```ron
+/// (
+///   version: 1,
+///   name: "Two blocks",
+///   config: Some((gravity: 800.0, viscosity: 0.2)),
+///   particles: [
+///     Rect(center: (-300.0, 0.0), size: (200.0, 300.0)),
+///     Circle(center: (300.0, 100.0), radius: 80.0, velocity: (-200.0, 0.0), color: Some(1)),
+///     Rect(center: (0.0, 200.0), size: (100.0, 100.0), material: Some((viscosity: 2.0, surface_tension: 40.0))),
+///   ],
+///   emitters: [(position: (0.0, 300.0), direction: (0.0, -1.0), rate: 10.0)],
+///   drains: [(position: (500.0, -300.0), radius: 30.0)],
+///   obstacles: [(shape: Circle(radius: 60.0), position: (0.0, -150.0))],
+/// )
+/// ```
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+#[serde(default)]
+pub struct SceneFile {
+  pub name: String,
+  pub description: String,
+  /// World-space size of the simulation area. It can only be set when the
+  /// app starts, so scenes loaded later keep the current size.
+  pub domain: Option<Vec2>,
+  /// Parameters for the scene; fields left out take their defaults. Without
+  /// it, the current parameters are kept.
+  pub config: Option<SimulationConfig>,
+  pub particles: Vec<ParticleBlock>,
+  pub emitters: Vec<SceneEmitter>,
+  pub drains: Vec<SceneDrain>,
+  pub obstacles: Vec<SceneObstacle>,
+}
+
+/// A shape filled with particles on a square grid `spacing` apart, all
+/// starting at `velocity`. `color` picks from the emitter palette. With a
+/// `material`, the particles are that fluid, drawn in its colour, instead of
+/// the config's.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub enum ParticleBlock {
+  Rect {
+    center: Vec2,
+    size: Vec2,
+    #[serde(default = "default_spacing")]
+    spacing: f32,
+    #[serde(default)]
+    velocity: Vec2,
+    #[serde(default)]
+    color: Option<usize>,
+    #[serde(default)]
+    material: Option<FluidMaterial>,
+  },
+  Circle {
+    center: Vec2,
+    radius: f32,
+    #[serde(default = "default_spacing")]
+    spacing: f32,
+    #[serde(default)]
+    velocity: Vec2,
+    #[serde(default)]
+    color: Option<usize>,
+    #[serde(default)]
+    material: Option<FluidMaterial>,
+  },
+}
+
+fn default_spacing() -> f32 {
+  DEFAULT_SPACING
+}
+
+impl ParticleBlock {
+  /// Grid positions inside the shape, and the velocity, colour and material
+  /// they share.
+  fn points(&self) -> (Vec<Vec2>, Vec2, Color, Option<FluidMaterial>) {
+    let (center, half, spacing, velocity, color, material, round) = match *self {
+      ParticleBlock::Rect { center, size, spacing, velocity, color, material } => {
+        (center, size / 2.0, spacing, velocity, color, material, false)
+      }
+      ParticleBlock::Circle { center, radius, spacing, velocity, color, material } => {
+        (center, Vec2::splat(radius), spacing, velocity, color, material, true)
+      }
+    };
+    let spacing = spacing.max(1.0);
+    let (columns, rows) = ((half.x * 2.0 / spacing) as i32, (half.y * 2.0 / spacing) as i32);
+
+    let mut points = Vec::new();
+    for row in 0..=rows {
+      for column in 0..=columns {
+        let offset = Vec2::new(column as f32, row as f32) * spacing - half;
+        if !round || offset.length() <= half.x {
+          points.push(center + offset);
+        }
+      }
+    }
+    let color = match (material, color) {
+      (Some(material), _) => material.color,
+      (None, Some(index)) => EMITTER_PALETTE[index % EMITTER_PALETTE.len()],
+      (None, None) => COLOR,
+    };
+    (points, velocity, color, material)
+  }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct SceneEmitter {
+  pub position: Vec2,
+  pub direction: Vec2,
+  #[serde(default = "default_rate")]
+  pub rate: f32,
+  #[serde(default = "default_speed")]
+  pub speed: f32,
+  #[serde(default)]
+  pub jitter: f32,
+  #[serde(default)]
+  pub lifetime: Option<f32>,
+  #[serde(default)]
+  pub max_count: Option<u32>,
+  #[serde(default)]
+  pub color: usize,
+}
+
+fn default_rate() -> f32 {
+  DEFAULT_RATE
+}
+
+fn default_speed() -> f32 {
+  DEFAULT_SPEED
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct SceneDrain {
+  pub position: Vec2,
+  pub radius: f32,
+  #[serde(default)]
+  pub max_rate: Option<f32>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct SceneObstacle {
+  pub shape: ObstacleShape,
+  pub position: Vec2,
+}
+
+impl SceneFile {
+  pub fn load(path: &Path) -> io::Result<Self> {
+    let text = fs::read_to_string(path)?;
+    schema::from_str(&text).map_err(io::Error::other)
+  }
+
+  /// The scene's particles, which a reset spawns again.
+  pub fn spawn_particles(
+    &self,
+    commands: &mut Commands,
+    meshes: &mut Assets<Mesh>,
+    materials: &mut Assets<ColorMaterial>,
+    domains: &Domains,
+    config: &SimulationConfig,
+  ) {
+    for particle in self.sample(domains, config, || {}) {
+      particle.spawn(commands, meshes, materials, config);
+    }
+  }
+
+  /// Where `spawn_particles` puts each particle, in every domain, calling
+  /// `progress` after each block of each domain.
+  pub fn sample(&self, domains: &Domains, config: &SimulationConfig, mut progress: impl FnMut()) -> Vec<SampledParticle> {
+    let mut particles = Vec::new();
+    for i in 0..domains.params.len() {
+      let (center, half) = domains.bounds(i);
+      let container = &domains.config(i, config).container;
+
+      for block in &self.particles {
+        let (points, velocity, color, material) = block.points();
+        particles.extend(points.into_iter().filter(|point| container.contains(*point, half)).map(|point| SampledParticle {
+          position: center + point,
+          velocity,
+          domain: DomainId(i),
+          color,
+          material,
+        }));
+        progress();
+      }
+    }
+    particles
+  }
+
+  /// Emitters, drains and obstacles, only spawned when the scene loads.
+  pub fn spawn_fixtures(&self, commands: &mut Commands, domains: &Domains) {
+    for i in 0..domains.params.len() {
+      let (center, _) = domains.bounds(i);
+
+      for scene_emitter in &self.emitters {
+        let mut emitter = Emitter::new(
+          center + scene_emitter.position, scene_emitter.direction, scene_emitter.color, DomainId(i),
+        );
+        emitter.rate = scene_emitter.rate;
+        emitter.speed = scene_emitter.speed;
+        emitter.jitter = scene_emitter.jitter;
+        emitter.lifetime = scene_emitter.lifetime;
+        emitter.max_count = scene_emitter.max_count;
+        commands.spawn(emitter);
+      }
+      for drain in &self.drains {
+        commands.spawn(Drain::new(center + drain.position, drain.radius, drain.max_rate));
+      }
+      for obstacle in &self.obstacles {
+        commands.spawn((
+          Obstacle { shape: obstacle.shape },
+          Transform::from_translation((center + obstacle.position).extend(-1.0)),
+        ));
+      }
+    }
+  }
+}
+
+/// One of a scene's particles, placed but not yet spawned.
+#[derive(Clone, Debug)]
+pub struct SampledParticle {
+  pub position: Vec2,
+  pub velocity: Vec2,
+  pub domain: DomainId,
+  pub color: Color,
+  pub material: Option<FluidMaterial>,
+}
+
+impl SampledParticle {
+  fn spawn(&self, commands: &mut Commands, meshes: &mut Assets<Mesh>, materials: &mut Assets<ColorMaterial>, config: &SimulationConfig) {
+    let entity = ParticleSpawn {
+      position: self.position.extend(0.0),
+      velocity: self.velocity.extend(0.0),
+      domain: self.domain,
+      color: self.color,
+      size: config.particle_size,
+    }.spawn(commands, meshes, materials);
+    if let Some(material) = self.material {
+      commands.entity(entity).insert(material);
+    }
+  }
+}
+
+/// A scene file being read and sampled on the async compute pool, then
+/// spawned `SPAWN_BATCH` particles a frame, while `SimState::Loading` holds
+/// the physics. The current scene is only torn down once the file has been
+/// read, so one that fails to load leaves it as it was. Loading another
+/// scene or a scenario abandons it.
+#[derive(Resource)]
+pub struct SceneLoading {
+  pub path: PathBuf,
+  task: Option<Task<io::Result<(SceneFile, Vec<SampledParticle>)>>>,
+  particles: Vec<SampledParticle>,
+  spawned: usize,
+  // blocks sampled so far, out of the total once the file is parsed
+  blocks: Arc<(AtomicUsize, AtomicUsize)>,
+}
+
+impl SceneLoading {
+  fn start(path: PathBuf, domains: Domains, config: SimulationConfig) -> Self {
+    let blocks = Arc::new((AtomicUsize::new(0), AtomicUsize::new(0)));
+    let counts = blocks.clone();
+    let file = path.clone();
+    let task = AsyncComputeTaskPool::get().spawn(async move {
+      let scene = SceneFile::load(&file)?;
+      counts.1.store(scene.particles.len() * domains.params.len(), Ordering::Relaxed);
+      let config = scene.config.clone().unwrap_or(config);
+      let particles = scene.sample(&domains, &config, || {
+        counts.0.fetch_add(1, Ordering::Relaxed);
+      });
+      Ok((scene, particles))
+    });
+    SceneLoading { path, task: Some(task), particles: Vec::new(), spawned: 0, blocks }
+  }
+
+  /// What the load is doing, and how far through it is, from 0 to 1.
+  pub fn progress(&self) -> (&'static str, f32) {
+    if self.task.is_some() {
+      let (done, total) = (self.blocks.0.load(Ordering::Relaxed), self.blocks.1.load(Ordering::Relaxed));
+      ("placing particles", if total == 0 { 0.0 } else { done as f32 / total as f32 })
+    } else if self.particles.is_empty() {
+      ("spawning particles", 1.0)
+    } else {
+      ("spawning particles", self.spawned as f32 / self.particles.len() as f32)
+    }
+  }
+}
+
+/// The scene file currently loaded, if any. While it is set, resets respawn
+/// its particles instead of the scenario's; loading a scenario clears it.
+#[derive(Resource, Default)]
+pub struct LoadedScene(pub Option<SceneFile>);
+
+/// Loads a scene from scratch, like `LoadScenario` does a scenario: from a
+/// file, or one already read, as replays do.
+#[derive(Event, Clone, Debug)]
+pub enum LoadScene {
+  File(PathBuf),
+  Inline(Box<SceneFile>),
+}
+
+/// Files load in the background, except while recording or replaying,
+/// where the frame a scene appears on is part of the session.
+pub fn load_scene(
+  mut commands: Commands,
+  mut loads: EventReader<LoadScene>,
+  teardown_query: Query<Entity, Or<(With<Particle>, With<Obstacle>, With<Emitter>, With<Drain>, With<Zone>)>>,
+  mut meshes: ResMut<Assets<Mesh>>,
+  mut materials: ResMut<Assets<ColorMaterial>>,
+  domains: Res<Domains>,
+  mut config: ResMut<SimulationConfig>,
+  options: Res<SpawnOptions>,
+  mut loaded: ResMut<LoadedScene>,
+  mut edits: EventWriter<SceneEdited>,
+  mut rng: ResMut<SimulationRng>,
+  recorder: Res<InputRecorder>,
+  replay: Option<Res<InputReplay>>,
+  mut next: ResMut<NextState<SimState>>,
+  loading: Option<Res<SceneLoading>>,
+) {
+  let Some(load) = loads.read().last() else {
+    return;
+  };
+  let background = !recorder.recording() && replay.is_none();
+  let scene = match load {
+    LoadScene::File(path) if background => {
+      info!("loading scene {}", path.display());
+      commands.insert_resource(SceneLoading::start(path.clone(), domains.clone(), config.clone()));
+      next.set(SimState::Loading);
+      return;
+    }
+    LoadScene::File(path) => match SceneFile::load(path) {
+      Ok(scene) => scene,
+      Err(err) => {
+        warn!("could not read scene {}: {err}", path.display());
+        return;
+      }
+    },
+    LoadScene::Inline(scene) => (**scene).clone(),
+  };
+  // a file still loading would spawn over this one
+  if loading.is_some() {
+    commands.remove_resource::<SceneLoading>();
+    next.set(SimState::Running);
+  }
+  warn_about_domain(&scene, &domains);
+  rng.reseed(options.seed);
+
+  for entity in &teardown_query {
+    commands.entity(entity).despawn_recursive();
+  }
+
+  info!("loading scene {:?}", scene.name);
+  if let Some(scene_config) = &scene.config {
+    *config = scene_config.clone();
+  }
+  scene.spawn_particles(&mut commands, &mut meshes, &mut materials, &domains, &config);
+  scene.spawn_fixtures(&mut commands, &domains);
+  loaded.0 = Some(scene);
+  edits.send(SceneEdited::Loaded);
+}
+
+fn warn_about_domain(scene: &SceneFile, domains: &Domains) {
+  if scene.domain.is_some_and(|size| size != domains.size) {
+    warn!("scene {:?} sets the domain size, which only applies when passed with --scene", scene.name);
+  }
+}
+
+/// Waits for the background load's particles, then spawns a batch of them a
+/// frame, and resumes the simulation once they are all in.
+pub fn continue_scene_load(
+  mut commands: Commands,
+  mut loading: ResMut<SceneLoading>,
+  teardown_query: Query<Entity, Or<(With<Particle>, With<Obstacle>, With<Emitter>, With<Drain>, With<Zone>)>>,
+  mut meshes: ResMut<Assets<Mesh>>,
+  mut materials: ResMut<Assets<ColorMaterial>>,
+  domains: Res<Domains>,
+  mut config: ResMut<SimulationConfig>,
+  options: Res<SpawnOptions>,
+  mut rng: ResMut<SimulationRng>,
+  mut loaded: ResMut<LoadedScene>,
+  mut edits: EventWriter<SceneEdited>,
+  mut next: ResMut<NextState<SimState>>,
+) {
+  let loading = &mut *loading;
+  if let Some(task) = &mut loading.task {
+    let Some(result) = block_on(future::poll_once(task)) else {
+      return;
+    };
+    loading.task = None;
+    match result {
+      Ok((scene, particles)) => {
+        warn_about_domain(&scene, &domains);
+        rng.reseed(options.seed);
+        for entity in &teardown_query {
+          commands.entity(entity).despawn_recursive();
+        }
+        if let Some(scene_config) = &scene.config {
+          *config = scene_config.clone();
+        }
+        scene.spawn_fixtures(&mut commands, &domains);
+        // resets are ignored until the load finishes, and by then this is what's spawned
+        loaded.0 = Some(scene);
+        loading.particles = particles;
+      }
+      Err(err) => {
+        warn!("could not read scene {}: {err}", loading.path.display());
+        commands.remove_resource::<SceneLoading>();
+        next.set(SimState::Running);
+        return;
+      }
+    }
+  }
+
+  let end = (loading.spawned + SPAWN_BATCH).min(loading.particles.len());
+  for particle in &loading.particles[loading.spawned..end] {
+    particle.spawn(&mut commands, &mut meshes, &mut materials, &config);
+  }
+  loading.spawned = end;
+  if end == loading.particles.len() {
+    info!("loaded scene {:?} with {end} particles", loaded.0.as_ref().map_or("", |scene| scene.name.as_str()));
+    edits.send(SceneEdited::Loaded);
+    commands.remove_resource::<SceneLoading>();
+    next.set(SimState::Running);
+  }
+}
+
+#[cfg(feature = "egui")]
+pub fn loading_panel(mut contexts: EguiContexts, loading: Res<SceneLoading>) {
+  let (stage, progress) = loading.progress();
+  egui::Window::new("Loading")
+    .anchor(egui::Align2::CENTER_CENTER, [0.0, 0.0])
+    .collapsible(false)
+    .resizable(false)
+    .show(contexts.ctx_mut(), |ui| {
+      ui.label(loading.path.display().to_string());
+      ui.add(egui::ProgressBar::new(progress).text(stage).desired_width(240.0));
+    });
+}
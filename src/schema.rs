@@ -0,0 +1,96 @@
+use std::borrow::Cow;
+
+use bevy::prelude::*;
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+
+/// Written into every preset, config, scene, checkpoint and session file the
+/// app saves. Bump it, with a `Migration`, whenever a saved field is renamed
+/// or changes meaning; added fields need neither, since they default.
+pub const SCHEMA_VERSION: u32 = 1;
+
+/// One step of the upgrade path: rewrites the RON of a file older than
+/// `version` into that version's form.
+pub struct Migration {
+  pub version: u32,
+  pub apply: fn(&str) -> String,
+}
+
+/// Every upgrade step, oldest first, applied in turn to files written before
+/// each one. Files without a version predate it and read as version 1.
+pub const MIGRATIONS: &[Migration] = &[];
+
+#[derive(Deserialize)]
+struct Header {
+  #[serde(default = "unversioned")]
+  version: u32,
+}
+
+fn unversioned() -> u32 {
+  1
+}
+
+/// The version a saved file was written at.
+pub fn version(text: &str) -> Result<u32, ron::error::SpannedError> {
+  Ok(ron::from_str::<Header>(text)?.version)
+}
+
+/// Pretty RON for a saved file, with the current version as its first field.
+pub fn to_string<T: Serialize>(value: &T) -> ron::Result<String> {
+  let text = ron::ser::to_string_pretty(value, ron::ser::PrettyConfig::default())?;
+  // every saved format is a struct, so its fields follow the first parenthesis
+  let fields = text.strip_prefix('(').unwrap_or(&text);
+  Ok(format!("(\n    version: {SCHEMA_VERSION},{fields}"))
+}
+
+/// Reads a saved file of any version, upgrading it through `MIGRATIONS`.
+pub fn from_str<T: DeserializeOwned>(text: &str) -> Result<T, ron::error::SpannedError> {
+  let version = version(text)?;
+  if version > SCHEMA_VERSION {
+    warn!("file is from a newer version ({version}, expected up to {SCHEMA_VERSION}); reading it anyway");
+  }
+  ron::from_str(&migrate(text, version, MIGRATIONS))
+}
+
+/// `text`, written at `version`, with every later migration applied.
+pub fn migrate<'a>(text: &'a str, version: u32, migrations: &[Migration]) -> Cow<'a, str> {
+  migrations
+    .iter()
+    .filter(|migration| migration.version > version)
+    .fold(Cow::Borrowed(text), |text, migration| Cow::Owned((migration.apply)(&text)))
+}
+
+/// Renames every field called `from`, at any depth, for migrations of renamed
+/// parameters. Strings and comments are left alone.
+pub fn rename_field(text: &str, from: &str, to: &str) -> String {
+  let mut renamed = String::with_capacity(text.len());
+  let mut rest = text;
+  while let Some(c) = rest.chars().next() {
+    if c == '"' || rest.starts_with("//") {
+      // copy the string or comment through untouched
+      let end = if c == '"' {
+        let mut escaped = false;
+        rest[1..]
+          .find(|c| {
+            let close = c == '"' && !escaped;
+            escaped = c == '\\' && !escaped;
+            close
+          })
+          .map_or(rest.len(), |i| i + 2)
+      } else {
+        rest.find('\n').unwrap_or(rest.len())
+      };
+      renamed.push_str(&rest[..end]);
+      rest = &rest[end..];
+    } else if c.is_alphabetic() || c == '_' {
+      let end = rest.find(|c: char| !c.is_alphanumeric() && c != '_').unwrap_or(rest.len());
+      let (ident, after) = rest.split_at(end);
+      let is_field = after.trim_start().starts_with(':');
+      renamed.push_str(if ident == from && is_field { to } else { ident });
+      rest = after;
+    } else {
+      renamed.push(c);
+      rest = &rest[c.len_utf8()..];
+    }
+  }
+  renamed
+}
@@ -0,0 +1,231 @@
+use std::{
+  fs,
+  path::PathBuf,
+  sync::{Arc, Mutex},
+  time::SystemTime,
+};
+
+use bevy::prelude::*;
+use rand::{rngs::StdRng, Rng, SeedableRng};
+use rhai::{CallFnOptions, Dynamic, Engine, Scope, AST};
+
+use crate::{
+  console::ConsoleCommand,
+  force_field::{ForceField, GlobalWind},
+  rng::SimulationRng,
+  spawn::{Region, SpawnParticles},
+  SimulationConfig, SimulationMode,
+};
+
+// how often the file's modification time is checked
+const POLL_INTERVAL: f32 = 0.5;
+// called every frame with the seconds since the script was loaded
+const UPDATE_FN: &str = "update";
+
+/// Something a script asked for, applied once the script call returns.
+enum ScriptAction {
+  Command(String),
+  At(f32, String),
+  Spawn(Vec2, Vec2),
+  Set(String, f32),
+  Wind(Vec2),
+}
+
+type ActionQueue = Arc<Mutex<Vec<ScriptAction>>>;
+type ScriptRng = Arc<Mutex<StdRng>>;
+
+/// A rhai script passed with `--script` or the console's `script` command.
+/// Its top level runs when it's loaded, to spawn patterns and schedule
+/// events with `at`; an `update(t)` function runs every frame after that, for
+/// forces that change over time. The file is reloaded whenever it changes,
+/// which starts its clock over.
+#[derive(Resource)]
+pub struct SceneScript {
+  path: PathBuf,
+  modified: Option<SystemTime>,
+  poll: Timer,
+  engine: Engine,
+  actions: ActionQueue,
+  // reseeded from the simulation's generator on every load
+  random: ScriptRng,
+  // `None` once the script has failed, so the error is only reported once
+  ast: Option<AST>,
+  elapsed: f32,
+  scheduled: Vec<(f32, String)>,
+}
+
+impl SceneScript {
+  pub fn new(path: impl Into<PathBuf>) -> Self {
+    let actions = ActionQueue::default();
+    let random = Arc::new(Mutex::new(StdRng::seed_from_u64(0)));
+    SceneScript {
+      path: path.into(),
+      modified: None,
+      poll: Timer::from_seconds(POLL_INTERVAL, TimerMode::Repeating),
+      engine: script_engine(&actions, &random),
+      actions,
+      random,
+      ast: None,
+      elapsed: 0.0,
+      scheduled: Vec::new(),
+    }
+  }
+
+  fn load(&mut self, seed: u64) -> Result<(), String> {
+    self.ast = None;
+    self.elapsed = 0.0;
+    self.scheduled.clear();
+    *self.random.lock().unwrap() = StdRng::seed_from_u64(seed);
+
+    let text = fs::read_to_string(&self.path).map_err(|err| err.to_string())?;
+    let ast = self.engine.compile(text).map_err(|err| err.to_string())?;
+    self.engine.run_ast(&ast).map_err(|err| err.to_string())?;
+    self.ast = Some(ast);
+    Ok(())
+  }
+
+  fn update(&mut self) -> Result<(), String> {
+    let Some(ast) = &self.ast else {
+      return Ok(());
+    };
+    if !ast.iter_functions().any(|function| function.name == UPDATE_FN) {
+      return Ok(());
+    }
+
+    let options = CallFnOptions::new().eval_ast(false);
+    self
+      .engine
+      .call_fn_with_options::<Dynamic>(options, &mut Scope::new(), ast, UPDATE_FN, (self.elapsed as rhai::FLOAT,))
+      .map(|_| ())
+      .map_err(|err| err.to_string())
+  }
+}
+
+/// Scripts can pass whole or decimal numbers anywhere a number is expected.
+fn number(value: Dynamic) -> f32 {
+  value
+    .as_float()
+    .or_else(|_| value.as_int().map(|int| int as rhai::FLOAT))
+    .unwrap_or_default() as f32
+}
+
+fn script_engine(actions: &ActionQueue, random: &ScriptRng) -> Engine {
+  let mut engine = Engine::new();
+
+  let queue = actions.clone();
+  engine.register_fn("command", move |line: &str| {
+    queue.lock().unwrap().push(ScriptAction::Command(line.to_owned()));
+  });
+  let queue = actions.clone();
+  engine.register_fn("at", move |seconds: Dynamic, line: &str| {
+    queue.lock().unwrap().push(ScriptAction::At(number(seconds), line.to_owned()));
+  });
+  let queue = actions.clone();
+  engine.register_fn("particle", move |x: Dynamic, y: Dynamic| {
+    let position = Vec2::new(number(x), number(y));
+    queue.lock().unwrap().push(ScriptAction::Spawn(position, Vec2::ZERO));
+  });
+  let queue = actions.clone();
+  engine.register_fn("particle", move |x: Dynamic, y: Dynamic, vx: Dynamic, vy: Dynamic| {
+    let position = Vec2::new(number(x), number(y));
+    let velocity = Vec2::new(number(vx), number(vy));
+    queue.lock().unwrap().push(ScriptAction::Spawn(position, velocity));
+  });
+  let queue = actions.clone();
+  engine.register_fn("set", move |name: &str, value: Dynamic| {
+    queue.lock().unwrap().push(ScriptAction::Set(name.to_owned(), number(value)));
+  });
+  let queue = actions.clone();
+  engine.register_fn("wind", move |x: Dynamic, y: Dynamic| {
+    queue.lock().unwrap().push(ScriptAction::Wind(Vec2::new(number(x), number(y))));
+  });
+  let random = random.clone();
+  engine.register_fn("random", move |min: Dynamic, max: Dynamic| -> rhai::FLOAT {
+    let (min, max) = (number(min), number(max));
+    (min + random.lock().unwrap().gen::<f32>() * (max - min)) as rhai::FLOAT
+  });
+
+  engine
+}
+
+/// Polls the file on real time, like `--config`, and runs it again from the
+/// top whenever it changes.
+pub fn watch_script(mut script: ResMut<SceneScript>, mut rng: ResMut<SimulationRng>, time: Res<Time<Real>>) {
+  let first = script.modified.is_none();
+  if !script.poll.tick(time.delta()).just_finished() && !first {
+    return;
+  }
+
+  let Ok(modified) = fs::metadata(&script.path).and_then(|meta| meta.modified()) else {
+    if first {
+      warn!("could not read script {}", script.path.display());
+      script.modified = Some(SystemTime::UNIX_EPOCH);
+    }
+    return;
+  };
+  if script.modified == Some(modified) {
+    return;
+  }
+  script.modified = Some(modified);
+
+  match script.load(rng.0.gen()) {
+    Ok(()) => info!("running script {}", script.path.display()),
+    Err(err) => warn!("script {} failed: {err}", script.path.display()),
+  }
+}
+
+/// Advances the script's clock with the simulation, so a paused or slowed
+/// simulation holds its events back too, then applies what it asked for.
+pub fn run_script(
+  mut script: ResMut<SceneScript>,
+  mut console_commands: EventWriter<ConsoleCommand>,
+  mut config: ResMut<SimulationConfig>,
+  mut wind_query: Query<&mut ForceField, With<GlobalWind>>,
+  mut spawns: EventWriter<SpawnParticles>,
+  mode: Res<SimulationMode>,
+  time: Res<Time>,
+) {
+  let script = &mut *script;
+  script.elapsed += time.delta_secs();
+  if let Err(err) = script.update() {
+    warn!("script {} failed: {err}", script.path.display());
+    script.ast = None;
+  }
+
+  let actions: Vec<ScriptAction> = script.actions.lock().unwrap().drain(..).collect();
+  for action in actions {
+    match action {
+      ScriptAction::Command(line) => {
+        console_commands.send(ConsoleCommand(line));
+      }
+      ScriptAction::At(seconds, line) => script.scheduled.push((seconds, line)),
+      ScriptAction::Spawn(position, velocity) => {
+        if *mode == SimulationMode::ThreeD {
+          continue;
+        }
+        spawns.send(SpawnParticles { velocity, ..SpawnParticles::new(Region::Point(position), 1) });
+      }
+      ScriptAction::Set(name, value) => match config.field_mut(&name) {
+        Some(field) => *field = value,
+        None => warn!("script set unknown parameter {name}"),
+      },
+      ScriptAction::Wind(velocity) => {
+        if let Ok(mut field) = wind_query.get_single_mut() {
+          let ForceField::Wind { velocity: wind, .. } = &mut *field;
+          *wind = velocity;
+        }
+      }
+    }
+  }
+
+  let elapsed = script.elapsed;
+  script.scheduled.retain(|(seconds, line)| {
+    let due = *seconds <= elapsed;
+    if due {
+      info!("script at {seconds}s: {line}");
+      console_commands.send(ConsoleCommand(line.clone()));
+    }
+    !due
+  });
+}
+
@@ -0,0 +1,15 @@
+use bevy::prelude::*;
+
+/// Logs each shader the asset server reloads after its file was saved. The
+/// materials using it pick it up on the next frame; if it fails to compile,
+/// Bevy logs the error and they stay undrawn until the file is fixed, while
+/// the simulation keeps running.
+pub fn log_shader_reloads(mut events: EventReader<AssetEvent<Shader>>, asset_server: Res<AssetServer>) {
+  for event in events.read() {
+    if let AssetEvent::Modified { id } = event {
+      if let Some(path) = asset_server.get_path(*id) {
+        info!("reloaded shader {path}");
+      }
+    }
+  }
+}
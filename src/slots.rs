@@ -0,0 +1,169 @@
+use std::collections::HashMap;
+
+use bevy::prelude::*;
+
+use crate::{
+  domain::DomainId, layers::CollisionLayers, material::FluidMaterial, pin::Pinned, render::ParticleColor, Particle,
+  ParticleSpawn, SimulationConfig,
+};
+
+const SLOT_KEYS: [KeyCode; 4] = [KeyCode::F1, KeyCode::F2, KeyCode::F3, KeyCode::F4];
+const SAVE_KEY: KeyCode = KeyCode::F5;
+const RESTORE_KEY: KeyCode = KeyCode::F6;
+
+/// Everything about a particle that `ParticleSnapshot` keeps.
+pub type SnapshotQuery<'w, 's> = Query<
+  'w,
+  's,
+  (
+    Entity,
+    &'static Particle,
+    &'static DomainId,
+    &'static ParticleColor,
+    Has<Pinned>,
+    Option<&'static CollisionLayers>,
+    Option<&'static FluidMaterial>,
+  ),
+>;
+
+/// A copy of one particle, enough to put it back exactly as it was.
+pub struct ParticleSnapshot {
+  pub entity: Entity,
+  pub particle: Particle,
+  pub domain: DomainId,
+  pub color: ParticleColor,
+  pub pinned: bool,
+  pub layers: Option<CollisionLayers>,
+  pub material: Option<FluidMaterial>,
+}
+
+impl ParticleSnapshot {
+  /// Every particle in `particle_query`.
+  pub fn capture_all(particle_query: &SnapshotQuery) -> Vec<Self> {
+    particle_query
+      .iter()
+      .map(|(entity, particle, domain, color, pinned, layers, material)| ParticleSnapshot {
+        entity,
+        particle: particle.clone(),
+        domain: *domain,
+        color: color.clone(),
+        pinned,
+        layers: layers.copied(),
+        material: material.copied(),
+      })
+      .collect()
+  }
+
+  /// Adds or removes the optional components to match the snapshot.
+  fn restore_components(&self, entity: &mut EntityCommands) {
+    if self.pinned {
+      entity.insert(Pinned);
+    } else {
+      entity.remove::<Pinned>();
+    }
+    match self.layers {
+      Some(layers) => entity.insert(layers),
+      None => entity.remove::<CollisionLayers>(),
+    };
+    match self.material {
+      Some(material) => entity.insert(material),
+      None => entity.remove::<FluidMaterial>(),
+    };
+  }
+
+  /// Spawns a new particle in the saved state, drawn at `size`.
+  pub fn respawn(
+    &self,
+    size: f32,
+    commands: &mut Commands,
+    meshes: &mut Assets<Mesh>,
+    materials: &mut Assets<ColorMaterial>,
+  ) -> Entity {
+    let entity = ParticleSpawn {
+      position: self.particle.position,
+      velocity: self.particle.velocity,
+      domain: self.domain,
+      color: self.color.source,
+      size,
+    }.spawn(commands, meshes, materials);
+    let mut particle = commands.entity(entity);
+    particle.insert((self.particle.clone(), self.color.clone()));
+    self.restore_components(&mut particle);
+    entity
+  }
+}
+
+/// Puts the particles back the way `saved` had them. Particles that still
+/// exist are updated in place; ones removed since are respawned at `size`
+/// and ones added since are despawned.
+pub fn restore_in_place(
+  commands: &mut Commands,
+  particle_query: &mut Query<(Entity, &mut Particle, &mut ParticleColor)>,
+  saved: &[ParticleSnapshot],
+  size: f32,
+  meshes: &mut Assets<Mesh>,
+  materials: &mut Assets<ColorMaterial>,
+) {
+  let mut saved: HashMap<Entity, &ParticleSnapshot> = saved.iter().map(|snapshot| (snapshot.entity, snapshot)).collect();
+
+  for (entity, mut particle, mut color) in particle_query {
+    match saved.remove(&entity) {
+      Some(snapshot) => {
+        *particle = snapshot.particle.clone();
+        *color = snapshot.color.clone();
+        snapshot.restore_components(&mut commands.entity(entity));
+      }
+      None => commands.entity(entity).despawn_recursive(),
+    }
+  }
+  for snapshot in saved.into_values() {
+    snapshot.respawn(size, commands, meshes, materials);
+  }
+}
+
+/// In-memory snapshots of every particle, one per slot. F1-F4 pick the
+/// slot, F5 saves into it and F6 restores it.
+#[derive(Resource, Default)]
+pub struct StateSlots {
+  selected: usize,
+  slots: [Option<Vec<ParticleSnapshot>>; 4],
+}
+
+/// Restoring only replaces the particles; obstacles, emitters and the
+/// parameters stay as they are.
+pub fn quick_slots(
+  mut commands: Commands,
+  keys: Res<ButtonInput<KeyCode>>,
+  particle_query: SnapshotQuery,
+  mut meshes: ResMut<Assets<Mesh>>,
+  mut materials: ResMut<Assets<ColorMaterial>>,
+  mut slots: ResMut<StateSlots>,
+  config: Res<SimulationConfig>,
+) {
+  if let Some(slot) = SLOT_KEYS.iter().position(|key| keys.just_pressed(*key)) {
+    slots.selected = slot;
+    info!("state slot {}", slot + 1);
+  }
+  let selected = slots.selected;
+
+  if keys.just_pressed(SAVE_KEY) {
+    let snapshot = ParticleSnapshot::capture_all(&particle_query);
+    info!("saved {} particles to slot {}", snapshot.len(), selected + 1);
+    slots.slots[selected] = Some(snapshot);
+  }
+
+  if keys.just_pressed(RESTORE_KEY) {
+    let Some(snapshot) = &slots.slots[selected] else {
+      info!("slot {} is empty", selected + 1);
+      return;
+    };
+
+    for (entity, ..) in &particle_query {
+      commands.entity(entity).despawn_recursive();
+    }
+    for saved in snapshot {
+      saved.respawn(config.particle_size, &mut commands, &mut meshes, &mut materials);
+    }
+    info!("restored {} particles from slot {}", snapshot.len(), selected + 1);
+  }
+}
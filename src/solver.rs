@@ -0,0 +1,578 @@
+use bevy::{prelude::*, utils::HashMap};
+use fluid_core::{forces, integrate::integrate, neighbours};
+pub use fluid_core::neighbours::{NeighborSearch, SearchBackend};
+
+use crate::{
+  boundaries::detect_boundaries,
+  domain::{DomainId, Domains},
+  inspect,
+  kernels::{smoothing_kernel_dx, Dimension, SphVector},
+  layers::CollisionLayers,
+  material::FluidMaterial,
+  obstacle, pin,
+  pressure::{PressureSolvers, PressureStep},
+  zone, Particle, SimulationConfig, SimulationMode,
+};
+#[cfg(feature = "3d")]
+use crate::three_d;
+
+/// Per-particle densities, collision layers and materials from the last
+/// density pass, in particle query order, the query indices of the
+/// particles in each domain, and each particle's neighbours within its
+/// domain's smoothing radius, found with the `search` backend, so the
+/// neighbour loops only visit particles close enough to count. `indices`
+/// maps each particle to its query index, so systems outside the solver,
+/// whose queries need not visit particles in the same order, can look them
+/// up by entity.
+#[derive(Resource, Reflect, Default)]
+#[reflect(Resource)]
+pub struct SimulationState {
+  pub densities: Vec<f32>,
+  pub layers: Vec<CollisionLayers>,
+  pub materials: Vec<Option<FluidMaterial>>,
+  pub domains: Vec<Vec<usize>>,
+  pub neighbours: Vec<Vec<usize>>,
+  #[reflect(ignore)]
+  pub search: SearchBackend,
+  #[reflect(ignore)]
+  pub indices: HashMap<Entity, usize>,
+}
+
+impl SimulationState {
+  /// Query indices of the particles in `domain` as of the last density pass.
+  pub fn members(&self, domain: DomainId) -> &[usize] {
+    self.domains.get(domain.0).map_or(&[], Vec::as_slice)
+  }
+
+  /// Query indices of the particles near `index`, itself included, in
+  /// ascending order, as of the last density pass.
+  pub fn neighbours(&self, index: usize) -> &[usize] {
+    self.neighbours.get(index).map_or(&[], Vec::as_slice)
+  }
+
+  /// Query index of `entity` in the last density pass, if it was there.
+  pub fn index_of(&self, entity: Entity) -> Option<usize> {
+    self.indices.get(&entity).copied()
+  }
+
+  /// Density of `entity` from the last density pass, or zero for a particle
+  /// spawned since.
+  pub fn density_of(&self, entity: Entity) -> f32 {
+    self.index_of(entity).and_then(|i| self.densities.get(i)).copied().unwrap_or_default()
+  }
+
+  /// Material of `entity` as of the last density pass.
+  pub fn material_of(&self, entity: Entity) -> Option<&FluidMaterial> {
+    self.index_of(entity).and_then(|i| self.materials.get(i)).and_then(Option::as_ref)
+  }
+}
+
+/// Two particles bouncing off each other in `detect_collisions`; `impulse`
+/// is the momentum `a` received, and `b` the opposite. The plugin leaves
+/// that system out in favour of the pressure force, so these only arrive
+/// in apps that schedule it.
+#[derive(Event, Clone, Copy, Debug)]
+pub struct ParticleCollisionEvent {
+  pub a: Entity,
+  pub b: Entity,
+  pub impulse: Vec3,
+}
+
+/// A particle hitting its container's wall, with the wall's outward
+/// `normal` and the `speed` it was moving into the wall at. Particles resting
+/// on a wall nudge it every frame, so listeners usually want a minimum speed.
+#[derive(Event, Clone, Copy, Debug)]
+pub struct BoundaryHitEvent {
+  pub entity: Entity,
+  pub normal: Vec3,
+  pub speed: f32,
+}
+
+/// Integrates each particle's velocity and position under gravity and the
+/// zones it is in, and predicts where it will be a step later for the
+/// density pass.
+pub fn gravity(
+  mut particle_query: Query<(&mut Particle, &DomainId, Has<pin::Pinned>)>,
+  zone_query: Query<&zone::Zone>,
+  time: Res<Time>,
+  domains: Res<Domains>,
+  config: Res<SimulationConfig>,
+) {
+  let zones: Vec<zone::Zone> = zone_query.iter().cloned().collect();
+
+  for (mut particle, domain, pinned) in &mut particle_query {
+    let config = domains.config(domain.0, &config);
+    particle.previous_position = particle.position;
+    if pinned {
+      particle.velocity = Vec3::ZERO;
+      particle.predicted_position = particle.position;
+      continue;
+    }
+
+    let overrides = zone::overrides_at(&zones, particle.position.truncate());
+    let particle = &mut *particle;
+    particle.predicted_position = integrate(
+      &mut particle.position,
+      &mut particle.velocity,
+      config.gravity_vector() * overrides.gravity_scale,
+      overrides.damping,
+      time.delta_secs(),
+    );
+  }
+}
+
+/// Pushes particles that have moved out of their container or into an
+/// obstacle back out, bouncing them off it.
+pub fn resolve_boundaries(
+  mut particle_query: Query<(Entity, &mut Particle, &DomainId, Has<pin::Pinned>, Option<&CollisionLayers>)>,
+  mut obstacle_query: Query<
+    (
+      &obstacle::Obstacle,
+      &Transform,
+      Option<&mut obstacle::Kinematic>,
+      Option<&obstacle::Floating>,
+      Option<&CollisionLayers>,
+    ),
+    Without<Particle>,
+  >,
+  mut boundary_hits: EventWriter<BoundaryHitEvent>,
+  mode: Res<SimulationMode>,
+  domains: Res<Domains>,
+  config: Res<SimulationConfig>,
+) {
+  let obstacles: Vec<(obstacle::Obstacle, Vec2, Vec2, CollisionLayers)> = obstacle_query
+    .iter()
+    .map(|(obstacle, transform, kinematic, _, layers)| {
+      let velocity = kinematic.map(|kinematic| kinematic.velocity).unwrap_or_default();
+      (*obstacle, transform.translation.truncate(), velocity, CollisionLayers::of(layers))
+    })
+    .collect();
+  let mut impulses = vec![Vec2::ZERO; obstacles.len()];
+
+  for (entity, mut particle, domain, pinned, layers) in &mut particle_query {
+    if pinned {
+      continue;
+    }
+    let config = domains.config(domain.0, &config);
+    let layers = CollisionLayers::of(layers);
+
+    let hit = match *mode {
+      SimulationMode::TwoD => {
+        let (center, half) = domains.bounds(domain.0);
+        let hit = layers
+          .hits_walls()
+          .then(|| detect_boundaries(&mut particle, &config.container, center, half, config))
+          .flatten();
+        obstacle::collide_obstacles(&mut particle, layers, &obstacles, &mut impulses, config);
+        hit.map(|(normal, speed)| (normal.extend(0.0), speed))
+      }
+      #[cfg(feature = "3d")]
+      SimulationMode::ThreeD if layers.hits_walls() => three_d::detect_box_boundaries(&mut particle, config),
+      SimulationMode::ThreeD => None,
+    };
+    if let Some((normal, speed)) = hit {
+      boundary_hits.send(BoundaryHitEvent { entity, normal, speed });
+    }
+  }
+
+  // two-way coupling: floating obstacles take the reaction to their collisions
+  for ((_, _, kinematic, floating, _), impulse) in obstacle_query.iter_mut().zip(impulses) {
+    if let (Some(mut kinematic), Some(floating)) = (kinematic, floating) {
+      kinematic.velocity += impulse / floating.mass;
+    }
+  }
+}
+
+pub fn detect_collisions(
+  mut particle_query: Query<(Entity, &mut Particle)>,
+  mut collision_events: EventWriter<ParticleCollisionEvent>,
+  config: Res<SimulationConfig>,
+) {
+  let entities: Vec<(Entity, Vec3, Vec3, f32)> = particle_query
+    .iter()
+    .map(|(entity, particle)| (entity, particle.position, particle.velocity, particle.mass))
+    .collect();
+
+  let mut collisions = Vec::new();
+
+  for i in 0..entities.len() {
+    for j in (i + 1)..entities.len() {
+      let (e1, pos1, vel1, mass1) = entities[i];
+      let (e2, pos2, vel2, mass2) = entities[j];
+
+      let delta = pos1 - pos2;
+      let dist = delta.length();
+
+      // Check for collision
+      if dist < (mass1 + mass2) {
+        collisions.push((e1, e2, pos1, pos2, vel1, vel2, mass1, mass2));
+      }
+    }
+  }
+
+  for (e1, e2, pos1, pos2, vel1, vel2, mass1, mass2) in collisions {
+    let (new_vel1, new_vel2, impulse) = elastic_collision(
+      mass1, mass2,
+      vel1, vel2,
+      pos1, pos2,
+      config.restitution
+    );
+    if impulse != Vec3::ZERO {
+      collision_events.send(ParticleCollisionEvent { a: e1, b: e2, impulse });
+    }
+
+    if let Ok((_, mut particle)) = particle_query.get_mut(e1) {
+      particle.velocity = new_vel1;
+    }
+    if let Ok((_, mut particle)) = particle_query.get_mut(e2) {
+      particle.velocity = new_vel2;
+    }
+  }
+}
+
+fn elastic_collision(
+  m1: f32, m2: f32,
+  v1: Vec3, v2: Vec3,
+  r1: Vec3, r2: Vec3,
+  restitution: f32
+) -> (Vec3, Vec3, Vec3) {
+
+  let n = (r1 - r2).normalize();
+  
+  let v_rel = (v1 - v2).dot(n);
+  
+  if v_rel > 0.0 {
+    return (v1, v2, Vec3::ZERO);
+  }
+
+  let j = -(1.0 + restitution) * v_rel / (1.0/m1 + 1.0/m2);
+  
+  let v1f = v1 + (j / m1) * n;
+  let v2f = v2 - (j / m2) * n;
+
+  (v1f, v2f, j * n)
+}
+
+
+pub fn apply_pressure_force(
+  mut particle_query: Query<(&mut Particle, &DomainId, Option<&mut inspect::Inspected>, Has<pin::Pinned>)>,
+  mut solvers: Local<PressureSolvers>,
+  time: Res<Time>,
+  state: Res<SimulationState>,
+  mode: Res<SimulationMode>,
+  domains: Res<Domains>,
+  config: Res<SimulationConfig>,
+) {
+  match mode.dimension() {
+    Dimension::Two => pressure_step::<Vec2>(&mut particle_query, &mut solvers, &time, &state, &domains, &config),
+    Dimension::Three => pressure_step::<Vec3>(&mut particle_query, &mut solvers, &time, &state, &domains, &config),
+  }
+}
+
+/// Hands each domain's particles to the pressure solver its config picks.
+fn pressure_step<V: SphVector>(
+  particle_query: &mut Query<(&mut Particle, &DomainId, Option<&mut inspect::Inspected>, Has<pin::Pinned>)>,
+  solvers: &mut PressureSolvers,
+  time: &Time,
+  state: &SimulationState,
+  domains: &Domains,
+  config: &SimulationConfig,
+) {
+
+  // collect positions first to avoid conflicts
+  let positions: Vec<V> = particle_query
+    .iter()
+    .map(|(particle, ..)| V::from_vec3(particle.predicted_position))
+    .collect();
+
+  let mut accelerations = vec![V::default(); positions.len()];
+  for (domain, members) in state.domains.iter().enumerate() {
+    let config = domains.config(domain, config);
+    let step = PressureStep {
+      positions: &positions,
+      state,
+      config,
+      pressure_scale: domains.params[domain].pressure_scale,
+      dt: time.delta_secs(),
+    };
+    solvers.get::<V>(domain, config.pressure_solver).solve(&step, members, &mut accelerations);
+  }
+
+  for (i, (mut particle, _, inspected, pinned)) in particle_query.iter_mut().enumerate() {
+    if pinned {
+      continue;
+    }
+    let pressure_acceleration = accelerations[i].to_vec3();
+    particle.velocity += pressure_acceleration * time.delta_secs();
+
+    if let Some(mut inspected) = inspected {
+      inspected.pressure = pressure_acceleration;
+    }
+  }
+}
+
+
+/// The SPH density at `sample_index` from the `members` it interacts with,
+/// itself included.
+pub fn calculate_density<V: SphVector>(
+  positions: &[V],
+  layers: &[CollisionLayers],
+  members: &[usize],
+  sample_index: usize,
+  radius: f32,
+  mass: f32,
+) -> f32 {
+  let neighbours = members.iter().copied().filter(|&i| i == sample_index || layers[i].interacts(layers[sample_index]));
+  forces::density(positions, neighbours, sample_index, radius, mass)
+}
+
+
+/// Sorts the particles into their domains, in query order, with their
+/// collision layers and materials, for the neighbour loops that follow.
+pub fn index_particles(
+  particle_query: Query<(&DomainId, Option<&CollisionLayers>, Option<&FluidMaterial>), With<Particle>>,
+  mut state: ResMut<SimulationState>,
+  domains: Res<Domains>,
+) {
+  let state = &mut *state;
+  state.densities.resize(particle_query.iter().len(), 0.0);
+  state.layers = particle_query.iter().map(|(_, layers, _)| CollisionLayers::of(layers)).collect();
+  state.materials = particle_query.iter().map(|(.., material)| material.copied()).collect();
+  state.domains = neighbours::partition(particle_query.iter().map(|(domain, ..)| domain.0), domains.params.len());
+}
+
+/// Finds each particle's neighbours in its own domain, then estimates its
+/// density from them with that domain's parameters.
+pub fn update_density(
+  particle_query: Query<(Entity, &Particle)>,
+  mut state: ResMut<SimulationState>,
+  mode: Res<SimulationMode>,
+  domains: Res<Domains>,
+  config: Res<SimulationConfig>,
+) {
+  match mode.dimension() {
+    Dimension::Two => density_step::<Vec2>(&particle_query, &mut state, &domains, &config),
+    Dimension::Three => density_step::<Vec3>(&particle_query, &mut state, &domains, &config),
+  }
+}
+
+fn density_step<V: SphVector>(
+  particle_query: &Query<(Entity, &Particle)>,
+  state: &mut SimulationState,
+  domains: &Domains,
+  config: &SimulationConfig,
+) {
+  state.indices = particle_query.iter().enumerate().map(|(i, (entity, _))| (entity, i)).collect();
+  let positions: Vec<V> = particle_query.iter().map(|(_, particle)| V::from_vec3(particle.predicted_position)).collect();
+  let mut search = state.search.search::<V>();
+  state.neighbours.resize_with(positions.len(), Vec::new);
+  state.neighbours.iter_mut().for_each(Vec::clear);
+
+  for (domain, members) in state.domains.iter().enumerate() {
+    let config = domains.config(domain, config);
+    search.build(&positions, members, config.smoothing_radius);
+    for &i in members {
+      search.query(&positions, positions[i], &mut state.neighbours[i]);
+      state.densities[i] = calculate_density(
+        &positions, &state.layers, &state.neighbours[i], i, config.smoothing_radius, config.mass
+      );
+    }
+  }
+}
+
+
+/// The pressure force on `sample_index` from the `members` it interacts
+/// with, using the densities and materials in `state`.
+pub fn calculate_pressure_force<V: SphVector>(
+  positions: &[V],
+  members: &[usize],
+  state: &SimulationState,
+  sample_index: usize,
+  config: &SimulationConfig,
+  pressure_scale: f32,
+) -> V {
+  let pressure = |i: usize| material_pressure(state.densities[i], state.materials[i].as_ref(), V::DIMENSION, config);
+  let neighbours = members.iter().copied().filter(|&i| state.layers[i].interacts(state.layers[sample_index]));
+  forces::pressure_force(
+    positions, &state.densities, pressure, neighbours, sample_index, config.smoothing_radius, config.mass, pressure_scale,
+  )
+}
+
+
+/// Pulls each particle's velocity towards the kernel-weighted average of its
+/// neighbours', damping shear between them.
+pub fn apply_viscosity(
+  mut particle_query: Query<(&mut Particle, &DomainId, Option<&mut inspect::Inspected>, Has<pin::Pinned>)>,
+  zone_query: Query<&zone::Zone>,
+  time: Res<Time>,
+  state: Res<SimulationState>,
+  mode: Res<SimulationMode>,
+  domains: Res<Domains>,
+  config: Res<SimulationConfig>,
+) {
+  let zones: Vec<zone::Zone> = zone_query.iter().cloned().collect();
+  match mode.dimension() {
+    Dimension::Two => viscosity_step::<Vec2>(&mut particle_query, &zones, &time, &state, &domains, &config),
+    Dimension::Three => viscosity_step::<Vec3>(&mut particle_query, &zones, &time, &state, &domains, &config),
+  }
+}
+
+fn viscosity_step<V: SphVector>(
+  particle_query: &mut Query<(&mut Particle, &DomainId, Option<&mut inspect::Inspected>, Has<pin::Pinned>)>,
+  zones: &[zone::Zone],
+  time: &Time,
+  state: &SimulationState,
+  domains: &Domains,
+  config: &SimulationConfig,
+) {
+  let particle_data: Vec<(V, V)> = particle_query
+    .iter()
+    .map(|(particle, ..)| (V::from_vec3(particle.predicted_position), V::from_vec3(particle.velocity)))
+    .collect();
+
+  for (i, (mut particle, domain, inspected, pinned)) in particle_query.iter_mut().enumerate() {
+    let config = domains.config(domain.0, config);
+    let coefficient = state.materials[i].map_or(config.viscosity, |material| material.viscosity);
+    if pinned || coefficient <= 0.0 {
+      continue;
+    }
+    let viscosity_force = calculate_viscosity_force(
+      &particle_data, state.neighbours(i), state, i, config.smoothing_radius, config.mass
+    ).to_vec3();
+    let viscosity = coefficient * zone::overrides_at(zones, particle.position.truncate()).viscosity_scale;
+    particle.velocity += viscosity_force * viscosity * time.delta_secs();
+
+    if let Some(mut inspected) = inspected {
+      inspected.viscosity = viscosity_force * viscosity;
+    }
+  }
+}
+
+/// The viscous pull on `sample_index` towards the velocities of the
+/// `members` it shares a group with. `particle_data` holds each particle's
+/// predicted position and velocity.
+pub fn calculate_viscosity_force<V: SphVector>(
+  particle_data: &[(V, V)],
+  members: &[usize],
+  state: &SimulationState,
+  sample_index: usize,
+  radius: f32,
+  mass: f32,
+) -> V {
+  // immiscible groups push apart without sharing momentum
+  forces::viscosity_force(particle_data, &state.densities, miscible(members, state, sample_index), sample_index, radius, mass)
+}
+
+fn miscible<'a>(members: &'a [usize], state: &'a SimulationState, sample_index: usize) -> impl Iterator<Item = usize> + 'a {
+  let sample_layers = state.layers[sample_index];
+  members.iter().copied().filter(move |&i| {
+    let layers = state.layers[i];
+    layers.interacts(sample_layers) && layers.shares_group(sample_layers)
+  })
+}
+
+
+/// Pulls each particle with a material's `surface_tension` towards the
+/// neighbours it shares a group with, which rounds off drops and lets them
+/// bead up instead of spreading into a film.
+pub fn apply_surface_tension(
+  mut particle_query: Query<(&mut Particle, &DomainId, Has<pin::Pinned>)>,
+  time: Res<Time>,
+  state: Res<SimulationState>,
+  mode: Res<SimulationMode>,
+  domains: Res<Domains>,
+  config: Res<SimulationConfig>,
+) {
+  if state.materials.iter().all(|material| material.is_none_or(|material| material.surface_tension <= 0.0)) {
+    return;
+  }
+
+  match mode.dimension() {
+    Dimension::Two => surface_tension_step::<Vec2>(&mut particle_query, &time, &state, &domains, &config),
+    Dimension::Three => surface_tension_step::<Vec3>(&mut particle_query, &time, &state, &domains, &config),
+  }
+}
+
+fn surface_tension_step<V: SphVector>(
+  particle_query: &mut Query<(&mut Particle, &DomainId, Has<pin::Pinned>)>,
+  time: &Time,
+  state: &SimulationState,
+  domains: &Domains,
+  config: &SimulationConfig,
+) {
+  let positions: Vec<V> = particle_query.iter().map(|(particle, ..)| V::from_vec3(particle.predicted_position)).collect();
+  for (i, (mut particle, domain, pinned)) in particle_query.iter_mut().enumerate() {
+    let tension = state.materials[i].map_or(0.0, |material| material.surface_tension);
+    if pinned || tension <= 0.0 {
+      continue;
+    }
+    let config = domains.config(domain.0, config);
+    let cohesion = calculate_cohesion(
+      &positions, state.neighbours(i), state, i, config.smoothing_radius, config.mass
+    );
+    particle.velocity += cohesion.to_vec3() * tension * time.delta_secs();
+  }
+}
+
+/// The pull on `sample_index` towards the `members` it shares a group with,
+/// before scaling by the material's surface tension.
+pub fn calculate_cohesion<V: SphVector>(
+  positions: &[V],
+  members: &[usize],
+  state: &SimulationState,
+  sample_index: usize,
+  radius: f32,
+  mass: f32,
+) -> V {
+  forces::cohesion(positions, &state.densities, miscible(members, state, sample_index), sample_index, radius, mass)
+}
+
+/// SPH estimate of the velocity curl at a particle, as the z component, so
+/// positive values turn counter-clockwise. `particles` holds each particle's
+/// position, velocity and domain in query order.
+pub fn calculate_vorticity(
+  particles: &[(Vec3, Vec3, DomainId)],
+  densities: &[f32],
+  sample_index: usize,
+  dimension: Dimension,
+  radius: f32,
+  mass: f32,
+) -> f32 {
+  let (sample_position, sample_velocity, sample_domain) = particles[sample_index];
+  let mut curl: f32 = 0.0;
+
+  for (i, &(position, velocity, domain)) in particles.iter().enumerate() {
+    if i == sample_index || domain != sample_domain {
+      continue;
+    }
+
+    let offset = sample_position - position;
+    let dist = offset.length();
+    let density = densities.get(i).copied().unwrap_or_default();
+
+    if dist > 0.0 && density > 0.0 {
+      let gradient = -offset / dist * smoothing_kernel_dx(dimension, radius, dist);
+      let relative = velocity - sample_velocity;
+      curl += mass / density * (relative.x * gradient.y - relative.y * gradient.x);
+    }
+  }
+
+  curl
+}
+
+pub fn density_to_pressure(density: f32, dimension: Dimension, config: &SimulationConfig) -> f32 {
+  material_pressure(density, None, dimension, config)
+}
+
+/// The pressure at `density` of a particle made of `material`, or of the
+/// config's fluid without one.
+pub fn material_pressure(
+  density: f32,
+  material: Option<&FluidMaterial>,
+  dimension: Dimension,
+  config: &SimulationConfig,
+) -> f32 {
+  let (rest_density, stiffness) = material
+    .map_or((config.target_density, config.pressure_multiplier), |material| (material.rest_density, material.stiffness));
+  forces::pressure(density, rest_density, stiffness, dimension, config.smoothing_radius)
+}
@@ -0,0 +1,168 @@
+use bevy::prelude::*;
+use rand::Rng;
+
+use crate::{
+  domain::{DomainId, Domains},
+  follow::nearest_particle,
+  material::FluidMaterial,
+  rng::SimulationRng,
+  scenario::scatter_points,
+  Particle, ParticleSpawn, SimulationConfig, SimulationMode, COLOR,
+};
+
+// random points tried per particle before the region counts as full
+const MAX_ATTEMPTS: u32 = 20;
+
+/// A world-space area to spawn particles into or remove them from.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Region {
+  /// Every domain, with the same layout in each so their runs stay
+  /// comparable, as far as their containers allow.
+  Domains,
+  /// Exactly here, once per particle. Removing at a point takes the one
+  /// nearest particle within `follow::PICK_RADIUS`, since none sits exactly on it.
+  Point(Vec2),
+  Rect { center: Vec2, half_size: Vec2 },
+  Circle { center: Vec2, radius: f32 },
+}
+
+impl Region {
+  pub fn contains(&self, point: Vec2) -> bool {
+    match *self {
+      Region::Domains => true,
+      Region::Point(at) => point == at,
+      Region::Rect { center, half_size } => (point - center).abs().cmple(half_size).all(),
+      Region::Circle { center, radius } => point.distance(center) <= radius,
+    }
+  }
+
+  fn sample(&self, rng: &mut impl Rng) -> Vec2 {
+    match *self {
+      // handled per domain in `spawn_particles`
+      Region::Domains => Vec2::ZERO,
+      Region::Point(at) => at,
+      Region::Rect { center, half_size } => {
+        center + half_size * Vec2::new(rng.gen_range(-1.0..=1.0), rng.gen_range(-1.0..=1.0))
+      }
+      Region::Circle { center, radius } => loop {
+        let offset = Vec2::new(rng.gen_range(-1.0..=1.0), rng.gen_range(-1.0..=1.0));
+        if offset.length_squared() <= 1.0 {
+          break center + offset * radius;
+        }
+      },
+    }
+  }
+}
+
+/// Adds `count` particles at random points in `region`, inside whichever
+/// domain's container each lands in, all starting at `velocity`. With a
+/// `material` they are that fluid and drawn in its colour. The console,
+/// scripts and the population target add particles through this. Emitters,
+/// and scenario, scene and checkpoint loads, spawn theirs directly, since
+/// they need components this can't carry; everything that adds particles
+/// runs before `FluidSet::BuildGrid` either way, so the solver's buffers
+/// always match.
+#[derive(Event, Clone, Copy, Debug)]
+pub struct SpawnParticles {
+  pub region: Region,
+  pub count: u32,
+  pub velocity: Vec2,
+  pub material: Option<FluidMaterial>,
+}
+
+impl SpawnParticles {
+  pub fn new(region: Region, count: u32) -> Self {
+    SpawnParticles { region, count, velocity: Vec2::ZERO, material: None }
+  }
+}
+
+/// Removes every particle in `region`, for the console and scripts. Drains,
+/// lifetimes and the population target pick particles themselves and
+/// remove them directly, also before `FluidSet::BuildGrid`.
+#[derive(Event, Clone, Copy, Debug)]
+pub struct DespawnParticles {
+  pub region: Region,
+}
+
+pub fn spawn_particles(
+  mut commands: Commands,
+  mut spawns: EventReader<SpawnParticles>,
+  mut meshes: ResMut<Assets<Mesh>>,
+  mut materials: ResMut<Assets<ColorMaterial>>,
+  domains: Res<Domains>,
+  mode: Res<SimulationMode>,
+  config: Res<SimulationConfig>,
+  mut rng: ResMut<SimulationRng>,
+) {
+  for spawn in spawns.read() {
+    if *mode == SimulationMode::ThreeD {
+      warn!("particles can only be spawned in 2D");
+      continue;
+    }
+
+    let points = match spawn.region {
+      Region::Domains => scatter_points(&domains, &config, spawn.count, &mut rng.0),
+      region => {
+        let mut points = Vec::new();
+        for _ in 0..spawn.count * MAX_ATTEMPTS {
+          if points.len() == spawn.count as usize {
+            break;
+          }
+          let point = region.sample(&mut rng.0);
+          let inside = domains.containing(point).filter(|&i| {
+            let (center, half) = domains.bounds(i);
+            domains.config(i, &config).container.contains(point - center, half)
+          });
+          if let Some(domain) = inside {
+            points.push((point, domain));
+          }
+        }
+        points
+      }
+    };
+
+    let color = spawn.material.map_or(COLOR, |material| material.color);
+    for (point, domain) in points {
+      let entity = ParticleSpawn {
+        position: point.extend(0.0),
+        velocity: spawn.velocity.extend(0.0),
+        domain: DomainId(domain),
+        color,
+        size: config.particle_size,
+      }.spawn(&mut commands, &mut meshes, &mut materials);
+      if let Some(material) = spawn.material {
+        commands.entity(entity).insert(material);
+      }
+    }
+  }
+}
+
+pub fn despawn_particles(
+  mut commands: Commands,
+  mut despawns: EventReader<DespawnParticles>,
+  particle_query: Query<(Entity, &Particle)>,
+) {
+  let regions: Vec<Region> = despawns.read().map(|despawn| despawn.region).collect();
+  if regions.is_empty() {
+    return;
+  }
+  let nearest = regions.iter().filter_map(|region| match *region {
+    Region::Point(at) => {
+      nearest_particle(particle_query.iter().map(|(entity, particle)| (entity, particle.position.truncate())), at)
+    }
+    _ => None,
+  });
+  let mut removed: Vec<Entity> = nearest.collect();
+  for (entity, particle) in &particle_query {
+    let position = particle.position.truncate();
+    if regions.iter().any(|region| !matches!(region, Region::Point(_)) && region.contains(position)) {
+      removed.push(entity);
+    }
+  }
+  // one order every run, so later spawns reuse the freed entities alike
+  removed.sort();
+  removed.dedup();
+  for entity in removed {
+    commands.entity(entity).despawn_recursive();
+  }
+}
@@ -0,0 +1,183 @@
+use bevy::{prelude::*, render::{mesh::Indices, render_resource::PrimitiveTopology, view::RenderLayers}};
+
+use crate::{follow::FollowCamera, render::MAIN_VIEW_LAYER};
+
+#[derive(Clone)]
+pub enum Background {
+  Solid(Color),
+  Gradient { top: Color, bottom: Color },
+}
+
+/// Background, text colour, and the colour of outlines and markers drawn
+/// over the fluid.
+#[derive(Resource, Clone)]
+pub struct Theme {
+  pub name: &'static str,
+  pub background: Background,
+  pub text: Color,
+  pub foreground: Color,
+}
+
+impl Default for Theme {
+  fn default() -> Self {
+    Theme::dark()
+  }
+}
+
+impl Theme {
+  pub fn dark() -> Self {
+    Theme {
+      name: "dark",
+      background: Background::Solid(ClearColor::default().0),
+      text: Color::WHITE,
+      foreground: Color::WHITE,
+    }
+  }
+
+  pub fn gradient() -> Self {
+    Theme {
+      name: "gradient",
+      background: Background::Gradient {
+        top: Color::hsl(220.0, 0.35, 0.18),
+        bottom: Color::hsl(225.0, 0.4, 0.04),
+      },
+      text: Color::WHITE,
+      foreground: Color::WHITE,
+    }
+  }
+
+  pub fn light() -> Self {
+    Theme {
+      name: "light",
+      background: Background::Gradient {
+        top: Color::hsl(210.0, 0.3, 0.96),
+        bottom: Color::hsl(210.0, 0.2, 0.8),
+      },
+      text: Color::hsl(220.0, 0.2, 0.15),
+      foreground: Color::hsl(220.0, 0.2, 0.25),
+    }
+  }
+
+  /// Flat white with black markings, for figures in reports.
+  pub fn plot() -> Self {
+    Theme {
+      name: "plot",
+      background: Background::Solid(Color::WHITE),
+      text: Color::BLACK,
+      foreground: Color::BLACK,
+    }
+  }
+
+  pub fn from_name(name: &str) -> Option<Self> {
+    match name {
+      "dark" => Some(Theme::dark()),
+      "gradient" => Some(Theme::gradient()),
+      "light" => Some(Theme::light()),
+      "plot" => Some(Theme::plot()),
+      _ => None,
+    }
+  }
+
+  fn next(&self) -> Self {
+    match self.name {
+      "dark" => Theme::gradient(),
+      "gradient" => Theme::light(),
+      "light" => Theme::plot(),
+      _ => Theme::dark(),
+    }
+  }
+
+  fn clear_color(&self) -> Color {
+    match self.background {
+      Background::Solid(color) => color,
+      Background::Gradient { bottom, .. } => bottom,
+    }
+  }
+}
+
+/// Vertex-coloured quad behind the 2D scene that draws gradient backgrounds.
+#[derive(Component)]
+pub struct BackgroundQuad;
+
+fn quad_mesh(top: Color, bottom: Color) -> Mesh {
+  let (top, bottom) = (top.to_linear().to_f32_array(), bottom.to_linear().to_f32_array());
+  Mesh::new(PrimitiveTopology::TriangleList, default())
+    .with_inserted_attribute(Mesh::ATTRIBUTE_POSITION, vec![
+      [-0.5, 0.5, 0.0], [0.5, 0.5, 0.0], [0.5, -0.5, 0.0], [-0.5, -0.5, 0.0],
+    ])
+    .with_inserted_attribute(Mesh::ATTRIBUTE_COLOR, vec![top, top, bottom, bottom])
+    .with_inserted_indices(Indices::U32(vec![0, 2, 1, 0, 3, 2]))
+}
+
+pub fn setup_background(
+  mut commands: Commands,
+  mut meshes: ResMut<Assets<Mesh>>,
+  mut materials: ResMut<Assets<ColorMaterial>>,
+  camera_query: Query<Entity, With<FollowCamera>>,
+) {
+  let Ok(camera) = camera_query.get_single() else {
+    return;
+  };
+
+  let quad = commands.spawn((
+    BackgroundQuad,
+    Mesh2d(meshes.add(quad_mesh(Color::BLACK, Color::BLACK))),
+    MeshMaterial2d(materials.add(Color::WHITE)),
+    Transform::from_xyz(0.0, 0.0, -100.0),
+    Visibility::Hidden,
+    RenderLayers::layer(MAIN_VIEW_LAYER),
+  )).id();
+
+  commands.entity(camera).add_child(quad);
+}
+
+pub fn cycle_theme(keys: Res<ButtonInput<KeyCode>>, mut theme: ResMut<Theme>) {
+  if keys.just_pressed(KeyCode::KeyT) {
+    *theme = theme.next();
+  }
+}
+
+pub fn apply_theme(
+  theme: Res<Theme>,
+  mut clear_color: ResMut<ClearColor>,
+  mut meshes: ResMut<Assets<Mesh>>,
+  mut quad_query: Query<(&Mesh2d, &mut Visibility), With<BackgroundQuad>>,
+  mut text_query: Query<(Ref<Text>, &mut TextColor)>,
+) {
+  for (text, mut color) in &mut text_query {
+    if theme.is_changed() || text.is_added() {
+      color.0 = theme.text;
+    }
+  }
+
+  if !theme.is_changed() {
+    return;
+  }
+
+  clear_color.0 = theme.clear_color();
+
+  for (mesh, mut visibility) in &mut quad_query {
+    match theme.background {
+      Background::Solid(_) => *visibility = Visibility::Hidden,
+      Background::Gradient { top, bottom } => {
+        if let Some(mesh) = meshes.get_mut(&mesh.0) {
+          *mesh = quad_mesh(top, bottom);
+        }
+        *visibility = Visibility::Inherited;
+      }
+    }
+  }
+}
+
+pub fn fit_background(
+  projection_query: Query<&OrthographicProjection, (With<FollowCamera>, Changed<OrthographicProjection>)>,
+  mut quad_query: Query<&mut Transform, With<BackgroundQuad>>,
+) {
+  let Some(projection) = projection_query.iter().next() else {
+    return;
+  };
+
+  for mut transform in &mut quad_query {
+    transform.scale = projection.area.size().extend(1.0);
+  }
+}
@@ -0,0 +1,142 @@
+use bevy::{prelude::*, input::mouse::{AccumulatedMouseMotion, AccumulatedMouseScroll}};
+use rand::Rng;
+
+use crate::{domain::DomainId, scenario::SpawnOptions, theme::Theme, Particle, SimulationConfig, COLOR};
+
+pub const BOX_SIZE: Vec3 = Vec3::new(120.0, 160.0, 60.0);
+const ORBIT_SENSITIVITY: f32 = 0.005;
+const ZOOM_SENSITIVITY: f32 = 20.0;
+
+#[derive(Component)]
+pub struct OrbitCamera {
+  pub focus: Vec3,
+  pub radius: f32,
+  pub yaw: f32,
+  pub pitch: f32,
+}
+
+impl OrbitCamera {
+  fn transform(&self) -> Transform {
+    let rotation = Quat::from_euler(EulerRot::YXZ, self.yaw, self.pitch, 0.0);
+    let translation = self.focus + rotation * Vec3::Z * self.radius;
+    Transform::from_translation(translation).looking_at(self.focus, Vec3::Y)
+  }
+}
+
+pub fn setup_3d(
+  mut commands: Commands,
+  mut meshes: ResMut<Assets<Mesh>>,
+  mut materials: ResMut<Assets<StandardMaterial>>,
+  options: Res<SpawnOptions>,
+  config: Res<SimulationConfig>,
+) {
+  let orbit = OrbitCamera {
+    focus: Vec3::ZERO,
+    radius: 400.0,
+    yaw: 0.6,
+    pitch: -0.4,
+  };
+  commands.spawn((Camera3d::default(), orbit.transform(), orbit));
+
+  commands.spawn((
+    DirectionalLight {
+      illuminance: 8000.0,
+      ..default()
+    },
+    Transform::from_xyz(100.0, 300.0, 200.0).looking_at(Vec3::ZERO, Vec3::Y),
+  ));
+
+  spawn_particles_3d(&mut commands, &mut meshes, &mut materials, &options, &config);
+}
+
+pub fn spawn_particles_3d(
+  commands: &mut Commands,
+  meshes: &mut Assets<Mesh>,
+  materials: &mut Assets<StandardMaterial>,
+  options: &SpawnOptions,
+  config: &SimulationConfig,
+) {
+  // spheres all share one mesh and material, unlike the per-particle 2D circles
+  let shape = meshes.add(Sphere::new(config.particle_size));
+  let material = materials.add(StandardMaterial {
+    base_color: COLOR,
+    ..default()
+  });
+
+  let half = BOX_SIZE / 2.0;
+
+  let mut rng = options.rng();
+  let starts: Vec<(Vec3, Vec3)> = match &options.initial {
+    Some(initial) => initial.0.iter().map(|particle| (particle.position(), particle.velocity())).collect(),
+    None => (0..options.particles)
+      .map(|_| {
+        let position = Vec3::new(
+          rng.gen_range(-half.x..half.x),
+          rng.gen_range(-half.y..half.y),
+          rng.gen_range(-half.z..half.z),
+        );
+        (position, Vec3::ZERO)
+      })
+      .collect(),
+  };
+
+  for (position, velocity) in starts {
+    let particle = Particle {
+      position,
+      velocity,
+      predicted_position: Vec3::ZERO,
+      previous_position: position,
+      mass: config.particle_size,
+      temperature: 0.0,
+    };
+
+    commands.spawn((
+      particle,
+      DomainId(0),
+      Mesh3d(shape.clone()),
+      MeshMaterial3d(material.clone()),
+      Transform::from_translation(position)
+    ));
+  }
+}
+
+pub fn orbit_camera(
+  mut camera_query: Query<(&mut Transform, &mut OrbitCamera)>,
+  mouse_buttons: Res<ButtonInput<MouseButton>>,
+  mouse_motion: Res<AccumulatedMouseMotion>,
+  mouse_scroll: Res<AccumulatedMouseScroll>,
+) {
+  for (mut transform, mut orbit) in &mut camera_query {
+    if mouse_buttons.pressed(MouseButton::Left) {
+      orbit.yaw -= mouse_motion.delta.x * ORBIT_SENSITIVITY;
+      orbit.pitch = (orbit.pitch - mouse_motion.delta.y * ORBIT_SENSITIVITY).clamp(-1.5, 1.5);
+    }
+
+    orbit.radius = (orbit.radius - mouse_scroll.delta.y * ZOOM_SENSITIVITY).max(50.0);
+    *transform = orbit.transform();
+  }
+}
+
+pub fn draw_box(mut gizmos: Gizmos, theme: Res<Theme>) {
+  gizmos.cuboid(Transform::from_scale(BOX_SIZE), theme.foreground);
+}
+
+/// Clamps a particle inside the box, reflecting the velocity on every axis it
+/// crossed. Returns the outward normal and speed of the hit, if there was
+/// one, combined over the axes when it went through a corner.
+pub fn detect_box_boundaries(particle: &mut Particle, config: &SimulationConfig) -> Option<(Vec3, f32)> {
+  let half = BOX_SIZE / 2.0 - Vec3::splat(config.particle_size);
+  let mut into_wall = Vec3::ZERO;
+
+  for axis in 0..3 {
+    if particle.position[axis].abs() > half[axis] {
+      let side = particle.position[axis].signum();
+      particle.position[axis] = half[axis] * side;
+      into_wall[axis] = (particle.velocity[axis] * side).max(0.0) * side;
+      particle.velocity[axis] *= -config.collision_dampening;
+    }
+  }
+
+  let speed = into_wall.length();
+  (speed > 0.0).then(|| (into_wall / speed, speed))
+}
@@ -0,0 +1,89 @@
+use bevy::prelude::*;
+
+use crate::SimulationConfig;
+
+// radians per second at full key press or stick deflection
+const ROTATE_RATE: f32 = 1.5;
+const STICK_DEADZONE: f32 = 0.15;
+
+/// Left and right arrows, or the left stick of any gamepad, swing gravity
+/// around the container; the down arrow points it straight down again.
+pub fn rotate_gravity(
+  keys: Res<ButtonInput<KeyCode>>,
+  gamepads: Query<&Gamepad>,
+  mut config: ResMut<SimulationConfig>,
+  time: Res<Time>,
+) {
+  let mut turn = 0.0;
+  if keys.pressed(KeyCode::ArrowLeft) {
+    turn -= 1.0;
+  }
+  if keys.pressed(KeyCode::ArrowRight) {
+    turn += 1.0;
+  }
+  for gamepad in &gamepads {
+    let stick = gamepad.get(GamepadAxis::LeftStickX).unwrap_or(0.0);
+    if stick.abs() > STICK_DEADZONE {
+      turn += stick;
+    }
+  }
+
+  if keys.just_pressed(KeyCode::ArrowDown) {
+    config.gravity_angle = 0.0;
+  } else if turn != 0.0 {
+    let angle = config.gravity_angle + turn.clamp(-1.0, 1.0) * ROTATE_RATE * time.delta_secs();
+    config.gravity_angle = angle.rem_euclid(std::f32::consts::TAU);
+  }
+}
+
+/// Most recent `(beta, gamma)` in degrees from the browser's
+/// `deviceorientation` events, written by the JS callback.
+#[cfg(target_arch = "wasm32")]
+#[derive(Resource, Clone, Default)]
+pub struct DeviceTilt(std::sync::Arc<std::sync::Mutex<Option<(f32, f32)>>>);
+
+#[cfg(target_arch = "wasm32")]
+pub fn listen_device_orientation(mut commands: Commands) {
+  use wasm_bindgen::{closure::Closure, JsCast};
+  use web_sys::DeviceOrientationEvent;
+
+  let tilt = DeviceTilt::default();
+  let Some(window) = web_sys::window() else {
+    return;
+  };
+
+  let latest = tilt.0.clone();
+  let callback = Closure::<dyn FnMut(DeviceOrientationEvent)>::new(move |event: DeviceOrientationEvent| {
+    if let (Some(beta), Some(gamma)) = (event.beta(), event.gamma()) {
+      *latest.lock().unwrap() = Some((beta as f32, gamma as f32));
+    }
+  });
+
+  if window
+    .add_event_listener_with_callback("deviceorientation", callback.as_ref().unchecked_ref())
+    .is_ok()
+  {
+    // the listener lives as long as the page
+    callback.forget();
+    commands.insert_resource(tilt);
+  }
+}
+
+/// Points gravity along the real one projected onto the screen, so tilting a
+/// phone held upright in portrait sloshes the fluid. Held flat, gravity is
+/// into the screen and the last direction is kept.
+#[cfg(target_arch = "wasm32")]
+pub fn apply_device_tilt(tilt: Option<Res<DeviceTilt>>, mut config: ResMut<SimulationConfig>) {
+  let Some(tilt) = tilt else {
+    return;
+  };
+  let Some((beta, gamma)) = tilt.0.lock().unwrap().take() else {
+    return;
+  };
+
+  let (beta, gamma) = (beta.to_radians(), gamma.to_radians());
+  let screen = Vec2::new(beta.cos() * gamma.sin(), -beta.sin());
+  if screen.length() > 0.1 {
+    config.gravity_angle = screen.x.atan2(-screen.y).rem_euclid(std::f32::consts::TAU);
+  }
+}
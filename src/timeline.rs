@@ -0,0 +1,151 @@
+use std::collections::VecDeque;
+
+use bevy::prelude::*;
+#[cfg(feature = "egui")]
+use bevy_egui::{egui, EguiContexts};
+
+use crate::{
+  render::ParticleColor,
+  scenario::LoadScenario,
+  slots::{self, ParticleSnapshot, SnapshotQuery},
+  Particle, ResetSimulation, SimulationConfig,
+};
+#[cfg(feature = "egui")]
+use crate::pause::PauseControl;
+
+const TIMELINE_KEY: KeyCode = KeyCode::KeyL;
+// simulated seconds between snapshots
+const SNAPSHOT_INTERVAL: f32 = 0.25;
+// a minute of history at the interval above
+const MAX_SNAPSHOTS: usize = 240;
+
+struct TimelineFrame {
+  time: f32,
+  particles: Vec<ParticleSnapshot>,
+}
+
+/// Snapshots of every particle, taken at intervals while the timeline is
+/// open. Scrubbing pauses on a snapshot; resuming from it drops the
+/// snapshots after it, like editing after an undo.
+#[derive(Resource, Default)]
+pub struct Timeline {
+  pub open: bool,
+  frames: VecDeque<TimelineFrame>,
+  elapsed: f32,
+  since_snapshot: f32,
+  // the snapshot being shown while scrubbed back
+  viewing: Option<usize>,
+  pending: Option<usize>,
+}
+
+impl Timeline {
+  fn clear(&mut self) {
+    *self = Timeline { open: self.open, ..default() };
+  }
+}
+
+pub fn toggle_timeline(keys: Res<ButtonInput<KeyCode>>, mut timeline: ResMut<Timeline>) {
+  if keys.just_pressed(TIMELINE_KEY) {
+    timeline.open = !timeline.open;
+    if !timeline.open {
+      timeline.clear();
+    }
+  }
+}
+
+/// Runs with the physics, so only simulated time is recorded. A scenario
+/// load or restart starts a new history.
+pub fn record_timeline(
+  mut timeline: ResMut<Timeline>,
+  particle_query: SnapshotQuery,
+  mut loads: EventReader<LoadScenario>,
+  mut resets: EventReader<ResetSimulation>,
+  time: Res<Time>,
+) {
+  let restarted = loads.read().count() + resets.read().count() > 0;
+  if !timeline.open {
+    return;
+  }
+  if restarted {
+    timeline.clear();
+  }
+
+  if let Some(index) = timeline.viewing.take() {
+    timeline.frames.truncate(index + 1);
+    timeline.elapsed = timeline.frames[index].time;
+    timeline.since_snapshot = 0.0;
+  }
+
+  timeline.elapsed += time.delta_secs();
+  timeline.since_snapshot += time.delta_secs();
+  if !timeline.frames.is_empty() && timeline.since_snapshot < SNAPSHOT_INTERVAL {
+    return;
+  }
+  timeline.since_snapshot = 0.0;
+
+  let frame = TimelineFrame {
+    time: timeline.elapsed,
+    particles: ParticleSnapshot::capture_all(&particle_query),
+  };
+  timeline.frames.push_back(frame);
+  if timeline.frames.len() > MAX_SNAPSHOTS {
+    timeline.frames.pop_front();
+  }
+}
+
+#[cfg(feature = "egui")]
+pub fn timeline_panel(
+  mut contexts: EguiContexts,
+  mut timeline: ResMut<Timeline>,
+  mut pause: PauseControl,
+) {
+  if !timeline.open {
+    return;
+  }
+
+  let Some(last) = timeline.frames.len().checked_sub(1) else {
+    egui::TopBottomPanel::bottom("timeline").show(contexts.ctx_mut(), |ui| {
+      ui.label("recording the timeline, resume the simulation to take the first snapshot");
+    });
+    return;
+  };
+  let mut index = timeline.viewing.unwrap_or(last);
+
+  egui::TopBottomPanel::bottom("timeline").show(contexts.ctx_mut(), |ui| {
+    ui.horizontal(|ui| {
+      let time = timeline.frames[index].time;
+      ui.label(format!("{time:.2}s"));
+      ui.spacing_mut().slider_width = (ui.available_width() - 80.0).max(100.0);
+      let slider = egui::Slider::new(&mut index, 0..=last).show_value(false);
+      if ui.add(slider).changed() {
+        pause.pause();
+        timeline.viewing = Some(index);
+        timeline.pending = Some(index);
+      }
+      if ui.button("Latest").clicked() && index != last {
+        pause.pause();
+        timeline.viewing = Some(last);
+        timeline.pending = Some(last);
+      }
+    });
+  });
+}
+
+/// Puts the particles back the way the scrubbed-to snapshot had them.
+pub fn scrub_timeline(
+  mut commands: Commands,
+  mut timeline: ResMut<Timeline>,
+  mut particle_query: Query<(Entity, &mut Particle, &mut ParticleColor)>,
+  mut meshes: ResMut<Assets<Mesh>>,
+  mut materials: ResMut<Assets<ColorMaterial>>,
+  config: Res<SimulationConfig>,
+) {
+  let Some(index) = timeline.pending.take() else {
+    return;
+  };
+
+  let frame = &timeline.frames[index];
+  slots::restore_in_place(
+    &mut commands, &mut particle_query, &frame.particles, config.particle_size, &mut meshes, &mut materials,
+  );
+}
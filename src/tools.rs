@@ -0,0 +1,733 @@
+use std::ops::RangeInclusive;
+
+use bevy::{input::mouse::AccumulatedMouseScroll, prelude::*, window::PrimaryWindow};
+
+use crate::{
+  domain::{DomainId, Domains},
+  drain::{Drain, DEFAULT_DRAIN_RADIUS},
+  emitter::{Emitter, DEFAULT_RATE, DEFAULT_SPEED},
+  follow::{cursor_world_position, FollowCamera},
+  history::SceneEdited,
+  obstacle::{Obstacle, ObstacleShape},
+  pin::Pinned,
+  query::FluidQuery,
+  theme::Theme,
+  ui::UiFocus,
+  zone::{Zone, ZoneOverrides},
+  Particle, SimulationConfig,
+};
+
+const HANDLE_SIZE: f32 = 8.0;
+const MIN_OBSTACLE_SIZE: f32 = 4.0;
+const EMITTER_ARROW_LENGTH: f32 = 30.0;
+// drags shorter than this keep the emitter's previous aim
+const MIN_AIM_DRAG: f32 = 4.0;
+// spacing between recorded lasso points
+const LASSO_STEP: f32 = 4.0;
+const RULER_TICK: f32 = 4.0;
+// radius change per scroll line while a brush is active
+const BRUSH_SCROLL_STEP: f32 = 0.1;
+
+pub const DRAIN_RADIUS_RANGE: RangeInclusive<f32> = 5.0..=100.0;
+pub const STIR_RADIUS_RANGE: RangeInclusive<f32> = 10.0..=300.0;
+pub const HEAT_RADIUS_RANGE: RangeInclusive<f32> = 10.0..=200.0;
+pub const ZONE_RADIUS_RANGE: RangeInclusive<f32> = 10.0..=200.0;
+
+/// What a left click in the 2D view does.
+#[derive(Resource, Clone, Copy, PartialEq, Eq, Default, Debug, serde::Serialize, serde::Deserialize)]
+pub enum Tool {
+  #[default]
+  Follow,
+  Inspect,
+  Obstacle,
+  Emitter,
+  Drain,
+  Impulse,
+  Stir,
+  Heat,
+  Ruler,
+  Zone,
+  Pin,
+}
+
+impl Tool {
+  pub const ALL: [Tool; 11] = [
+    Tool::Follow,
+    Tool::Inspect,
+    Tool::Obstacle,
+    Tool::Emitter,
+    Tool::Drain,
+    Tool::Impulse,
+    Tool::Stir,
+    Tool::Heat,
+    Tool::Ruler,
+    Tool::Zone,
+    Tool::Pin,
+  ];
+
+  pub fn label(self) -> &'static str {
+    match self {
+      Tool::Follow => "Follow",
+      Tool::Inspect => "Inspect",
+      Tool::Obstacle => "Obstacle",
+      Tool::Emitter => "Emitter",
+      Tool::Drain => "Drain",
+      Tool::Impulse => "Impulse",
+      Tool::Stir => "Stir",
+      Tool::Heat => "Heat",
+      Tool::Ruler => "Ruler",
+      Tool::Zone => "Zone",
+      Tool::Pin => "Pin",
+    }
+  }
+}
+
+#[derive(Clone, Copy, Debug)]
+enum ObstacleGrab {
+  Move { offset: Vec2 },
+  Resize,
+}
+
+/// Options and in-progress drag of the obstacle tool.
+#[derive(Resource, Default)]
+pub struct ObstacleTool {
+  pub place_box: bool,
+  grab: Option<(Entity, ObstacleGrab)>,
+}
+
+fn handle_position(shape: ObstacleShape, center: Vec2) -> Vec2 {
+  match shape {
+    ObstacleShape::Circle { radius } => center + Vec2::X * radius,
+    ObstacleShape::Box { half_size } => center + half_size,
+  }
+}
+
+fn resized(shape: ObstacleShape, local: Vec2) -> ObstacleShape {
+  match shape {
+    ObstacleShape::Circle { .. } => ObstacleShape::Circle {
+      radius: local.length().max(MIN_OBSTACLE_SIZE),
+    },
+    ObstacleShape::Box { .. } => ObstacleShape::Box {
+      half_size: local.abs().max(Vec2::splat(MIN_OBSTACLE_SIZE)),
+    },
+  }
+}
+
+/// Clicking empty space drops a new obstacle and drags out its size, dragging
+/// an obstacle moves it, dragging its corner handle resizes it, and Delete
+/// removes the one under the cursor.
+pub fn obstacle_tool(
+  mut commands: Commands,
+  mouse_buttons: Res<ButtonInput<MouseButton>>,
+  keys: Res<ButtonInput<KeyCode>>,
+  window_query: Query<&Window, With<PrimaryWindow>>,
+  camera_query: Query<(&Camera, &GlobalTransform), With<FollowCamera>>,
+  mut obstacle_query: Query<(Entity, &mut Obstacle, &mut Transform)>,
+  mut tool: ResMut<ObstacleTool>,
+  focus: Res<UiFocus>,
+  mut gizmos: Gizmos,
+  theme: Res<Theme>,
+  mut edits: EventWriter<SceneEdited>,
+) {
+  if mouse_buttons.just_released(MouseButton::Left) && tool.grab.take().is_some() {
+    edits.send(SceneEdited::Edit);
+  }
+
+  let Some(cursor) = cursor_world_position(&window_query, &camera_query) else {
+    return;
+  };
+
+  for (_, obstacle, transform) in &obstacle_query {
+    let handle = handle_position(obstacle.shape, transform.translation.truncate());
+    gizmos.rect_2d(Isometry2d::from_translation(handle), Vec2::splat(HANDLE_SIZE), theme.foreground);
+  }
+
+  let hovered = obstacle_query
+    .iter()
+    .find(|(_, obstacle, transform)| obstacle.shape.sdf(cursor - transform.translation.truncate()) < 0.0)
+    .map(|(entity, _, transform)| (entity, transform.translation.truncate()));
+
+  if keys.just_pressed(KeyCode::Delete) || keys.just_pressed(KeyCode::Backspace) {
+    if let Some((entity, _)) = hovered {
+      commands.entity(entity).despawn_recursive();
+      edits.send(SceneEdited::Edit);
+    }
+  }
+
+  if mouse_buttons.just_pressed(MouseButton::Left) && !focus.pointer {
+    let on_handle = obstacle_query.iter().find(|(_, obstacle, transform)| {
+      handle_position(obstacle.shape, transform.translation.truncate()).distance(cursor) < HANDLE_SIZE
+    });
+
+    tool.grab = if let Some((entity, _, _)) = on_handle {
+      Some((entity, ObstacleGrab::Resize))
+    } else if let Some((entity, center)) = hovered {
+      Some((entity, ObstacleGrab::Move { offset: center - cursor }))
+    } else {
+      let shape = if tool.place_box {
+        ObstacleShape::Box { half_size: Vec2::splat(MIN_OBSTACLE_SIZE) }
+      } else {
+        ObstacleShape::Circle { radius: MIN_OBSTACLE_SIZE }
+      };
+      let entity = commands
+        .spawn((Obstacle { shape }, Transform::from_translation(cursor.extend(-1.0))))
+        .id();
+      Some((entity, ObstacleGrab::Resize))
+    };
+    return;
+  }
+
+  let Some((entity, grab)) = tool.grab else {
+    return;
+  };
+  let Ok((_, mut obstacle, mut transform)) = obstacle_query.get_mut(entity) else {
+    return;
+  };
+
+  match grab {
+    ObstacleGrab::Move { offset } => {
+      transform.translation = (cursor + offset).extend(transform.translation.z);
+    }
+    ObstacleGrab::Resize => {
+      let shape = resized(obstacle.shape, cursor - transform.translation.truncate());
+      // only touch the component when the size changes, its visuals are rebuilt on change
+      if shape != obstacle.shape {
+        obstacle.shape = shape;
+      }
+    }
+  }
+}
+
+/// Settings for emitters placed with the emitter tool, and the one being aimed.
+#[derive(Resource)]
+pub struct EmitterTool {
+  pub rate: f32,
+  pub speed: f32,
+  aiming: Option<Entity>,
+}
+
+impl Default for EmitterTool {
+  fn default() -> Self {
+    EmitterTool { rate: DEFAULT_RATE, speed: DEFAULT_SPEED, aiming: None }
+  }
+}
+
+/// Clicking places an emitter at the cursor and dragging before releasing
+/// aims it; Delete removes the emitter nearest the cursor.
+pub fn emitter_tool(
+  mut commands: Commands,
+  mouse_buttons: Res<ButtonInput<MouseButton>>,
+  keys: Res<ButtonInput<KeyCode>>,
+  window_query: Query<&Window, With<PrimaryWindow>>,
+  camera_query: Query<(&Camera, &GlobalTransform), With<FollowCamera>>,
+  mut emitter_query: Query<(Entity, &mut Emitter)>,
+  mut tool: ResMut<EmitterTool>,
+  domains: Res<Domains>,
+  focus: Res<UiFocus>,
+  mut gizmos: Gizmos,
+  mut edits: EventWriter<SceneEdited>,
+) {
+  if mouse_buttons.just_released(MouseButton::Left) && tool.aiming.take().is_some() {
+    edits.send(SceneEdited::Edit);
+  }
+
+  for (_, emitter) in &emitter_query {
+    let tip = emitter.position + emitter.direction * EMITTER_ARROW_LENGTH;
+    gizmos.arrow_2d(emitter.position, tip, emitter.color);
+  }
+
+  let Some(cursor) = cursor_world_position(&window_query, &camera_query) else {
+    return;
+  };
+
+  if keys.just_pressed(KeyCode::Delete) || keys.just_pressed(KeyCode::Backspace) {
+    let nearest = emitter_query
+      .iter()
+      .map(|(entity, emitter)| (entity, emitter.position.distance(cursor)))
+      .filter(|&(_, dist)| dist < EMITTER_ARROW_LENGTH)
+      .min_by(|a, b| a.1.total_cmp(&b.1));
+    if let Some((entity, _)) = nearest {
+      commands.entity(entity).despawn();
+      edits.send(SceneEdited::Edit);
+    }
+  }
+
+  if mouse_buttons.just_pressed(MouseButton::Left) && !focus.pointer {
+    let Some(domain) = domains.containing(cursor) else {
+      return;
+    };
+
+    let mut emitter = Emitter::new(cursor, Vec2::Y, emitter_query.iter().len(), DomainId(domain));
+    emitter.rate = tool.rate;
+    emitter.speed = tool.speed;
+    tool.aiming = Some(commands.spawn(emitter).id());
+    return;
+  }
+
+  let Some(entity) = tool.aiming else {
+    return;
+  };
+  if let Ok((_, mut emitter)) = emitter_query.get_mut(entity) {
+    let aim = cursor - emitter.position;
+    if aim.length() > MIN_AIM_DRAG {
+      emitter.direction = aim.normalize();
+    }
+  }
+}
+
+/// Settings for drains placed with the drain tool.
+#[derive(Resource)]
+pub struct DrainTool {
+  pub radius: f32,
+  pub limited: bool,
+  pub max_rate: f32,
+}
+
+impl Default for DrainTool {
+  fn default() -> Self {
+    DrainTool { radius: DEFAULT_DRAIN_RADIUS, limited: false, max_rate: 20.0 }
+  }
+}
+
+/// Clicking places a drain at the cursor; Delete removes the hovered one.
+pub fn drain_tool(
+  mut commands: Commands,
+  mouse_buttons: Res<ButtonInput<MouseButton>>,
+  keys: Res<ButtonInput<KeyCode>>,
+  window_query: Query<&Window, With<PrimaryWindow>>,
+  camera_query: Query<(&Camera, &GlobalTransform), With<FollowCamera>>,
+  drain_query: Query<(Entity, &Drain)>,
+  tool: Res<DrainTool>,
+  focus: Res<UiFocus>,
+  mut edits: EventWriter<SceneEdited>,
+  mut gizmos: Gizmos,
+  theme: Res<Theme>,
+) {
+  let Some(cursor) = cursor_world_position(&window_query, &camera_query) else {
+    return;
+  };
+  if !focus.pointer {
+    gizmos.circle_2d(cursor, tool.radius, theme.foreground.with_alpha(0.5));
+  }
+
+  if keys.just_pressed(KeyCode::Delete) || keys.just_pressed(KeyCode::Backspace) {
+    let hovered = drain_query
+      .iter()
+      .find(|(_, drain)| drain.position.distance(cursor) < drain.radius);
+    if let Some((entity, _)) = hovered {
+      commands.entity(entity).despawn();
+      edits.send(SceneEdited::Edit);
+    }
+  }
+
+  if mouse_buttons.just_pressed(MouseButton::Left) && !focus.pointer {
+    let max_rate = tool.limited.then_some(tool.max_rate);
+    commands.spawn(Drain::new(cursor, tool.radius, max_rate));
+    edits.send(SceneEdited::Edit);
+  }
+}
+
+/// Settings for the impulse tool, and the selection being dragged out.
+#[derive(Resource)]
+pub struct ImpulseTool {
+  pub lasso: bool,
+  /// Replace the selected particles' velocity instead of adding to it.
+  pub set_velocity: bool,
+  pub velocity: Vec2,
+  selection: Vec<Vec2>,
+}
+
+impl Default for ImpulseTool {
+  fn default() -> Self {
+    ImpulseTool { lasso: false, set_velocity: false, velocity: Vec2::new(300.0, 0.0), selection: Vec::new() }
+  }
+}
+
+impl ImpulseTool {
+  fn contains(&self, point: Vec2) -> bool {
+    if self.lasso {
+      // even-odd rule over the closed lasso
+      let mut inside = false;
+      let mut previous = *self.selection.last().unwrap();
+      for &current in &self.selection {
+        if (current.y > point.y) != (previous.y > point.y) {
+          let t = (point.y - current.y) / (previous.y - current.y);
+          if point.x < current.x + t * (previous.x - current.x) {
+            inside = !inside;
+          }
+        }
+        previous = current;
+      }
+      inside
+    } else {
+      let (start, end) = (self.selection[0], *self.selection.last().unwrap());
+      point.cmpge(start.min(end)).all() && point.cmple(start.max(end)).all()
+    }
+  }
+}
+
+/// Dragging selects a rectangle or lasso; releasing kicks every particle
+/// inside with the tool's velocity.
+pub fn impulse_tool(
+  mouse_buttons: Res<ButtonInput<MouseButton>>,
+  window_query: Query<&Window, With<PrimaryWindow>>,
+  camera_query: Query<(&Camera, &GlobalTransform), With<FollowCamera>>,
+  mut particle_query: Query<&mut Particle>,
+  fluid: FluidQuery,
+  mut tool: ResMut<ImpulseTool>,
+  focus: Res<UiFocus>,
+  mut gizmos: Gizmos,
+  theme: Res<Theme>,
+) {
+  let cursor = cursor_world_position(&window_query, &camera_query);
+
+  if mouse_buttons.just_pressed(MouseButton::Left) && !focus.pointer {
+    tool.selection = cursor.into_iter().collect();
+    return;
+  }
+
+  if tool.selection.is_empty() {
+    return;
+  }
+
+  if mouse_buttons.just_released(MouseButton::Left) {
+    if tool.selection.len() > 2 || !tool.lasso {
+      let velocity = tool.velocity.extend(0.0);
+      // the lasso's bounds narrow it down to the particles worth testing
+      let (min, max) = tool.selection.iter().fold((Vec2::INFINITY, Vec2::NEG_INFINITY), |(min, max), point| {
+        (min.min(*point), max.max(*point))
+      });
+      for entity in fluid.particles_in_rect((min + max) / 2.0, (max - min) / 2.0) {
+        let Ok(mut particle) = particle_query.get_mut(entity) else {
+          continue;
+        };
+        if tool.contains(particle.position.truncate()) {
+          particle.velocity = if tool.set_velocity { velocity } else { particle.velocity + velocity };
+        }
+      }
+    }
+    tool.selection.clear();
+    return;
+  }
+
+  if let Some(cursor) = cursor {
+    let last = *tool.selection.last().unwrap();
+    if !tool.lasso {
+      tool.selection.truncate(1);
+      tool.selection.push(cursor);
+    } else if last.distance(cursor) > LASSO_STEP {
+      tool.selection.push(cursor);
+    }
+  }
+
+  if tool.lasso {
+    gizmos.linestrip_2d(tool.selection.iter().copied().chain(tool.selection.first().copied()), theme.foreground);
+  } else {
+    let (start, end) = (tool.selection[0], *tool.selection.last().unwrap());
+    gizmos.rect_2d(Isometry2d::from_translation((start + end) / 2.0), (end - start).abs(), theme.foreground);
+  }
+
+  let center = tool.selection.iter().sum::<Vec2>() / tool.selection.len() as f32;
+  gizmos.arrow_2d(center, center + tool.velocity * 0.1, theme.foreground);
+}
+
+/// The rectangle being dragged out by the pin tool.
+#[derive(Resource, Default)]
+pub struct PinTool {
+  selection: Option<(Vec2, Vec2)>,
+}
+
+/// Dragging a rectangle pins every particle inside it in place; holding
+/// Shift on release unpins them instead.
+pub fn pin_tool(
+  mut commands: Commands,
+  mouse_buttons: Res<ButtonInput<MouseButton>>,
+  keys: Res<ButtonInput<KeyCode>>,
+  window_query: Query<&Window, With<PrimaryWindow>>,
+  camera_query: Query<(&Camera, &GlobalTransform), With<FollowCamera>>,
+  fluid: FluidQuery,
+  mut tool: ResMut<PinTool>,
+  focus: Res<UiFocus>,
+  mut gizmos: Gizmos,
+  theme: Res<Theme>,
+) {
+  let cursor = cursor_world_position(&window_query, &camera_query);
+
+  if mouse_buttons.just_pressed(MouseButton::Left) && !focus.pointer {
+    tool.selection = cursor.map(|cursor| (cursor, cursor));
+    return;
+  }
+  let Some((start, mut end)) = tool.selection else {
+    return;
+  };
+  if let Some(cursor) = cursor {
+    end = cursor;
+    tool.selection = Some((start, end));
+  }
+  let (min, max) = (start.min(end), start.max(end));
+
+  if mouse_buttons.just_released(MouseButton::Left) {
+    let unpin = keys.any_pressed([KeyCode::ShiftLeft, KeyCode::ShiftRight]);
+    for entity in fluid.particles_in_rect((min + max) / 2.0, (max - min) / 2.0) {
+      let Some(mut entity) = commands.get_entity(entity) else {
+        continue;
+      };
+      if unpin {
+        entity.remove::<Pinned>();
+      } else {
+        entity.insert(Pinned);
+      }
+    }
+    tool.selection = None;
+    return;
+  }
+
+  gizmos.rect_2d(Isometry2d::from_translation((min + max) / 2.0), max - min, theme.foreground);
+}
+
+/// Radius, strength and direction of the stirring brush.
+#[derive(Resource)]
+pub struct StirTool {
+  pub radius: f32,
+  pub strength: f32,
+  pub clockwise: bool,
+}
+
+impl Default for StirTool {
+  fn default() -> Self {
+    StirTool { radius: 80.0, strength: 3000.0, clockwise: false }
+  }
+}
+
+/// While the left button is held, particles near the cursor are pushed
+/// tangentially around it, strongest at the centre and fading to the edge.
+pub fn stir_tool(
+  mouse_buttons: Res<ButtonInput<MouseButton>>,
+  window_query: Query<&Window, With<PrimaryWindow>>,
+  camera_query: Query<(&Camera, &GlobalTransform), With<FollowCamera>>,
+  mut particle_query: Query<&mut Particle>,
+  tool: Res<StirTool>,
+  mut gizmos: Gizmos,
+  theme: Res<Theme>,
+  time: Res<Time>,
+) {
+  let Some(cursor) = cursor_world_position(&window_query, &camera_query) else {
+    return;
+  };
+  gizmos.circle_2d(cursor, tool.radius, theme.foreground);
+
+  if !mouse_buttons.pressed(MouseButton::Left) {
+    return;
+  }
+
+  let sign = if tool.clockwise { -1.0 } else { 1.0 };
+  for mut particle in &mut particle_query {
+    let offset = particle.position.truncate() - cursor;
+    let dist = offset.length();
+    if dist >= tool.radius || dist < f32::EPSILON {
+      continue;
+    }
+
+    let falloff = 1.0 - dist / tool.radius;
+    let swirl = offset.perp() / dist * sign * tool.strength * falloff;
+    particle.velocity += swirl.extend(0.0) * time.delta_secs();
+  }
+}
+
+/// Radius and rate of the heat brush; a negative rate cools.
+#[derive(Resource)]
+pub struct HeatTool {
+  pub radius: f32,
+  pub rate: f32,
+}
+
+impl Default for HeatTool {
+  fn default() -> Self {
+    HeatTool { radius: 40.0, rate: 20.0 }
+  }
+}
+
+/// While the left button is held, particles under the brush are heated (or
+/// cooled) at the brush's rate.
+pub fn heat_tool(
+  mouse_buttons: Res<ButtonInput<MouseButton>>,
+  window_query: Query<&Window, With<PrimaryWindow>>,
+  camera_query: Query<(&Camera, &GlobalTransform), With<FollowCamera>>,
+  mut particle_query: Query<&mut Particle>,
+  fluid: FluidQuery,
+  tool: Res<HeatTool>,
+  mut gizmos: Gizmos,
+  time: Res<Time>,
+) {
+  let Some(cursor) = cursor_world_position(&window_query, &camera_query) else {
+    return;
+  };
+  let color = if tool.rate >= 0.0 { Color::srgb(0.9, 0.3, 0.1) } else { Color::srgb(0.2, 0.5, 0.9) };
+  gizmos.circle_2d(cursor, tool.radius, color);
+
+  if !mouse_buttons.pressed(MouseButton::Left) {
+    return;
+  }
+
+  for entity in fluid.particles_in_circle(cursor, tool.radius) {
+    if let Ok(mut particle) = particle_query.get_mut(entity) {
+      particle.temperature += tool.rate * time.delta_secs();
+    }
+  }
+}
+
+/// Brush size and the overrides painted with it, plus where the stroke last
+/// left a dab.
+#[derive(Resource)]
+pub struct ZoneTool {
+  pub radius: f32,
+  pub overrides: ZoneOverrides,
+  painting: bool,
+  last_dab: Option<Vec2>,
+  edited: bool,
+}
+
+impl Default for ZoneTool {
+  fn default() -> Self {
+    ZoneTool { radius: 40.0, overrides: ZoneOverrides::MUD, painting: false, last_dab: None, edited: false }
+  }
+}
+
+/// Dragging paints a zone along the cursor's path; Shift-dragging erases
+/// any zone the brush touches. The stroke becomes one undo step.
+pub fn zone_tool(
+  mut commands: Commands,
+  mouse_buttons: Res<ButtonInput<MouseButton>>,
+  keys: Res<ButtonInput<KeyCode>>,
+  window_query: Query<&Window, With<PrimaryWindow>>,
+  camera_query: Query<(&Camera, &GlobalTransform), With<FollowCamera>>,
+  zone_query: Query<(Entity, &Zone)>,
+  mut tool: ResMut<ZoneTool>,
+  focus: Res<UiFocus>,
+  mut edits: EventWriter<SceneEdited>,
+  mut gizmos: Gizmos,
+  theme: Res<Theme>,
+) {
+  if mouse_buttons.just_pressed(MouseButton::Left) && !focus.pointer {
+    tool.painting = true;
+  }
+  if mouse_buttons.just_released(MouseButton::Left) {
+    tool.painting = false;
+    tool.last_dab = None;
+    if std::mem::take(&mut tool.edited) {
+      edits.send(SceneEdited::Edit);
+    }
+  }
+
+  let Some(cursor) = cursor_world_position(&window_query, &camera_query) else {
+    return;
+  };
+  if !focus.pointer {
+    gizmos.circle_2d(cursor, tool.radius, theme.foreground.with_alpha(0.5));
+  }
+
+  if !tool.painting {
+    return;
+  }
+
+  if keys.any_pressed([KeyCode::ShiftLeft, KeyCode::ShiftRight]) {
+    for (entity, zone) in &zone_query {
+      if zone.position.distance(cursor) < zone.radius + tool.radius {
+        commands.entity(entity).despawn();
+        tool.edited = true;
+      }
+    }
+    return;
+  }
+
+  // dabs half a radius apart make a continuous stroke
+  if tool.last_dab.is_some_and(|last| last.distance(cursor) < tool.radius * 0.5) {
+    return;
+  }
+  commands.spawn(Zone { position: cursor, radius: tool.radius, overrides: tool.overrides });
+  tool.last_dab = Some(cursor);
+  tool.edited = true;
+}
+
+/// Whether the active tool is a brush sized by its radius, which the scroll
+/// wheel resizes instead of zooming.
+pub fn brush_active(tool: Res<Tool>) -> bool {
+  matches!(*tool, Tool::Drain | Tool::Stir | Tool::Heat | Tool::Zone)
+}
+
+/// Scrolling scales the active brush's radius, within the range its slider
+/// allows. The brush's own circle around the cursor previews the new size.
+pub fn resize_brush(
+  mouse_scroll: Res<AccumulatedMouseScroll>,
+  tool: Res<Tool>,
+  mut drain_tool: ResMut<DrainTool>,
+  mut stir_tool: ResMut<StirTool>,
+  mut heat_tool: ResMut<HeatTool>,
+  mut zone_tool: ResMut<ZoneTool>,
+) {
+  let delta = mouse_scroll.delta.y;
+  if delta == 0.0 {
+    return;
+  }
+
+  let (radius, range) = match *tool {
+    Tool::Drain => (&mut drain_tool.radius, DRAIN_RADIUS_RANGE),
+    Tool::Stir => (&mut stir_tool.radius, STIR_RADIUS_RANGE),
+    Tool::Heat => (&mut heat_tool.radius, HEAT_RADIUS_RANGE),
+    Tool::Zone => (&mut zone_tool.radius, ZONE_RADIUS_RANGE),
+    _ => return,
+  };
+  let scale = (1.0 + BRUSH_SCROLL_STEP).powf(delta.clamp(-10.0, 10.0));
+  *radius = (*radius * scale).clamp(*range.start(), *range.end());
+}
+
+/// The segment last measured with the ruler, kept after the drag ends.
+#[derive(Resource, Default)]
+pub struct RulerTool {
+  pub segment: Option<(Vec2, Vec2)>,
+}
+
+impl RulerTool {
+  pub fn length(&self) -> Option<f32> {
+    self.segment.map(|(start, end)| start.distance(end))
+  }
+}
+
+/// Left drag measures from where it started to the cursor, ticked every
+/// smoothing radius so sizes can be read off against the kernel.
+pub fn ruler_tool(
+  mouse_buttons: Res<ButtonInput<MouseButton>>,
+  window_query: Query<&Window, With<PrimaryWindow>>,
+  camera_query: Query<(&Camera, &GlobalTransform), With<FollowCamera>>,
+  focus: Res<UiFocus>,
+  mut tool: ResMut<RulerTool>,
+  config: Res<SimulationConfig>,
+  mut gizmos: Gizmos,
+  theme: Res<Theme>,
+) {
+  if let Some(cursor) = cursor_world_position(&window_query, &camera_query) {
+    if mouse_buttons.just_pressed(MouseButton::Left) && !focus.pointer {
+      tool.segment = Some((cursor, cursor));
+    } else if mouse_buttons.pressed(MouseButton::Left) {
+      if let Some((_, end)) = &mut tool.segment {
+        *end = cursor;
+      }
+    }
+  }
+
+  let Some((start, end)) = tool.segment else {
+    return;
+  };
+  gizmos.line_2d(start, end, theme.foreground);
+
+  let length = start.distance(end);
+  let direction = (end - start).normalize_or_zero();
+  let normal = direction.perp() * RULER_TICK;
+  let mut distance = 0.0;
+  while distance <= length {
+    let tick = start + direction * distance;
+    gizmos.line_2d(tick - normal, tick + normal, theme.foreground);
+    distance += config.smoothing_radius;
+  }
+  gizmos.line_2d(end - normal * 2.0, end + normal * 2.0, theme.foreground);
+}
@@ -0,0 +1,124 @@
+use bevy::{input::touch::Touch, prelude::*};
+
+use crate::{
+  follow::{CameraAnchor, FollowCamera, Tagged, MAX_ZOOM, MIN_ZOOM},
+  pause::{PauseControl, SimState},
+  ui::UiFocus,
+  Particle,
+};
+
+const TOUCH_RADIUS: f32 = 60.0;
+// pull towards a finger, and how strongly particles take on its motion
+const TOUCH_ATTRACTION: f32 = 1500.0;
+const TOUCH_DRAG: f32 = 8.0;
+// how far apart in seconds and screen pixels two taps count as a double tap,
+// and how far a finger may move for its touch to count as a tap at all
+const DOUBLE_TAP_INTERVAL: f64 = 0.3;
+const DOUBLE_TAP_DISTANCE: f32 = 40.0;
+const TAP_DISTANCE: f32 = 10.0;
+
+/// Touch input for tablets and phones, mainly the web build. Exactly two
+/// fingers pan and pinch-zoom the 2D camera; otherwise every finger pulls
+/// the fluid around it towards itself and along with its motion.
+pub fn touch_interaction(
+  touches: Res<Touches>,
+  mut camera_query: Query<(&Camera, &GlobalTransform, &mut Transform, &mut OrthographicProjection), With<FollowCamera>>,
+  mut particle_query: Query<&mut Particle>,
+  tagged_query: Query<Entity, With<Tagged>>,
+  mut commands: Commands,
+  mut anchor: ResMut<CameraAnchor>,
+  focus: Res<UiFocus>,
+  time: Res<Time>,
+) {
+  let Ok((camera, camera_transform, mut transform, mut projection)) = camera_query.get_single_mut() else {
+    return;
+  };
+  let to_world = |screen: Vec2| camera.viewport_to_world_2d(camera_transform, screen).ok();
+
+  let fingers: Vec<&Touch> = touches.iter().collect();
+  if focus.pointer || fingers.is_empty() {
+    return;
+  }
+
+  if let [first, second] = fingers[..] {
+    let (Some(a), Some(b), Some(previous_a), Some(previous_b)) = (
+      to_world(first.position()),
+      to_world(second.position()),
+      to_world(first.previous_position()),
+      to_world(second.previous_position()),
+    ) else {
+      return;
+    };
+
+    // panning takes the camera off any particle it was following
+    for entity in &tagged_query {
+      commands.entity(entity).remove::<Tagged>();
+    }
+
+    let pan = (previous_a + previous_b) / 2.0 - (a + b) / 2.0;
+    let pinch = previous_a.distance(previous_b) / a.distance(b).max(f32::EPSILON);
+    projection.scale = (projection.scale * pinch).clamp(MIN_ZOOM, MAX_ZOOM);
+    transform.translation += pan.extend(0.0);
+    anchor.position = transform.translation.truncate();
+    return;
+  }
+
+  let dt = time.delta_secs();
+  if dt <= 0.0 {
+    return;
+  }
+
+  for finger in fingers {
+    let (Some(position), Some(previous)) = (to_world(finger.position()), to_world(finger.previous_position())) else {
+      continue;
+    };
+    let finger_velocity = (position - previous) / dt;
+
+    for mut particle in &mut particle_query {
+      let offset = position - particle.position.truncate();
+      let dist = offset.length();
+      if dist >= TOUCH_RADIUS {
+        continue;
+      }
+
+      let falloff = 1.0 - dist / TOUCH_RADIUS;
+      let pull = offset.normalize_or_zero() * TOUCH_ATTRACTION;
+      let drag = (finger_velocity - particle.velocity.truncate()) * TOUCH_DRAG;
+      particle.velocity += ((pull + drag) * falloff * dt).extend(0.0);
+    }
+  }
+}
+
+/// A double tap pauses and resumes, since a touch screen has no space bar.
+/// Taps that land on a panel don't count.
+pub fn double_tap_pause(
+  touches: Res<Touches>,
+  state: Res<State<SimState>>,
+  mut control: PauseControl,
+  mut last_tap: Local<Option<(f64, Vec2)>>,
+  focus: Res<UiFocus>,
+  time: Res<Time<Real>>,
+) {
+  if focus.pointer {
+    return;
+  }
+  let now = time.elapsed_secs_f64();
+  for finger in touches.iter_just_released() {
+    if finger.distance().length() > TAP_DISTANCE {
+      continue;
+    }
+    let position = finger.position();
+    let double = last_tap
+      .take()
+      .is_some_and(|(at, previous)| now - at <= DOUBLE_TAP_INTERVAL && previous.distance(position) <= DOUBLE_TAP_DISTANCE);
+    if !double {
+      *last_tap = Some((now, position));
+    } else if *state.get() == SimState::Running {
+      control.pause();
+      info!("simulation paused");
+    } else if *state.get() != SimState::Loading {
+      control.resume();
+      info!("simulation resumed");
+    }
+  }
+}
@@ -0,0 +1,45 @@
+use bevy::{prelude::*, window::PrimaryWindow};
+#[cfg(feature = "egui")]
+use bevy_egui::EguiContexts;
+
+use crate::replay::InputReplay;
+
+/// The egui panels, which lay themselves out after the hotkeys and before
+/// `track_pointer` reads whether they took the pointer.
+#[derive(SystemSet, Clone, PartialEq, Eq, Hash, Debug)]
+pub struct PanelSet;
+
+/// Whether egui is using the pointer this frame, so world clicks and scrolls
+/// over the panel don't also tag particles or zoom the camera.
+#[derive(Resource, Default)]
+pub struct UiFocus {
+  pub pointer: bool,
+}
+
+pub fn pointer_free(focus: Res<UiFocus>) -> bool {
+  !focus.pointer
+}
+
+/// False when running headless, where there is no egui context to draw into.
+pub fn windowed(window_query: Query<(), With<PrimaryWindow>>) -> bool {
+  !window_query.is_empty()
+}
+
+/// Runs after every panel has been laid out for the frame.
+/// A replay uses the recorded focus, so replayed clicks land where they did.
+pub fn track_pointer(
+  #[cfg(feature = "egui")] mut contexts: EguiContexts,
+  mut focus: ResMut<UiFocus>,
+  replay: Option<Res<InputReplay>>,
+) {
+  focus.pointer = match replay {
+    Some(replay) => replay.pointer_over_ui(),
+    #[cfg(feature = "egui")]
+    None => {
+      let ctx = contexts.ctx_mut();
+      ctx.wants_pointer_input() || ctx.is_pointer_over_area()
+    }
+    #[cfg(not(feature = "egui"))]
+    None => false,
+  };
+}
@@ -0,0 +1,64 @@
+use bevy::prelude::*;
+
+/// What changes for particles inside a zone, relative to the global
+/// parameters.
+#[derive(Clone, Copy, PartialEq, Debug, serde::Serialize, serde::Deserialize)]
+pub struct ZoneOverrides {
+  pub gravity_scale: f32,
+  pub viscosity_scale: f32,
+  // fraction of velocity lost per second
+  pub damping: f32,
+}
+
+impl ZoneOverrides {
+  /// Leaves the global parameters as they are.
+  pub const NONE: ZoneOverrides = ZoneOverrides { gravity_scale: 1.0, viscosity_scale: 1.0, damping: 0.0 };
+  pub const MUD: ZoneOverrides = ZoneOverrides { gravity_scale: 1.0, viscosity_scale: 8.0, damping: 3.0 };
+  pub const ICE: ZoneOverrides = ZoneOverrides { gravity_scale: 1.0, viscosity_scale: 0.0, damping: 0.0 };
+  pub const WEIGHTLESS: ZoneOverrides = ZoneOverrides { gravity_scale: 0.0, viscosity_scale: 1.0, damping: 0.0 };
+
+  pub const PRESETS: [(&'static str, ZoneOverrides); 3] = [
+    ("mud", ZoneOverrides::MUD),
+    ("ice", ZoneOverrides::ICE),
+    ("weightless", ZoneOverrides::WEIGHTLESS),
+  ];
+
+  /// Brown for thick zones, pale blue for slippery ones, violet where gravity
+  /// is weakened.
+  #[cfg(feature = "render")]
+  fn color(&self) -> Color {
+    if self.gravity_scale < 1.0 {
+      Color::srgba(0.6, 0.4, 0.9, 0.6)
+    } else if self.viscosity_scale > 1.0 || self.damping > 0.0 {
+      Color::srgba(0.55, 0.35, 0.15, 0.6)
+    } else {
+      Color::srgba(0.6, 0.85, 1.0, 0.6)
+    }
+  }
+}
+
+/// One dab of a painted region; a brush stroke leaves a trail of them.
+#[derive(Component, Clone, Debug, serde::Serialize, serde::Deserialize)]
+pub struct Zone {
+  pub position: Vec2,
+  pub radius: f32,
+  pub overrides: ZoneOverrides,
+}
+
+/// The overrides at `point`. Where dabs overlap, the one centred nearest
+/// wins, so painting over a zone with another replaces it.
+pub fn overrides_at(zones: &[Zone], point: Vec2) -> ZoneOverrides {
+  zones
+    .iter()
+    .map(|zone| (zone, zone.position.distance(point)))
+    .filter(|(zone, dist)| *dist < zone.radius)
+    .min_by(|a, b| a.1.total_cmp(&b.1))
+    .map_or(ZoneOverrides::NONE, |(zone, _)| zone.overrides)
+}
+
+#[cfg(feature = "render")]
+pub fn draw_zones(zone_query: Query<&Zone>, mut gizmos: Gizmos) {
+  for zone in &zone_query {
+    gizmos.circle_2d(zone.position, zone.radius, zone.overrides.color());
+  }
+}
@@ -0,0 +1,27 @@
+//! Arguments that would crash the app at startup are turned away by the
+//! parser instead.
+
+use std::process::Command;
+
+fn rejects(args: &[&str]) {
+  let output = Command::new(env!("CARGO_BIN_EXE_fluid-simulation")).args(args).output().unwrap();
+  let stderr = String::from_utf8_lossy(&output.stderr);
+  // clap's usage error, not a panic
+  assert_eq!(output.status.code(), Some(2), "{args:?}: {stderr}");
+  assert!(stderr.contains("error: invalid value"), "{args:?}: {stderr}");
+}
+
+#[test]
+fn empty_or_negative_domains_are_rejected() {
+  rejects(&["--headless", "--domain", "0x400"]);
+  rejects(&["--headless", "--domain=-100x400"]);
+  rejects(&["--headless", "--domain", "NaNx400"]);
+}
+
+#[test]
+fn steps_must_be_positive_and_finite() {
+  rejects(&["--headless", "--dt=-1"]);
+  rejects(&["--headless", "--dt", "0"]);
+  rejects(&["--headless", "--dt", "NaN"]);
+  rejects(&["--headless", "--dt", "inf"]);
+}
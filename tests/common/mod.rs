@@ -0,0 +1,53 @@
+//! The headless app the integration tests step: `MinimalPlugins` and the
+//! physics chain, without the rendering, input or panels.
+
+use std::time::Duration;
+
+use bevy::{prelude::*, time::TimeUpdateStrategy};
+use fluid_simulation::{
+  domain::{DomainId, Domains},
+  Particle, SimulationConfig, SolverPlugin,
+};
+
+pub const DOMAIN_SIZE: Vec2 = Vec2::new(600.0, 400.0);
+pub const BLOCK: (u32, u32) = (15, 20);
+
+/// Runs at a fixed 60 steps a second, with a block of `BLOCK` particles
+/// dropped into the bottom left of each domain.
+pub fn app(config: SimulationConfig, domains: Domains) -> App {
+  let mut app = App::new();
+  app
+    .add_plugins((MinimalPlugins, SolverPlugin { config, domains: domains.clone(), ..default() }))
+    .insert_resource(TimeUpdateStrategy::ManualDuration(Duration::from_secs_f64(1.0 / 60.0)));
+
+  for i in 0..domains.params.len() {
+    let (center, half) = domains.bounds(i);
+    let corner = center - half + Vec2::splat(50.0);
+    for row in 0..BLOCK.1 {
+      for column in 0..BLOCK.0 {
+        let position = (corner + Vec2::new(column as f32, row as f32) * 8.0).extend(0.0);
+        app.world_mut().spawn((
+          Particle::at_rest(position, 1.0),
+          DomainId(i),
+          Transform::from_translation(position),
+        ));
+      }
+    }
+  }
+  app
+}
+
+pub fn step(app: &mut App, steps: u32) {
+  for _ in 0..steps {
+    app.update();
+  }
+}
+
+/// Every particle, in spawn order.
+pub fn particles(app: &mut App) -> Vec<Particle> {
+  let world = app.world_mut();
+  let mut particles: Vec<(Entity, Particle)> =
+    world.query::<(Entity, &Particle)>().iter(world).map(|(entity, particle)| (entity, particle.clone())).collect();
+  particles.sort_by_key(|(entity, _)| *entity);
+  particles.into_iter().map(|(_, particle)| particle).collect()
+}
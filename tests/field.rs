@@ -0,0 +1,56 @@
+//! `FluidField` samples against SPH sums over every particle.
+#![cfg(feature = "render")]
+
+use bevy::{ecs::system::RunSystemOnce, prelude::*};
+use fluid_simulation::{
+  domain::Domains,
+  field::{FieldSample, FluidField},
+  kernels::{smoothing_kernel, Dimension},
+  query::{index_regions, ParticleIndex},
+  solver::{density_to_pressure, SimulationState},
+  Particle, SimulationConfig,
+};
+
+mod common;
+
+use common::{particles, step, DOMAIN_SIZE};
+
+fn app() -> App {
+  let mut app = common::app(SimulationConfig::default(), Domains::single(DOMAIN_SIZE));
+  app.init_resource::<ParticleIndex>().add_systems(Last, index_regions);
+  step(&mut app, 90);
+  app
+}
+
+/// The field at `point` summed over every particle, as it was before the grid.
+fn scanned(app: &mut App, point: Vec3) -> FieldSample {
+  let config = SimulationConfig::default();
+  let world = app.world_mut();
+  let mut query = world.query::<(Entity, &Particle)>();
+  let state = world.resource::<SimulationState>();
+  let mut sample = FieldSample::default();
+  for (entity, particle) in query.iter(world) {
+    let influence = smoothing_kernel(Dimension::Two, config.smoothing_radius, point.distance(particle.position));
+    sample.density += config.mass * influence;
+    sample.velocity += particle.velocity * config.mass / state.density_of(entity) * influence;
+  }
+  sample.pressure = density_to_pressure(sample.density, Dimension::Two, &config);
+  sample
+}
+
+#[test]
+fn samples_match_a_scan_over_every_particle() {
+  let mut app = app();
+  let mut points: Vec<Vec3> =
+    particles(&mut app).iter().step_by(37).map(|particle| particle.position + Vec3::new(1.5, -2.0, 0.0)).collect();
+  points.push(Vec3::new(250.0, 150.0, 0.0));
+  for point in points {
+    let sample = app.world_mut().run_system_once(move |field: FluidField| field.sample(point)).unwrap();
+    let expected = scanned(&mut app, point);
+    // only the order of the sums differs
+    let close = |a: f32, b: f32| (a - b).abs() <= 1e-4 * b.abs().max(1.0);
+    assert!(close(sample.density, expected.density), "{point}: {sample:?} vs {expected:?}");
+    assert!(close(sample.pressure, expected.pressure), "{point}: {sample:?} vs {expected:?}");
+    assert!(sample.velocity.abs_diff_eq(expected.velocity, 1e-4 * expected.velocity.length().max(1.0)), "{point}: {sample:?} vs {expected:?}");
+  }
+}
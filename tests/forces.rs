@@ -0,0 +1,112 @@
+//! The solver's neighbour sums, run through the same checks in 2D and 3D,
+//! since both simulations share one implementation of them.
+
+use bevy::prelude::*;
+use fluid_simulation::{
+  kernels::{smoothing_kernel, SphVector},
+  layers::CollisionLayers,
+  solver::{calculate_cohesion, calculate_density, calculate_pressure_force, calculate_viscosity_force, SimulationState},
+  SimulationConfig,
+};
+use proptest::prelude::*;
+
+/// One domain holding every particle, at `densities`.
+fn state(densities: Vec<f32>) -> SimulationState {
+  let count = densities.len();
+  SimulationState {
+    densities,
+    layers: vec![CollisionLayers::default(); count],
+    materials: vec![None; count],
+    domains: vec![(0..count).collect()],
+    ..Default::default()
+  }
+}
+
+fn point() -> impl Strategy<Value = Vec3> {
+  (-60.0f32..60.0, -60.0f32..60.0, -60.0f32..60.0).prop_map(|(x, y, z)| Vec3::new(x, y, z))
+}
+
+fn pressure_is_equal_and_opposite<V: SphVector>(a: Vec3, b: Vec3, density: f32) -> Result<(), TestCaseError> {
+  let config = SimulationConfig::default();
+  let positions = [V::from_vec3(a), V::from_vec3(b)];
+  let state = state(vec![density, density]);
+  let on_a = calculate_pressure_force(&positions, &[0, 1], &state, 0, &config, 1.0).to_vec3();
+  let on_b = calculate_pressure_force(&positions, &[0, 1], &state, 1, &config, 1.0).to_vec3();
+  let tolerance = 1e-4 * on_a.length().max(1.0);
+  prop_assert!((on_a + on_b).length() <= tolerance, "{:?} against {:?}", on_a, on_b);
+  Ok(())
+}
+
+fn lone_density_is_its_own_mass<V: SphVector>(a: Vec3, b: Vec3) -> Result<(), TestCaseError> {
+  let config = SimulationConfig::default();
+  let radius = config.smoothing_radius;
+  // b is moved out past the radius, so it doesn't count
+  let far = a + Vec3::X * (radius + 1.0 + b.x.abs());
+  let positions = [V::from_vec3(a), V::from_vec3(far)];
+  let layers = [CollisionLayers::default(); 2];
+  let density = calculate_density(&positions, &layers, &[0, 1], 0, radius, config.mass);
+  prop_assert_eq!(density, config.mass * smoothing_kernel(V::DIMENSION, radius, 0.0));
+  Ok(())
+}
+
+fn viscosity_pulls_towards_the_neighbour<V: SphVector>(a: Vec3, b: Vec3, velocity: Vec3) -> Result<(), TestCaseError> {
+  let config = SimulationConfig::default();
+  let particles = [(V::from_vec3(a), V::default()), (V::from_vec3(b), V::from_vec3(velocity))];
+  let state = state(vec![1.0, 1.0]);
+  let force = calculate_viscosity_force(&particles, &[0, 1], &state, 0, config.smoothing_radius, config.mass);
+  prop_assert!(force.to_vec3().dot(V::from_vec3(velocity).to_vec3()) >= 0.0);
+  Ok(())
+}
+
+fn cohesion_pulls_towards_the_neighbour<V: SphVector>(a: Vec3, b: Vec3) -> Result<(), TestCaseError> {
+  let config = SimulationConfig::default();
+  let positions = [V::from_vec3(a), V::from_vec3(b)];
+  let state = state(vec![1.0, 1.0]);
+  let cohesion = calculate_cohesion(&positions, &[0, 1], &state, 0, config.smoothing_radius, config.mass);
+  let towards = (positions[1] - positions[0]).to_vec3();
+  prop_assert!(cohesion.to_vec3().dot(towards) >= 0.0);
+  Ok(())
+}
+
+proptest! {
+  #[test]
+  fn pressure_forces_are_equal_and_opposite(a in point(), b in point(), density in 0.001f32..1.0) {
+    pressure_is_equal_and_opposite::<Vec2>(a, b, density)?;
+    pressure_is_equal_and_opposite::<Vec3>(a, b, density)?;
+  }
+
+  #[test]
+  fn a_lone_particle_only_counts_itself(a in point(), b in point()) {
+    lone_density_is_its_own_mass::<Vec2>(a, b)?;
+    lone_density_is_its_own_mass::<Vec3>(a, b)?;
+  }
+
+  #[test]
+  fn viscosity_drags_along_with_the_neighbour(a in point(), b in point(), velocity in point()) {
+    viscosity_pulls_towards_the_neighbour::<Vec2>(a, b, velocity)?;
+    viscosity_pulls_towards_the_neighbour::<Vec3>(a, b, velocity)?;
+  }
+
+  #[test]
+  fn cohesion_pulls_neighbours_together(a in point(), b in point()) {
+    cohesion_pulls_towards_the_neighbour::<Vec2>(a, b)?;
+    cohesion_pulls_towards_the_neighbour::<Vec3>(a, b)?;
+  }
+
+  /// In the plane, the same particles give the same density up to the
+  /// kernel's normalisation, so one sum serves both dimensions.
+  #[test]
+  fn flat_densities_differ_only_by_normalisation(points in prop::collection::vec(point(), 2..12)) {
+    let config = SimulationConfig::default();
+    let radius = config.smoothing_radius;
+    let flat: Vec<Vec3> = points.iter().map(|point| point.with_z(0.0)).collect();
+    let layers = vec![CollisionLayers::default(); flat.len()];
+    let members: Vec<usize> = (0..flat.len()).collect();
+
+    let planar: Vec<Vec2> = flat.iter().map(|point| Vec2::from_vec3(*point)).collect();
+    let two = calculate_density(&planar, &layers, &members, 0, radius, config.mass);
+    let three = calculate_density(&flat, &layers, &members, 0, radius, config.mass);
+    let ratio = <Vec3 as SphVector>::DIMENSION.kernel_volume(radius) / <Vec2 as SphVector>::DIMENSION.kernel_volume(radius);
+    prop_assert!((two - three * ratio).abs() <= 1e-4 * two.max(1e-6), "{} against {}", two, three * ratio);
+  }
+}
@@ -0,0 +1,90 @@
+//! Runs small scenes for a fixed number of steps and compares every particle
+//! against the state stored in `tests/golden/`, so physics changes show up
+//! as failures rather than slipping through. After an intended change,
+//! regenerate the files with `UPDATE_GOLDEN=1 cargo test --test golden`.
+
+use std::{fmt::Write as _, fs, path::PathBuf};
+
+use fluid_simulation::{
+  determinism::state_checksum,
+  domain::{ContainerShape, Domains},
+  Particle, SimulationConfig,
+};
+
+mod common;
+
+use common::{particles, step, DOMAIN_SIZE};
+
+const STEPS: u32 = 90;
+// compared with a tolerance rather than the checksum, since platforms' maths
+// libraries differ in the last bits; a physics change moves particles further
+const POSITION_TOLERANCE: f32 = 0.05;
+const VELOCITY_TOLERANCE: f32 = 0.5;
+
+fn golden_path(name: &str) -> PathBuf {
+  PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests/golden").join(name).with_extension("csv")
+}
+
+fn to_csv(particles: &[Particle]) -> String {
+  let mut csv = format!("# checksum {:016x}\nx,y,vx,vy\n", state_checksum(particles));
+  for particle in particles {
+    let (p, v) = (particle.position, particle.velocity);
+    let _ = writeln!(csv, "{},{},{},{}", p.x, p.y, v.x, v.y);
+  }
+  csv
+}
+
+fn from_csv(csv: &str) -> Vec<[f32; 4]> {
+  csv
+    .lines()
+    .filter(|line| !line.starts_with('#'))
+    .skip(1)
+    .map(|line| {
+      let values: Vec<f32> = line.split(',').map(|value| value.parse().expect("golden values are numbers")).collect();
+      values.try_into().expect("golden rows have four columns")
+    })
+    .collect()
+}
+
+fn check(name: &str, config: SimulationConfig, domains: Domains) {
+  let mut app = common::app(config, domains);
+  step(&mut app, STEPS);
+  let particles = particles(&mut app);
+
+  let path = golden_path(name);
+  if std::env::var_os("UPDATE_GOLDEN").is_some() {
+    fs::write(&path, to_csv(&particles)).expect("could not write the golden file");
+    return;
+  }
+
+  let csv = fs::read_to_string(&path)
+    .unwrap_or_else(|err| panic!("could not read {}: {err}; run with UPDATE_GOLDEN=1 to create it", path.display()));
+  let golden = from_csv(&csv);
+  assert_eq!(particles.len(), golden.len(), "{name}: particle count changed");
+
+  for (i, (particle, [x, y, vx, vy])) in particles.iter().zip(golden).enumerate() {
+    let (position, velocity) = (particle.position.truncate(), particle.velocity.truncate());
+    assert!(
+      position.distance(bevy::math::vec2(x, y)) <= POSITION_TOLERANCE
+        && velocity.distance(bevy::math::vec2(vx, vy)) <= VELOCITY_TOLERANCE,
+      "{name}: particle {i} is at {position} moving at {velocity}, expected ({x}, {y}) moving at ({vx}, {vy}); \
+       if the change was intended, rerun with UPDATE_GOLDEN=1",
+    );
+  }
+}
+
+#[test]
+fn dam_break() {
+  check("dam_break", SimulationConfig::default(), Domains::single(DOMAIN_SIZE));
+}
+
+#[test]
+fn round_container() {
+  let config = SimulationConfig { container: ContainerShape::Circle, ..Default::default() };
+  check("round_container", config, Domains::single(DOMAIN_SIZE));
+}
+
+#[test]
+fn side_by_side_domains() {
+  check("side_by_side_domains", SimulationConfig::default(), Domains::comparison(DOMAIN_SIZE));
+}
@@ -0,0 +1,45 @@
+//! Heat spreading through the fluid and lifting the warm parts of it.
+
+use bevy::prelude::*;
+use fluid_simulation::{domain::Domains, pin::Pinned, Particle, SimulationConfig};
+
+mod common;
+
+use common::{particles, step, DOMAIN_SIZE};
+
+/// The block with its first particle, a bottom corner, heated and pinned.
+fn app(domains: Domains) -> App {
+  let mut app = common::app(SimulationConfig::default(), domains);
+  let world = app.world_mut();
+  let heated = world.query_filtered::<Entity, With<Particle>>().iter(world).min().unwrap();
+  world.entity_mut(heated).insert(Pinned).get_mut::<Particle>().unwrap().temperature = 100.0;
+  app
+}
+
+#[test]
+fn heat_reaches_the_neighbours() {
+  let mut app = app(Domains::single(DOMAIN_SIZE));
+  // the first update only starts the clock
+  step(&mut app, 2);
+  let particles = particles(&mut app);
+  assert!(particles[0].temperature < 100.0);
+  assert!(particles[1].temperature > 0.0);
+}
+
+#[test]
+fn pinned_particles_are_not_lifted() {
+  let mut app = app(Domains::single(DOMAIN_SIZE));
+  step(&mut app, 10);
+  assert_eq!(particles(&mut app)[0].velocity, Vec3::ZERO);
+}
+
+#[test]
+fn domains_conduct_with_their_own_parameters() {
+  let mut domains = Domains::single(DOMAIN_SIZE);
+  domains.params[0].config = Some(SimulationConfig { thermal_diffusion: 0.0, cooling: 0.0, ..default() });
+  let mut app = app(domains);
+  step(&mut app, 2);
+  let particles = particles(&mut app);
+  assert_eq!(particles[0].temperature, 100.0);
+  assert_eq!(particles[1].temperature, 0.0);
+}
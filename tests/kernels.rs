@@ -0,0 +1,75 @@
+//! Properties every SPH kernel needs for the solver's densities and forces
+//! to come out right, checked over random radii and distances.
+
+use std::f32::consts::PI;
+
+use fluid_simulation::kernels::{smoothing_kernel, smoothing_kernel_dx, Dimension};
+use proptest::prelude::*;
+
+fn dimension() -> impl Strategy<Value = Dimension> {
+  prop_oneof![Just(Dimension::Two), Just(Dimension::Three)]
+}
+
+// the smoothing radii the parameter panel allows, with room either side
+fn radius() -> impl Strategy<Value = f32> {
+  1.0f32..200.0
+}
+
+proptest! {
+  #[test]
+  fn kernel_is_never_negative(dimension in dimension(), radius in radius(), dist in 0.0f32..400.0) {
+    prop_assert!(smoothing_kernel(dimension, radius, dist) >= 0.0);
+    prop_assert!(smoothing_kernel_dx(dimension, radius, dist) >= 0.0);
+  }
+
+  #[test]
+  fn kernel_vanishes_outside_its_radius(dimension in dimension(), radius in radius(), beyond in 0.0f32..400.0) {
+    let dist = radius + beyond;
+    prop_assert_eq!(smoothing_kernel(dimension, radius, dist), 0.0);
+    prop_assert_eq!(smoothing_kernel_dx(dimension, radius, dist), 0.0);
+  }
+
+  #[test]
+  fn kernel_falls_off_with_distance(
+    dimension in dimension(),
+    radius in radius(),
+    near in 0.0f32..1.0,
+    far in 0.0f32..1.0,
+  ) {
+    let (near, far) = (near.min(far) * radius, near.max(far) * radius);
+    prop_assert!(smoothing_kernel(dimension, radius, near) >= smoothing_kernel(dimension, radius, far));
+    prop_assert!(smoothing_kernel_dx(dimension, radius, near) >= smoothing_kernel_dx(dimension, radius, far));
+  }
+
+  /// `smoothing_kernel_dx` is the slope's magnitude: the kernel only falls.
+  #[test]
+  fn gradient_matches_a_numerical_derivative(dimension in dimension(), radius in radius(), t in 0.05f32..0.95) {
+    let dist = t * radius;
+    let h = radius * 1e-3;
+    let slope = (smoothing_kernel(dimension, radius, dist + h) - smoothing_kernel(dimension, radius, dist - h)) / (2.0 * h);
+    let gradient = smoothing_kernel_dx(dimension, radius, dist);
+    prop_assert!(
+      (gradient + slope).abs() <= 1e-2 * gradient.abs().max(f32::EPSILON),
+      "dx {} against a numerical slope of {}", gradient, slope,
+    );
+  }
+
+  /// Summed over the disc or ball it covers, the kernel comes to one, so a
+  /// particle's density is its neighbours' mass and nothing else.
+  #[test]
+  fn kernel_integrates_to_one(dimension in dimension(), radius in radius()) {
+    const SHELLS: usize = 2000;
+    let width = radius / SHELLS as f32;
+    let integral: f64 = (0..SHELLS)
+      .map(|i| {
+        let dist = (i as f32 + 0.5) * width;
+        let shell = match dimension {
+          Dimension::Two => 2.0 * PI * dist,
+          Dimension::Three => 4.0 * PI * dist * dist,
+        };
+        (smoothing_kernel(dimension, radius, dist) * shell * width) as f64
+      })
+      .sum();
+    prop_assert!((integral - 1.0).abs() < 1e-3, "integral {}", integral);
+  }
+}
@@ -0,0 +1,28 @@
+//! The metrics endpoint keeps answering scrapes while another client sits
+//! idle on its connection.
+#![cfg(feature = "metrics")]
+
+use std::{
+  io::{Read, Write},
+  net::{TcpListener, TcpStream},
+  time::{Duration, Instant},
+};
+
+use fluid_simulation::metrics::MetricsEndpoint;
+
+#[test]
+fn an_idle_client_does_not_hold_up_scrapes() {
+  let port = TcpListener::bind("127.0.0.1:0").unwrap().local_addr().unwrap().port();
+  let _endpoint = MetricsEndpoint::bind(("127.0.0.1", port)).unwrap();
+
+  let _idle = TcpStream::connect(("127.0.0.1", port)).unwrap();
+  let started = Instant::now();
+  let mut scrape = TcpStream::connect(("127.0.0.1", port)).unwrap();
+  scrape.set_read_timeout(Some(Duration::from_secs(10))).unwrap();
+  scrape.write_all(b"GET /metrics HTTP/1.1\r\n\r\n").unwrap();
+  let mut response = String::new();
+  scrape.read_to_string(&mut response).unwrap();
+
+  assert!(response.starts_with("HTTP/1.1 200 OK"), "{response}");
+  assert!(started.elapsed() < Duration::from_secs(2), "the scrape waited {:?}", started.elapsed());
+}
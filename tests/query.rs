@@ -0,0 +1,58 @@
+//! Area queries through `FluidQuery` against checking every particle, on a
+//! fluid that has had time to spread out.
+#![cfg(feature = "render")]
+
+use bevy::{ecs::system::RunSystemOnce, prelude::*};
+use fluid_simulation::{
+  domain::Domains,
+  query::{index_regions, FluidQuery, ParticleIndex},
+  spawn::Region,
+  Particle, SimulationConfig,
+};
+
+mod common;
+
+use common::{particles, step, DOMAIN_SIZE};
+
+fn app() -> App {
+  let mut app = common::app(SimulationConfig::default(), Domains::single(DOMAIN_SIZE));
+  app.init_resource::<ParticleIndex>().add_systems(Last, index_regions);
+  step(&mut app, 90);
+  app
+}
+
+/// What `FluidQuery` finds in `region`, and what a scan over every particle does.
+fn found_and_expected(app: &mut App, region: Region) -> (Vec<Entity>, Vec<Entity>) {
+  let found = app.world_mut().run_system_once(move |fluid: FluidQuery| fluid.particles_in(region)).unwrap();
+  let world = app.world_mut();
+  let expected = world
+    .query::<(Entity, &Particle)>()
+    .iter(world)
+    .filter(|(_, particle)| region.contains(particle.position.truncate()))
+    .map(|(entity, _)| entity)
+    .collect();
+  (found, expected)
+}
+
+#[test]
+fn rects_and_circles_find_the_same_particles_as_a_scan() {
+  let mut app = app();
+  let regions = [
+    Region::Rect { center: Vec2::new(-150.0, -150.0), half_size: Vec2::new(80.0, 40.0) },
+    Region::Rect { center: Vec2::ZERO, half_size: DOMAIN_SIZE },
+    Region::Circle { center: Vec2::new(-200.0, -170.0), radius: 45.0 },
+    Region::Circle { center: Vec2::new(250.0, 150.0), radius: 5.0 },
+    Region::Domains,
+  ];
+  for region in regions {
+    let (found, expected) = found_and_expected(&mut app, region);
+    assert_eq!(found, expected, "{region:?}");
+  }
+}
+
+#[test]
+fn a_region_around_the_fluid_finds_every_particle() {
+  let mut app = app();
+  let (found, _) = found_and_expected(&mut app, Region::Rect { center: Vec2::ZERO, half_size: DOMAIN_SIZE });
+  assert_eq!(found.len(), particles(&mut app).len());
+}
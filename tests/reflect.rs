@@ -0,0 +1,17 @@
+//! The reflected `SimulationConfig` is what bevy-inspector-egui edits, so it
+//! has to reach the same fields the console and scripts set by name.
+
+use bevy::reflect::Struct;
+use fluid_simulation::SimulationConfig;
+
+#[test]
+fn every_named_parameter_is_a_reflected_field() {
+  let mut config = SimulationConfig::default();
+  for (i, name) in SimulationConfig::FIELDS.into_iter().enumerate() {
+    *config.field_mut(name).unwrap() = i as f32 + 0.5;
+  }
+  for (i, name) in SimulationConfig::FIELDS.into_iter().enumerate() {
+    let field = config.field(name).and_then(|field| field.try_downcast_ref::<f32>());
+    assert_eq!(field, Some(&(i as f32 + 0.5)), "{name}");
+  }
+}
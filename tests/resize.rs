@@ -0,0 +1,64 @@
+//! Window resizes under each `ResizePolicy`, against a stand-in primary
+//! window since the tests run without one.
+#![cfg(feature = "render")]
+
+use bevy::{
+  prelude::*,
+  window::{PrimaryWindow, WindowResized},
+};
+use fluid_simulation::{
+  domain::{handle_window_resize, Domains, ResizePolicy},
+  Particle,
+};
+
+const DOMAIN_SIZE: Vec2 = Vec2::new(600.0, 400.0);
+const POINT: Vec3 = Vec3::new(150.0, -100.0, 0.0);
+
+/// The domain size and the particle at `POINT` after the primary window is
+/// resized to `width` by `height`.
+fn resize(policy: ResizePolicy, width: f32, height: f32) -> (Vec2, Vec3) {
+  resize_window(policy, true, width, height)
+}
+
+/// As `resize`, but with a second, non-primary window open, like the
+/// diagnostics one, and resizing that instead unless `primary`.
+fn resize_window(policy: ResizePolicy, primary: bool, width: f32, height: f32) -> (Vec2, Vec3) {
+  let mut app = App::new();
+  app
+    .insert_resource(Domains::single(DOMAIN_SIZE))
+    .insert_resource(policy)
+    .add_event::<WindowResized>()
+    .add_systems(Update, handle_window_resize);
+  let primary_window = app.world_mut().spawn((Window::default(), PrimaryWindow)).id();
+  let other_window = app.world_mut().spawn(Window::default()).id();
+  let window = if primary { primary_window } else { other_window };
+  let particle = app.world_mut().spawn(Particle::at_rest(POINT, 1.0)).id();
+
+  app.world_mut().send_event(WindowResized { window, width, height });
+  app.update();
+  let position = app.world().get::<Particle>(particle).unwrap().position;
+  (app.world().resource::<Domains>().size, position)
+}
+
+#[test]
+fn keeping_the_domain_leaves_walls_and_particles_alone() {
+  assert_eq!(resize(ResizePolicy::KeepDomain, 400.0, 400.0), (DOMAIN_SIZE, POINT));
+}
+
+#[test]
+fn rescaling_follows_the_window_shape_at_the_same_height() {
+  let (size, position) = resize(ResizePolicy::RescaleDomain, 400.0, 400.0);
+  assert_eq!(size, Vec2::new(400.0, 400.0));
+  // the same spot relative to the walls
+  assert_eq!(position, Vec3::new(100.0, -100.0, 0.0));
+}
+
+#[test]
+fn minimising_is_not_a_resize() {
+  assert_eq!(resize(ResizePolicy::RescaleDomain, 0.0, 0.0), (DOMAIN_SIZE, POINT));
+}
+
+#[test]
+fn other_windows_resizing_leave_the_domain_alone() {
+  assert_eq!(resize_window(ResizePolicy::RescaleDomain, false, 400.0, 400.0), (DOMAIN_SIZE, POINT));
+}
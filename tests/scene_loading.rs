@@ -0,0 +1,107 @@
+//! Loading a scene file in the background: the physics holds in
+//! `SimState::Loading` while it is read and spawned in batches, then runs.
+#![cfg(feature = "render")]
+
+use std::{fs, path::PathBuf};
+
+use bevy::{prelude::*, state::app::StatesPlugin};
+use fluid_simulation::{
+  domain::Domains,
+  history::SceneEdited,
+  pause::SimState,
+  replay::InputRecorder,
+  rng::SimulationRng,
+  scenario::SpawnOptions,
+  scene::{continue_scene_load, load_scene, LoadScene, LoadedScene, SceneFile, SceneLoading},
+  Particle, SimulationConfig,
+};
+
+// more than one frame's batch
+const SCENE: &str = "(name: \"Big block\", particles: [Rect(center: (0.0, 0.0), size: (560.0, 360.0), spacing: 3.0)])";
+
+fn app() -> App {
+  let mut app = App::new();
+  app
+    .add_plugins((MinimalPlugins, StatesPlugin))
+    .init_state::<SimState>()
+    .insert_resource(Domains::single(Vec2::new(600.0, 400.0)))
+    .init_resource::<SimulationConfig>()
+    .init_resource::<SpawnOptions>()
+    .init_resource::<LoadedScene>()
+    .insert_resource(SimulationRng::new(Some(0)))
+    .init_resource::<InputRecorder>()
+    .init_resource::<Assets<Mesh>>()
+    .init_resource::<Assets<ColorMaterial>>()
+    .add_event::<LoadScene>()
+    .add_event::<SceneEdited>()
+    .add_systems(Update, (load_scene, continue_scene_load.run_if(resource_exists::<SceneLoading>)).chain());
+  app
+}
+
+fn scene_file(name: &str, text: &str) -> PathBuf {
+  let path = std::env::temp_dir().join(format!("fluid-{name}-{}.ron", std::process::id()));
+  fs::write(&path, text).unwrap();
+  path
+}
+
+fn state(app: &App) -> SimState {
+  *app.world().resource::<State<SimState>>().get()
+}
+
+/// Steps until the load finishes, returning how many frames it held the physics.
+fn load(app: &mut App, path: PathBuf) -> u32 {
+  app.world_mut().send_event(LoadScene::File(path));
+  app.update();
+  let mut frames = 0;
+  while app.world().contains_resource::<SceneLoading>() {
+    assert!(frames < 1000, "the load never finished");
+    app.update();
+    assert_eq!(state(app), SimState::Loading);
+    frames += 1;
+  }
+  // the state changes take effect at the start of the next frame
+  app.update();
+  frames
+}
+
+#[test]
+fn a_file_loads_in_batches_then_resumes() {
+  let mut app = app();
+  let path = scene_file("big", SCENE);
+  let frames = load(&mut app, path.clone());
+  fs::remove_file(path).ok();
+
+  let scene: SceneFile = ron::from_str(SCENE).unwrap();
+  let expected = scene.sample(app.world().resource::<Domains>(), &SimulationConfig::default(), || {}).len();
+  let world = app.world_mut();
+  assert_eq!(world.query::<&Particle>().iter(world).count(), expected);
+  assert!(frames > 1, "spawned {expected} particles in {frames} frames");
+  assert_eq!(app.world().resource::<LoadedScene>().0.as_ref().unwrap().name, "Big block");
+  assert_eq!(state(&app), SimState::Running);
+}
+
+#[test]
+fn an_unreadable_file_resumes_without_a_scene() {
+  let mut app = app();
+  load(&mut app, std::env::temp_dir().join("fluid-missing-scene.ron"));
+  assert_eq!(state(&app), SimState::Running);
+  assert!(app.world().resource::<LoadedScene>().0.is_none());
+}
+
+#[test]
+fn a_failed_load_keeps_the_current_scene() {
+  let mut app = app();
+  let path = scene_file("kept", SCENE);
+  load(&mut app, path.clone());
+  fs::remove_file(path).ok();
+  let world = app.world_mut();
+  let before = world.query::<&Particle>().iter(world).count();
+
+  let broken = scene_file("broken", "(name: \"Broken\", particles: [");
+  load(&mut app, broken.clone());
+  fs::remove_file(broken).ok();
+  let world = app.world_mut();
+  assert_eq!(world.query::<&Particle>().iter(world).count(), before);
+  assert_eq!(app.world().resource::<LoadedScene>().0.as_ref().unwrap().name, "Big block");
+  assert_eq!(state(&app), SimState::Running);
+}
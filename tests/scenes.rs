@@ -0,0 +1,29 @@
+//! The scene files shipped in `scenes/` parse and fit their particles inside
+//! the domain they ask for.
+#![cfg(feature = "render")]
+
+use std::fs;
+
+use fluid_simulation::{
+  domain::{Domains, DEFAULT_DOMAIN_SIZE},
+  scene::SceneFile,
+};
+
+#[test]
+fn bundled_scenes_load_and_fit_their_domain() {
+  let mut paths: Vec<_> = fs::read_dir("scenes").unwrap().map(|entry| entry.unwrap().path()).collect();
+  paths.sort();
+  assert!(!paths.is_empty());
+
+  for path in paths {
+    let scene = SceneFile::load(&path).unwrap_or_else(|err| panic!("{}: {err}", path.display()));
+    let domains = Domains::single(scene.domain.unwrap_or(DEFAULT_DOMAIN_SIZE));
+    let config = scene.config.clone().unwrap_or_default();
+    let particles = scene.sample(&domains, &config, || {});
+    assert!(!particles.is_empty() || !scene.emitters.is_empty(), "{} starts empty", path.display());
+
+    // blocks poking out of the container are clipped, which a bigger domain shows
+    let unclipped = scene.sample(&Domains::single(domains.size * 4.0), &config, || {});
+    assert_eq!(particles.len(), unclipped.len(), "{} has particles outside its container", path.display());
+  }
+}
@@ -0,0 +1,56 @@
+//! Saved files carry a schema version, and older ones are upgraded through
+//! the migrations before they are parsed.
+
+#[cfg(feature = "render")]
+use fluid_simulation::scene::SceneFile;
+use fluid_simulation::{
+  domain::ContainerShape,
+  schema::{self, Migration, SCHEMA_VERSION},
+  SimulationConfig,
+};
+
+#[test]
+fn saved_files_lead_with_the_version_and_read_back() {
+  let config = SimulationConfig { viscosity: 0.7, container: ContainerShape::Hexagon, ..Default::default() };
+  let text = schema::to_string(&config).unwrap();
+  assert!(text.starts_with(&format!("(\n    version: {SCHEMA_VERSION},")), "{text}");
+  assert_eq!(schema::from_str::<SimulationConfig>(&text).unwrap(), config);
+}
+
+#[test]
+fn files_from_before_versioning_still_load() {
+  let config: SimulationConfig = schema::from_str("(gravity: 200.0, container: Circle)").unwrap();
+  assert_eq!(config, SimulationConfig { gravity: 200.0, container: ContainerShape::Circle, ..Default::default() });
+}
+
+#[cfg(feature = "render")]
+#[test]
+fn scenes_from_before_versioning_still_load() {
+  let scene = SceneFile::load("scenes/two-blocks.ron".as_ref()).unwrap();
+  assert_eq!(scene.name, "Two blocks");
+}
+
+#[test]
+fn unversioned_files_read_as_version_one() {
+  assert_eq!(schema::version("(gravity: 200.0)").unwrap(), 1);
+  assert_eq!(schema::version("(version: 3, gravity: 200.0)").unwrap(), 3);
+
+  // so they still go through every migration after the first version
+  let rename = [Migration { version: 2, apply: |text| schema::rename_field(text, "drag", "viscosity") }];
+  let old = "(drag: 0.5)";
+  assert_eq!(schema::migrate(old, schema::version(old).unwrap(), &rename), "(viscosity: 0.5)");
+}
+
+#[test]
+fn migrations_only_upgrade_older_files() {
+  let rename = [Migration { version: 2, apply: |text| schema::rename_field(text, "drag", "viscosity") }];
+  let old = "(version: 1, drag: 0.5, name: \"drag: kept\")";
+  assert_eq!(schema::migrate(old, 1, &rename), "(version: 1, viscosity: 0.5, name: \"drag: kept\")");
+  assert_eq!(schema::migrate(old, 2, &rename), old);
+}
+
+#[test]
+fn renames_reach_nested_fields_but_not_values() {
+  let text = "(config: Some((drag: 1.0)), kind: drag, // drag: 2.0\n)";
+  assert_eq!(schema::rename_field(text, "drag", "viscosity"), "(config: Some((viscosity: 1.0)), kind: drag, // drag: 2.0\n)");
+}
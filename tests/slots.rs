@@ -0,0 +1,57 @@
+//! Snapshots from the quick slots, the timeline and rollback put particles
+//! back with everything that sets how they behave, not just their state.
+#![cfg(feature = "render")]
+
+use bevy::{ecs::system::RunSystemOnce, prelude::*};
+use fluid_simulation::{
+  domain::DomainId,
+  layers::CollisionLayers,
+  material::FluidMaterial,
+  pin::Pinned,
+  render::ParticleColor,
+  slots::{restore_in_place, ParticleSnapshot, SnapshotQuery},
+  Particle,
+};
+
+fn spawn_particle(app: &mut App, x: f32) -> Entity {
+  app.world_mut().spawn((
+    Particle::at_rest(Vec3::new(x, 0.0, 0.0), 1.0),
+    DomainId(0),
+    ParticleColor::new(Color::WHITE),
+    Pinned,
+    CollisionLayers::new(2, CollisionLayers::WALLS | 2),
+    FluidMaterial { viscosity: 4.0, surface_tension: 30.0, ..default() },
+  )).id()
+}
+
+fn assert_restored(app: &App, entity: Entity) {
+  let particle = app.world().entity(entity);
+  assert!(particle.contains::<Pinned>());
+  assert_eq!(particle.get::<CollisionLayers>(), Some(&CollisionLayers::new(2, CollisionLayers::WALLS | 2)));
+  assert_eq!(particle.get::<FluidMaterial>().map(|material| material.viscosity), Some(4.0));
+}
+
+#[test]
+fn restoring_keeps_pins_layers_and_materials() {
+  let mut app = App::new();
+  app.init_resource::<Assets<Mesh>>().init_resource::<Assets<ColorMaterial>>();
+  let kept = spawn_particle(&mut app, 0.0);
+  let removed = spawn_particle(&mut app, 50.0);
+  let saved = app.world_mut().run_system_once(|particle_query: SnapshotQuery| ParticleSnapshot::capture_all(&particle_query)).unwrap();
+
+  app.world_mut().entity_mut(kept).remove::<(Pinned, CollisionLayers, FluidMaterial)>();
+  app.world_mut().despawn(removed);
+  app.world_mut().run_system_once(
+    move |mut commands: Commands,
+          mut particle_query: Query<(Entity, &mut Particle, &mut ParticleColor)>,
+          mut meshes: ResMut<Assets<Mesh>>,
+          mut materials: ResMut<Assets<ColorMaterial>>| {
+      restore_in_place(&mut commands, &mut particle_query, &saved, 2.0, &mut meshes, &mut materials);
+    },
+  ).unwrap();
+
+  assert_restored(&app, kept);
+  let world = app.world_mut();
+  let respawned = world.query::<(Entity, &Particle)>().iter(world).find(|(_, particle)| particle.position.x == 50.0).unwrap().0;
+  assert_restored(&app, respawned);
+}
@@ -0,0 +1,45 @@
+//! Removing particles through `DespawnParticles`.
+#![cfg(feature = "render")]
+
+use bevy::prelude::*;
+use fluid_simulation::{
+  spawn::{despawn_particles, DespawnParticles, Region},
+  Particle,
+};
+
+fn app(xs: &[f32]) -> App {
+  let mut app = App::new();
+  app.add_event::<DespawnParticles>().add_systems(Update, despawn_particles);
+  for &x in xs {
+    app.world_mut().spawn(Particle::at_rest(Vec3::new(x, 0.0, 0.0), 1.0));
+  }
+  app
+}
+
+fn remaining(app: &mut App) -> Vec<f32> {
+  let world = app.world_mut();
+  let mut xs: Vec<f32> = world.query::<&Particle>().iter(world).map(|particle| particle.position.x).collect();
+  xs.sort_by(f32::total_cmp);
+  xs
+}
+
+#[test]
+fn a_point_removes_the_nearest_particle_only() {
+  let mut app = app(&[0.0, 4.0, 100.0]);
+  app.world_mut().send_event(DespawnParticles { region: Region::Point(Vec2::new(3.1, 0.5)) });
+  app.update();
+  assert_eq!(remaining(&mut app), [0.0, 100.0]);
+
+  // nothing close enough to pick
+  app.world_mut().send_event(DespawnParticles { region: Region::Point(Vec2::new(50.0, 0.0)) });
+  app.update();
+  assert_eq!(remaining(&mut app), [0.0, 100.0]);
+}
+
+#[test]
+fn a_circle_removes_everything_inside() {
+  let mut app = app(&[0.0, 4.0, 100.0]);
+  app.world_mut().send_event(DespawnParticles { region: Region::Circle { center: Vec2::ZERO, radius: 10.0 } });
+  app.update();
+  assert_eq!(remaining(&mut app), [100.0]);
+}